@@ -0,0 +1,97 @@
+//! A fast box stops at a thin wall instead of tunneling through it.
+//! Hold the arrow keys to move the box, and watch the hit boxes drawn as debug shapes.
+use anyhow::Result;
+use wasm_bindgen::prelude::*;
+
+use retrospector::app::{run, App, AppConfig};
+use retrospector::collision::{rect_circle, swept_rect_rect, Circle, Rect};
+use retrospector::render::{clear, Renderer};
+use retrospector::update::KeyEvent;
+
+// The box moves farther than the width of the wall in a frame.
+const SPEED: f64 = 12.0;
+
+fn main() -> Result<(), JsValue> {
+    let config = AppConfig::new(String::from("canvas"), 352.0, 352.0);
+    let app = Game {
+        player: Rect::new(32.0, 160.0, 16.0, 16.0).map_err(to_js)?,
+        wall: Rect::new(240.0, 64.0, 2.0, 224.0).map_err(to_js)?,
+        coin: Circle::new(160.0, 96.0, 12.0).map_err(to_js)?,
+    };
+    run(app, config)?;
+    Ok(())
+}
+
+struct Game {
+    player: Rect,
+    wall: Rect,
+    coin: Circle,
+}
+
+impl App for Game {
+    fn update(&mut self, _elapsed_time: f64, key_event: &KeyEvent) -> Result<()> {
+        let mut velocity = (0.0, 0.0);
+        if key_event.is_arrow_right_down() {
+            velocity.0 += SPEED;
+        }
+        if key_event.is_arrow_left_down() {
+            velocity.0 -= SPEED;
+        }
+        if key_event.is_arrow_up_down() {
+            velocity.1 -= SPEED;
+        }
+        if key_event.is_arrow_down_down() {
+            velocity.1 += SPEED;
+        }
+        let time = match swept_rect_rect(&self.player, velocity, &self.wall) {
+            Some(hit) => hit.time(),
+            None => 1.0,
+        };
+        self.player = Rect::new(
+            self.player.x() + velocity.0 * time,
+            self.player.y() + velocity.1 * time,
+            self.player.width(),
+            self.player.height(),
+        )?;
+        Ok(())
+    }
+
+    fn render(&self, renderer: &Renderer) -> Result<()> {
+        clear(renderer);
+        let touching_coin = rect_circle(&self.player, &self.coin);
+        stroke_rect(renderer, &self.player, "#00a0ff");
+        stroke_rect(renderer, &self.wall, "#ffffff");
+        stroke_circle(
+            renderer,
+            &self.coin,
+            if touching_coin { "#ff4040" } else { "#ffd000" },
+        )
+    }
+}
+
+fn stroke_rect(renderer: &Renderer, rect: &Rect, color: &str) {
+    let context = renderer.context();
+    context.set_stroke_style(&JsValue::from_str(color));
+    context.stroke_rect(rect.x(), rect.y(), rect.width(), rect.height());
+}
+
+fn stroke_circle(renderer: &Renderer, circle: &Circle, color: &str) -> Result<()> {
+    let context = renderer.context();
+    context.set_stroke_style(&JsValue::from_str(color));
+    context.begin_path();
+    context
+        .arc(
+            circle.x(),
+            circle.y(),
+            circle.radius(),
+            0.0,
+            std::f64::consts::TAU,
+        )
+        .map_err(|e| anyhow::anyhow!("failed to draw a circle: {:?}", e))?;
+    context.stroke();
+    Ok(())
+}
+
+fn to_js(e: anyhow::Error) -> JsValue {
+    JsValue::from_str(&format!("{:#}", e))
+}
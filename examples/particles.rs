@@ -0,0 +1,70 @@
+//! A fountain sprays water forever, and Enter sets off an explosion in the middle.
+use std::f64::consts::{FRAC_PI_2, TAU};
+
+use anyhow::Result;
+use wasm_bindgen::prelude::*;
+
+use retrospector::app::{run, App, AppConfig};
+use retrospector::render::color::Color;
+use retrospector::render::{clear, ParticleEmitter, ParticleLook, Position, Renderer};
+use retrospector::update::KeyEvent;
+
+fn main() -> Result<(), JsValue> {
+    let config = AppConfig::new(String::from("canvas"), 352.0, 352.0);
+    let water = ParticleLook::Color {
+        color: Color::rgb(64, 160, 255),
+        size: 3.0,
+    };
+    let fire = ParticleLook::Color {
+        color: Color::rgb(255, 128, 32),
+        size: 6.0,
+    };
+    let app = Game {
+        last_time: None,
+        enter_was_down: false,
+        fountain: ParticleEmitter::new(water, Position::new(176.0, 320.0), 400)
+            .rate(200.0)
+            .lifetime(1200.0..1600.0)
+            .velocity(-FRAC_PI_2, 0.4, 0.35..0.45)
+            .gravity(0.0, 0.0006)
+            .alpha(1.0, 0.2),
+        explosion: ParticleEmitter::new(fire, Position::new(176.0, 128.0), 300)
+            .lifetime(300.0..700.0)
+            .velocity(0.0, TAU, 0.05..0.3)
+            .alpha(1.0, 0.0)
+            .scale(1.0, 0.3)
+            .additive(true),
+    };
+    run(app, config)?;
+    Ok(())
+}
+
+struct Game {
+    last_time: Option<f64>,
+    enter_was_down: bool,
+    fountain: ParticleEmitter,
+    explosion: ParticleEmitter,
+}
+
+impl App for Game {
+    fn update(&mut self, elapsed_time: f64, key_event: &KeyEvent) -> Result<()> {
+        let dt = elapsed_time - self.last_time.unwrap_or(elapsed_time);
+        self.last_time = Some(elapsed_time);
+
+        let enter_is_down = key_event.is_enter_down();
+        if enter_is_down && !self.enter_was_down {
+            self.explosion.burst(300);
+        }
+        self.enter_was_down = enter_is_down;
+
+        self.fountain.update(dt);
+        self.explosion.update(dt);
+        Ok(())
+    }
+
+    fn render(&self, renderer: &Renderer) -> Result<()> {
+        clear(renderer);
+        self.fountain.draw(renderer)?;
+        self.explosion.draw(renderer)
+    }
+}
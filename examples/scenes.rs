@@ -0,0 +1,95 @@
+//! Title -> Game -> Pause flow with SceneStack.
+//! Press Enter on the title, P to pause the game, R to resume, and Q to quit.
+use anyhow::Result;
+use wasm_bindgen::prelude::*;
+
+use retrospector::app::{run, AppConfig};
+use retrospector::render::{clear, Position, Renderer};
+use retrospector::scene::{Scene, SceneStack, Transition};
+use retrospector::update::KeyEvent;
+
+fn main() -> Result<(), JsValue> {
+    let config = AppConfig::new(String::from("canvas"), 352.0, 352.0);
+    run(SceneStack::new(Box::new(Title)), config)?;
+    Ok(())
+}
+
+struct Title;
+
+impl Scene for Title {
+    fn update(&mut self, _elapsed_time: f64, key_event: &KeyEvent) -> Result<Transition> {
+        if key_event.is_enter_down() {
+            return Ok(Transition::Switch(Box::new(Game { x: 0.0 })));
+        }
+        Ok(Transition::None)
+    }
+
+    fn render(&self, renderer: &Renderer) -> Result<()> {
+        clear(renderer);
+        fill_text(renderer, "TITLE: press Enter", Position::new(16.0, 32.0))
+    }
+}
+
+struct Game {
+    x: f64,
+}
+
+impl Scene for Game {
+    fn update(&mut self, _elapsed_time: f64, key_event: &KeyEvent) -> Result<Transition> {
+        if key_event.is_key_p_down() {
+            return Ok(Transition::Push {
+                scene: Box::new(Pause),
+                draw_previous: true,
+            });
+        }
+        if key_event.is_key_q_down() {
+            return Ok(Transition::Quit);
+        }
+        if key_event.is_arrow_right_down() {
+            self.x += 1.0;
+        }
+        if key_event.is_arrow_left_down() {
+            self.x -= 1.0;
+        }
+        Ok(Transition::None)
+    }
+
+    fn render(&self, renderer: &Renderer) -> Result<()> {
+        clear(renderer);
+        fill_text(
+            renderer,
+            "GAME: P to pause, Q to quit",
+            Position::new(16.0, 32.0),
+        )?;
+        fill_text(renderer, "@", Position::new(self.x, 64.0))
+    }
+}
+
+struct Pause;
+
+impl Scene for Pause {
+    fn update(&mut self, _elapsed_time: f64, key_event: &KeyEvent) -> Result<Transition> {
+        if key_event.is_key_r_down() {
+            return Ok(Transition::Pop);
+        }
+        Ok(Transition::None)
+    }
+
+    fn render(&self, renderer: &Renderer) -> Result<()> {
+        // The game underneath is still visible through the translucent layer.
+        let context = renderer.context();
+        context.set_fill_style(&JsValue::from_str("rgba(0, 0, 0, 0.5)"));
+        context.fill_rect(0.0, 0.0, renderer.canvas_width(), renderer.canvas_height());
+        context.set_fill_style(&JsValue::from_str("#ffffff"));
+        fill_text(renderer, "PAUSE: press R", Position::new(16.0, 96.0))?;
+        context.set_fill_style(&JsValue::from_str("#000000"));
+        Ok(())
+    }
+}
+
+fn fill_text(renderer: &Renderer, text: &str, position: Position) -> Result<()> {
+    renderer
+        .context()
+        .fill_text(text, position.dx(), position.dy())
+        .map_err(|e| anyhow::anyhow!("failed to fill text: {:?}", e))
+}
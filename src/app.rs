@@ -1,97 +1,1568 @@
 use std::cell::RefCell;
+use std::fmt;
+use std::future::Future;
 use std::rc::Rc;
 
+use anyhow::{anyhow, ensure, Result};
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::{spawn_local, JsFuture};
 
-use crate::render::Renderer;
-use crate::update::KeyEvent;
+use crate::assets::{fetch_bytes, Assets, ResourceCache};
+use crate::audio::AudioSystem;
+use crate::console::install_panic_hook;
+use crate::overlay::Overlay;
+use crate::recording::Recorder;
+use crate::render::color::Color;
+use crate::render::{clear, Position, RenderPassId, Renderer};
+use crate::timing::{FrameReport, GameClock};
+use crate::update::{is_scrolling_key, KeyEvent, MouseEvent};
+
+mod driver;
+use driver::{Driver, Scheduled};
+
+mod teardown;
+use teardown::Teardown;
+pub(crate) use teardown::{drop_later, Listener};
+
+/// profiler exposes the durations of App::update and App::render in the last frame.
+/// It is enabled by the `profiling` feature.
+#[cfg(feature = "profiling")]
+pub mod profiler;
 
 /// App trait should be implemented by all game objects.
 pub trait App {
     /// update is responsible for updating game objects.
-    fn update(&mut self, elapsed_time: f64, key_event: &KeyEvent);
+    /// An error is passed to the handler configured by AppConfigBuilder::error_policy.
+    fn update(&mut self, elapsed_time: f64, key_event: &KeyEvent) -> Result<()>;
+    /// on_mouse is called before every update with the position and the movement of the mouse
+    /// since the last update.
+    fn on_mouse(&mut self, _mouse_event: &MouseEvent) {}
     /// render is responsible for rendering images on the canvas.
-    fn render(&self, renderer: &Renderer);
+    /// An error is passed to the handler configured by AppConfigBuilder::error_policy.
+    fn render(&self, renderer: &Renderer) -> Result<()>;
+    /// render_passes returns the passes which the loop renders every frame, in this order.
+    /// The default is only RenderPassId::World.
+    fn render_passes(&self) -> &[RenderPassId] {
+        &[RenderPassId::World]
+    }
+    /// render_pass is called for each of render_passes, e.g. to draw the world with a camera
+    /// transform and the UI without it. The default calls render, so implement this to opt in.
+    /// An error skips the rest of the passes in the frame.
+    fn render_pass(&self, renderer: &Renderer, _pass: RenderPassId) -> Result<()> {
+        self.render(renderer)
+    }
+    /// clear_before_pass returns true to clear the canvas before pass, e.g. before the first
+    /// pass instead of calling clear in it. The default is false.
+    fn clear_before_pass(&self, _pass: RenderPassId) -> bool {
+        false
+    }
+    /// resource_urls declares the resources which the app needs.
+    /// run fetches all of them before the first frame and passes them to on_load.
+    fn resource_urls(&self) -> Vec<String> {
+        vec![]
+    }
+    /// on_load is called with the fetched resources before the first frame. It returns a promise
+    /// which is resolved when the app finishes loading its assets, and the loop waits for it
+    /// before starting to call update and render. You can use SpriteStore::on_load for that.
+    fn on_load(&mut self, _resources: &ResourceCache) -> js_sys::Promise {
+        js_sys::Promise::resolve(&JsValue::UNDEFINED)
+    }
+    /// assets returns the tracker of the resources which the app needs before the first frame.
+    /// While any of them is pending, the loop calls render_loading instead of update and render.
+    /// If any of them fails, the loop is stopped.
+    fn assets(&self) -> Option<Assets> {
+        None
+    }
+    /// render_loading is called every frame while resources are loading, with the progress
+    /// from 0.0 to 1.0. The loading screen of run_with_loading_screen takes precedence over it.
+    fn render_loading(&self, _renderer: &Renderer, _progress: f64) {}
+    /// on_suspend is called when the tab is hidden and the loop is suspended.
+    /// You can pause music or show a "paused" banner here.
+    fn on_suspend(&mut self) {}
+    /// on_resume is called when the tab is visible again and the loop is resumed.
+    fn on_resume(&mut self) {}
+    /// time_scale is multiplied to the passage of the time passed to update, e.g. 0.5 for slow motion.
+    /// It is combined with AppHandle::set_time_scale. The default is 1.0.
+    fn time_scale(&self) -> f64 {
+        1.0
+    }
+    /// on_error is called with the message when App::update or App::render returns an error,
+    /// or when a JavaScript exception reaches the window, e.g. to display it in the game.
+    fn on_error(&mut self, _error: &str) {}
+    /// on_frame_stats is called after every frame with the durations of update and render,
+    /// e.g. to show them in the game. It is enabled by the `profiling` feature.
+    #[cfg(feature = "profiling")]
+    fn on_frame_stats(&mut self, _stats: &profiler::FrameStats) {}
+    /// should_stop is checked after every update. When it returns true, the loop is stopped.
+    fn should_stop(&self) -> bool {
+        false
+    }
+}
+
+// render_passes renders the passes of app in order.
+pub(crate) fn render_passes<T: App + ?Sized>(app: &T, renderer: &Renderer) -> Result<()> {
+    for &pass in app.render_passes() {
+        if app.clear_before_pass(pass) {
+            clear(renderer);
+        }
+        app.render_pass(renderer, pass)?;
+    }
+    Ok(())
 }
 
 /// run is an entry point for starting the game.
-pub fn run<T: App + 'static>(app: T, config: AppConfig) -> Result<(), JsValue> {
-    let document = web_sys::window().unwrap().document().unwrap();
+/// It returns an AppHandle to control the running game from outside the loop.
+pub fn run<T: App + 'static>(app: T, config: AppConfig) -> Result<AppHandle<T>, JsValue> {
+    start(app, config, None).map(|(handle, _)| handle)
+}
+
+/// run_async is run which resolves once App::resource_urls are fetched and the promise returned
+/// by App::on_load is resolved, so that the next frame updates the app. Await it in
+/// `#[wasm_bindgen(start)] async fn` or spawn_local. It fails if loading fails.
+pub async fn run_async<T: App + 'static>(
+    app: T,
+    config: AppConfig,
+) -> Result<AppHandle<T>, JsValue> {
+    let (handle, loaded) = start(app, config, None)?;
+    let error = JsFuture::from(loaded).await?;
+    if let Some(error) = error.as_string() {
+        return Err(JsValue::from_str(&error));
+    }
+    Ok(handle)
+}
+
+/// run_with_loading_screen is an entry point for starting the game with a loading screen.
+/// It calls loading_renderer every frame with the progress of fetching App::resource_urls
+/// from 0.0 to 1.0, until the promise returned by App::on_load is resolved,
+/// and then switches to the normal game loop. If loading fails, the loop is stopped.
+pub fn run_with_loading_screen<T: App + 'static>(
+    app: T,
+    config: AppConfig,
+    loading_renderer: impl Fn(&Renderer, f64) + 'static,
+) -> Result<AppHandle<T>, JsValue> {
+    start(app, config, Some(Box::new(loading_renderer))).map(|(handle, _)| handle)
+}
+
+type LoadingRenderer = Box<dyn Fn(&Renderer, f64)>;
+
+// The time passed to a single step when no time is specified, which is a frame at 60 fps.
+const DEFAULT_STEP_MS: f64 = 1000.0 / 60.0;
+
+// start starts the loop, and returns the handle and the promise returned by load.
+fn start<T: App + 'static>(
+    app: T,
+    config: AppConfig,
+    loading_renderer: Option<LoadingRenderer>,
+) -> Result<(AppHandle<T>, js_sys::Promise), JsValue> {
+    install_panic_hook();
+    let window = web_sys::window().ok_or_else(|| JsValue::from_str("no global window exists"))?;
+    let document = window
+        .document()
+        .ok_or_else(|| JsValue::from_str("the window has no document"))?;
+    if let Some(title) = &config.title {
+        document.set_title(title);
+    }
+    let canvas = config.canvas.resolve(&document)?;
+    let pixel_ratio = config.pixel_ratio.unwrap_or(1.0);
+    canvas.set_width((config.canvas_width * pixel_ratio).round() as u32);
+    canvas.set_height((config.canvas_height * pixel_ratio).round() as u32);
+    // A canvas with more pixels than its size is shrunk back to its size by CSS.
+    let display_size = match config.display_size {
+        None if pixel_ratio != 1.0 => Some((config.canvas_width, config.canvas_height)),
+        display_size => display_size,
+    };
+    if let Some((css_width, css_height)) = display_size {
+        let style = canvas.style();
+        style.set_property("width", &format!("{}px", css_width))?;
+        style.set_property("height", &format!("{}px", css_height))?;
+    }
+    if config.pixel_art {
+        canvas
+            .style()
+            .set_property("image-rendering", "pixelated")?;
+    }
+    if let Some(background) = &config.background {
+        canvas
+            .style()
+            .set_property("background-color", &background.to_string())?;
+    }
+    let context = context_2d(&canvas)?;
+    context.set_image_smoothing_enabled(config.antialiasing && !config.pixel_art);
+    context.scale(pixel_ratio, pixel_ratio)?;
+    let mut renderer = Renderer::new(context, config.canvas_width, config.canvas_height);
+
+    let handle = AppHandle::new(app, canvas, pixel_ratio);
     let shared_key_event = Rc::new(RefCell::new(KeyEvent::new()));
     {
         let keydown_event = Rc::clone(&shared_key_event);
+        let keydown_state = Rc::clone(&handle.state);
+        let prevent_default_keys = config.prevent_default_keys;
+        let debug_keys = config.debug_keys;
         let keydown_handler = Closure::wrap(Box::new(move |event: web_sys::KeyboardEvent| {
+            if prevent_default_keys && is_scrolling_key(&event) {
+                event.prevent_default();
+            }
+            if let Some((pause_key, step_key)) = debug_keys {
+                let mut state = keydown_state.borrow_mut();
+                if event.key_code() == pause_key && !event.repeat() {
+                    state.paused = !state.paused;
+                } else if event.key_code() == step_key && state.paused {
+                    state.step = Some(DEFAULT_STEP_MS);
+                }
+            }
             keydown_event.borrow_mut().update_on_keydown(event);
         }) as Box<dyn FnMut(_)>);
-        document.add_event_listener_with_callback(
-            "keydown",
-            keydown_handler.as_ref().unchecked_ref(),
-        )?;
-        keydown_handler.forget();
+        let listener = Listener::add(&document, "keydown", keydown_handler)?;
+        handle.teardown.borrow_mut().add_listener(listener);
     }
     {
         let keyup_event = Rc::clone(&shared_key_event);
         let keyup_handler = Closure::wrap(Box::new(move |event: web_sys::KeyboardEvent| {
             keyup_event.borrow_mut().update_on_keyup(event);
         }) as Box<dyn FnMut(_)>);
-        document
-            .add_event_listener_with_callback("keyup", keyup_handler.as_ref().unchecked_ref())?;
-        keyup_handler.forget();
-    }
-
-    let canvas = document
-        .get_element_by_id(&config.canvas_id)
-        .unwrap()
-        .dyn_into::<web_sys::HtmlCanvasElement>()?;
-    canvas.set_width(config.canvas_width as u32);
-    canvas.set_height(config.canvas_height as u32);
-    let context = canvas
-        .get_context("2d")?
-        .unwrap()
-        .dyn_into::<web_sys::CanvasRenderingContext2d>()?;
-    let renderer = Renderer::new(context, config.canvas_width, config.canvas_height);
-
-    let shared_app = Rc::new(RefCell::new(app));
-    let f = Rc::new(RefCell::new(None));
-    let g = Rc::clone(&f);
+        let listener = Listener::add(&document, "keyup", keyup_handler)?;
+        handle.teardown.borrow_mut().add_listener(listener);
+    }
+    let shared_mouse_event = Rc::new(RefCell::new(MouseEvent::new()));
+    {
+        let mousemove_event = Rc::clone(&shared_mouse_event);
+        let mousemove_canvas = handle.canvas.clone();
+        let mousemove_handler = Closure::wrap(Box::new(move |event: web_sys::MouseEvent| {
+            let position = canvas_position(
+                &mousemove_canvas,
+                pixel_ratio,
+                event.client_x() as f64,
+                event.client_y() as f64,
+            );
+            mousemove_event.borrow_mut().update_on_mousemove(position);
+        }) as Box<dyn FnMut(_)>);
+        let listener = Listener::add(&handle.canvas, "mousemove", mousemove_handler)?;
+        handle.teardown.borrow_mut().add_listener(listener);
+    }
     {
-        let app_cloned = Rc::clone(&shared_app);
-        g.replace(Some(Closure::wrap(Box::new(move |time: f64| {
-            app_cloned
-                .borrow_mut()
-                .update(time, &shared_key_event.borrow());
-            app_cloned.borrow().render(&renderer);
-            request_animation_frame(f.borrow().as_ref().unwrap());
-        }) as Box<dyn FnMut(f64)>)));
-        request_animation_frame(g.borrow().as_ref().unwrap());
+        let wheel_event = Rc::clone(&shared_mouse_event);
+        let wheel_handler = Closure::wrap(Box::new(move |event: web_sys::WheelEvent| {
+            wheel_event.borrow_mut().update_on_wheel(&event);
+        }) as Box<dyn FnMut(_)>);
+        let listener = Listener::add(&handle.canvas, "wheel", wheel_handler)?;
+        handle.teardown.borrow_mut().add_listener(listener);
+    }
+
+    if let Some(overlay) = &config.overlay {
+        let game_size = (config.canvas_width, config.canvas_height);
+        attach_overlay(&document, &handle, overlay, game_size)?;
+    }
+
+    if let Some(audio) = &config.audio {
+        handle.teardown.borrow_mut().set_audio(audio.clone());
+    }
+
+    forward_window_errors(&window, &handle)?;
+    let loaded = load(&handle)?;
+    if config.pause_when_hidden {
+        suspend_when_hidden(&document, &handle, config.audio.clone())?;
     }
+    let mut frame_limiter = FrameLimiter::new(config.max_fps, config.render_every);
+    let mut game_clock = GameClock::new();
+    let error_policy = config.error_policy;
+    let slow_frame = config.slow_frame.clone();
+    let audio = config.audio.clone();
+    let overlay = config.overlay.clone();
+    let recorder = config.recorder.clone();
+    // Two clock reads around each phase are cheap enough to profile every frame.
+    let performance = window
+        .performance()
+        .ok_or_else(|| JsValue::from_str("the window has no performance"))?;
+    {
+        let app_cloned = Rc::clone(&handle.app);
+        let state_cloned = Rc::clone(&handle.state);
+        let tick_document = document.clone();
+        let tick_canvas = handle.canvas.clone();
+        let tick = move |time: f64| {
+            let state = *state_cloned.borrow();
+            if state.stopped {
+                return false;
+            }
+            if let Some(audio) = &audio {
+                audio.set_suspended_by_loop(state.paused || state.suspended);
+            }
+            if let Some(overlay) = &overlay {
+                overlay.sync();
+            }
+            if state.loading {
+                render_loading(&app_cloned, &renderer, &loading_renderer, state.progress);
+                return true;
+            }
+            let assets = app_cloned.try_borrow().ok().and_then(|app| app.assets());
+            if let Some(assets) = assets {
+                if let Some(error) = assets.error() {
+                    let message = format!("failed to load the assets: {}", error);
+                    web_sys::console::error_1(&JsValue::from_str(&message));
+                    if let Ok(mut app) = app_cloned.try_borrow_mut() {
+                        app.on_error(&message);
+                    }
+                    state_cloned.borrow_mut().stopped = true;
+                    return false;
+                }
+                if !assets.is_ready() {
+                    render_loading(&app_cloned, &renderer, &loading_renderer, assets.progress());
+                    return true;
+                }
+            }
+            if state.suspended {
+                return true;
+            }
+            if state.reset_baseline {
+                // Do not deliver a giant delta after the tab was hidden.
+                frame_limiter.reset();
+                game_clock.reset();
+                renderer.frame_timing_mut().reset();
+                state_cloned.borrow_mut().reset_baseline = false;
+            }
+            if !frame_limiter.should_run(time) {
+                return true;
+            }
+            renderer.frame_timing_mut().record(time);
+            // The game clock does not advance while paused, so that resuming does not jump.
+            let time_scale = if state.paused {
+                0.0
+            } else {
+                // The app borrowed outside of the loop fails the update below, so 1.0 is enough.
+                let app_scale = app_cloned.try_borrow().map_or(1.0, |app| app.time_scale());
+                state.time_scale * app_scale
+            };
+            let mut game_time = game_clock.tick(time, time_scale);
+            let step = if state.paused {
+                state_cloned.borrow_mut().step.take()
+            } else {
+                None
+            };
+            if let Some(step) = step {
+                game_time = game_clock.advance(step);
+            }
+            let mut update_ms = None;
+            let mut render_ms = None;
+            if !state.paused || step.is_some() {
+                let started = performance.now();
+                // The callbacks may use the AppHandle, so they are called before the borrow.
+                if let Some(overlay) = &overlay {
+                    overlay.dispatch_clicks();
+                }
+                let result = match app_cloned.try_borrow_mut() {
+                    Ok(mut app) => {
+                        app.on_mouse(&shared_mouse_event.borrow());
+                        app.update(game_time, &shared_key_event.borrow())
+                    }
+                    Err(_) => Err(anyhow!("the app is already borrowed outside of the loop")),
+                };
+                // The movement of the mouse is measured between updates.
+                shared_mouse_event.borrow_mut().end_frame();
+                let duration_ms = performance.now() - started;
+                renderer.frame_timing_mut().record_update(duration_ms);
+                update_ms = Some(duration_ms);
+                if let Err(e) = result {
+                    handle_error(&app_cloned, &state_cloned, error_policy, "update", e);
+                }
+                let frame = {
+                    let mut state = state_cloned.borrow_mut();
+                    state.frame += 1;
+                    state.frame
+                };
+                renderer.frame_timing_mut().set_frame(frame);
+                if app_cloned.try_borrow().is_ok_and(|app| app.should_stop()) {
+                    state_cloned.borrow_mut().stopped = true;
+                    return false;
+                }
+            }
+            // Nothing is shown in a hidden tab, so the timer driver only keeps updating.
+            if (frame_limiter.should_render() || step.is_some()) && !tick_document.hidden() {
+                let started = performance.now();
+                let result = match app_cloned.try_borrow() {
+                    Ok(app) => render_passes(&*app, &renderer),
+                    Err(_) => Err(anyhow!("the app is already borrowed outside of the loop")),
+                };
+                let duration_ms = performance.now() - started;
+                renderer.frame_timing_mut().record_render(duration_ms);
+                render_ms = Some(duration_ms);
+                if let Err(e) = result {
+                    handle_error(&app_cloned, &state_cloned, error_policy, "render", e);
+                }
+                if state.debug_overlay {
+                    render_debug_overlay(&renderer, game_time, &state);
+                }
+                if let Some(recorder) = &recorder {
+                    if let Err(e) = recorder.capture(&tick_canvas, time) {
+                        let message = format!("failed to record the frame: {:?}", e);
+                        web_sys::console::error_1(&JsValue::from_str(&message));
+                        recorder.set_enabled(false);
+                    }
+                }
+            }
+            let report = FrameReport::new(
+                renderer.frame_timing().frame(),
+                update_ms.unwrap_or(0.0),
+                render_ms.unwrap_or(0.0),
+            );
+            #[cfg(feature = "profiling")]
+            {
+                profiler::record(report);
+                if let Ok(mut app) = app_cloned.try_borrow_mut() {
+                    app.on_frame_stats(&report);
+                }
+            }
+            if let Some(slow_frame) = &slow_frame {
+                if report.total_ms() > slow_frame.threshold_ms {
+                    (slow_frame.callback)(&report);
+                }
+            }
+            true
+        };
+        let driver = Driver::start(
+            config.loop_driver,
+            window,
+            document,
+            Rc::clone(&handle.state),
+            tick,
+        )?;
+        handle.teardown.borrow_mut().set_driver(driver);
+    }
+
+    Ok((handle, loaded))
+}
+
+/// is_supported returns true when the browser supports what run needs, i.e. a canvas with a 2d
+/// context and requestAnimationFrame. Pages can check it to show a fallback message instead.
+pub fn is_supported() -> bool {
+    let window = match web_sys::window() {
+        Some(window) => window,
+        None => return false,
+    };
+    let has_animation_frame =
+        js_sys::Reflect::has(&window, &JsValue::from_str("requestAnimationFrame")).unwrap_or(false);
+    let canvas = window
+        .document()
+        .and_then(|document| document.create_element("canvas").ok())
+        .and_then(|element| element.dyn_into::<web_sys::HtmlCanvasElement>().ok());
+    match canvas {
+        Some(canvas) => has_animation_frame && context_2d(&canvas).is_ok(),
+        None => false,
+    }
+}
 
+fn context_2d(
+    canvas: &web_sys::HtmlCanvasElement,
+) -> Result<web_sys::CanvasRenderingContext2d, JsValue> {
+    let name = if canvas.id().is_empty() {
+        String::from("the canvas")
+    } else {
+        format!("canvas '{}'", canvas.id())
+    };
+    canvas
+        .get_context("2d")
+        .map_err(|e| JsValue::from_str(&format!("{} cannot provide a 2d context: {:?}", name, e)))?
+        .ok_or_else(|| {
+            JsValue::from_str(&format!(
+                "{} cannot provide a 2d context; it may already have a webgl context",
+                name
+            ))
+        })?
+        .dyn_into::<web_sys::CanvasRenderingContext2d>()
+        .map_err(|_| JsValue::from_str(&format!("the context of {} is not a 2d context", name)))
+}
+
+// load fetches the resources and calls App::on_load in the background. The returned promise is
+// resolved with undefined when loading finishes, or with the message when it fails, which is
+// not a rejection so that the loops which do not await it do not report it twice.
+fn load<T: App + 'static>(handle: &AppHandle<T>) -> Result<js_sys::Promise, JsValue> {
+    let urls = handle
+        .app
+        .try_borrow()
+        .map_err(|_| JsValue::from_str("the app is already borrowed"))?
+        .resource_urls();
+    handle.state.borrow_mut().loading = true;
+    let handle = handle.clone();
+    let mut resolve_loaded = None;
+    let loaded = js_sys::Promise::new(&mut |resolve, _| resolve_loaded = Some(resolve));
+    spawn_local(async move {
+        let result = match load_resources(&handle, &urls).await {
+            Ok(()) => {
+                handle.state.borrow_mut().loading = false;
+                JsValue::UNDEFINED
+            }
+            Err(e) => {
+                let message = format!("failed to load the app: {:?}", e);
+                web_sys::console::error_1(&JsValue::from_str(&message));
+                handle.state.borrow_mut().stopped = true;
+                JsValue::from_str(&message)
+            }
+        };
+        if let Some(resolve) = resolve_loaded {
+            let _ = resolve.call1(&JsValue::UNDEFINED, &result);
+        }
+    });
+
+    Ok(loaded)
+}
+
+async fn load_resources<T: App>(handle: &AppHandle<T>, urls: &[String]) -> Result<()> {
+    let mut resources = ResourceCache::new();
+    for (i, url) in urls.iter().enumerate() {
+        resources.insert(url.clone(), fetch_bytes(url).await?);
+        handle.state.borrow_mut().progress = (i + 1) as f64 / urls.len() as f64;
+    }
+    handle.state.borrow_mut().progress = 1.0;
+    let promise = handle.with_app(|app| app.on_load(&resources))?;
+    JsFuture::from(promise)
+        .await
+        .map_err(|e| anyhow!("the promise returned by App::on_load is rejected: {:?}", e))?;
     Ok(())
 }
 
-fn request_animation_frame(f: &Closure<dyn FnMut(f64)>) {
-    web_sys::window()
-        .unwrap()
-        .request_animation_frame(f.as_ref().unchecked_ref())
-        .unwrap();
+// canvas_position translates a position in CSS pixels of the viewport to pixels of the canvas.
+fn canvas_position(
+    canvas: &web_sys::HtmlCanvasElement,
+    pixel_ratio: f64,
+    client_x: f64,
+    client_y: f64,
+) -> Position {
+    let rect = canvas.get_bounding_client_rect();
+    let scale_x = if rect.width() > 0.0 {
+        canvas.width() as f64 / pixel_ratio / rect.width()
+    } else {
+        1.0
+    };
+    let scale_y = if rect.height() > 0.0 {
+        canvas.height() as f64 / pixel_ratio / rect.height()
+    } else {
+        1.0
+    };
+    Position::new(
+        (client_x - rect.left()) * scale_x,
+        (client_y - rect.top()) * scale_y,
+    )
 }
 
-/// AppConfig is a configuration for starting the game.
+fn attach_overlay<T: App>(
+    document: &web_sys::Document,
+    handle: &AppHandle<T>,
+    overlay: &Overlay,
+    game_size: (f64, f64),
+) -> Result<(), JsValue> {
+    let element = overlay.attach(document, &handle.canvas, game_size)?;
+    let click_overlay = overlay.clone();
+    let click_handler = Closure::wrap(Box::new(move |event: web_sys::Event| {
+        click_overlay.handle_click(&event);
+    }) as Box<dyn FnMut(_)>);
+    let listener = Listener::add(&element, "click", click_handler)?;
+    let mut teardown = handle.teardown.borrow_mut();
+    teardown.add_listener(listener);
+    teardown.set_overlay(overlay.clone());
+
+    Ok(())
+}
+
+fn forward_window_errors<T: App + 'static>(
+    window: &web_sys::Window,
+    handle: &AppHandle<T>,
+) -> Result<(), JsValue> {
+    let app = Rc::clone(&handle.app);
+    let error_handler = Closure::wrap(Box::new(move |event: web_sys::Event| {
+        // Errors of loading resources, e.g. images, are not ErrorEvent.
+        if let Some(event) = event.dyn_ref::<web_sys::ErrorEvent>() {
+            if let Ok(mut app) = app.try_borrow_mut() {
+                app.on_error(&event.message());
+            }
+        }
+    }) as Box<dyn FnMut(_)>);
+    let listener = Listener::add(window, "error", error_handler)?;
+    handle.teardown.borrow_mut().add_listener(listener);
+
+    Ok(())
+}
+
+fn suspend_when_hidden<T: App + 'static>(
+    document: &web_sys::Document,
+    handle: &AppHandle<T>,
+    audio: Option<AudioSystem>,
+) -> Result<(), JsValue> {
+    let teardown = Rc::clone(&handle.teardown);
+    let handle = handle.clone();
+    let visibility_document = document.clone();
+    let visibility_handler = Closure::wrap(Box::new(move || {
+        let hidden = visibility_document.hidden();
+        {
+            let mut state = handle.state.borrow_mut();
+            if state.suspended == hidden {
+                return;
+            }
+            state.suspended = hidden;
+            state.reset_baseline = !hidden;
+            // The loop may not be called while hidden, so audio is suspended here.
+            if let Some(audio) = &audio {
+                audio.set_suspended_by_loop(hidden || state.paused);
+            }
+        }
+        if let Ok(mut app) = handle.app.try_borrow_mut() {
+            if hidden {
+                app.on_suspend();
+            } else {
+                app.on_resume();
+            }
+        }
+    }) as Box<dyn FnMut()>);
+    let listener = Listener::add(document, "visibilitychange", visibility_handler)?;
+    teardown.borrow_mut().add_listener(listener);
+
+    Ok(())
+}
+
+/// run_with_init is an entry point for starting the game which needs asynchronous setup.
+/// It awaits init to build the app, e.g. loading atlases and level data, and then starts the loop.
+/// The canvas is left blank while loading. You can await it in `#[wasm_bindgen(start)] async fn`.
+pub async fn run_with_init<T, F>(config: AppConfig, init: F) -> Result<AppHandle<T>, JsValue>
+where
+    T: App + 'static,
+    F: Future<Output = Result<T>>,
+{
+    let app = init
+        .await
+        .map_err(|e| JsValue::from_str(&format!("failed to initialize the app: {:?}", e)))?;
+    run(app, config)
+}
+
+fn render_loading<T: App>(
+    app: &RefCell<T>,
+    renderer: &Renderer,
+    loading_renderer: &Option<LoadingRenderer>,
+    progress: f64,
+) {
+    match loading_renderer {
+        Some(loading_renderer) => loading_renderer(renderer, progress),
+        None => {
+            if let Ok(app) = app.try_borrow() {
+                app.render_loading(renderer, progress);
+            }
+        }
+    }
+}
+
+fn handle_error<T: App>(
+    app: &RefCell<T>,
+    state: &RefCell<LoopState>,
+    policy: ErrorPolicy,
+    phase: &str,
+    error: anyhow::Error,
+) {
+    web_sys::console::error_1(&JsValue::from_str(&format!(
+        "App::{} failed: {:?}",
+        phase, error
+    )));
+    if let Ok(mut app) = app.try_borrow_mut() {
+        app.on_error(&format!("App::{} failed: {:#}", phase, error));
+    }
+    match policy {
+        ErrorPolicy::Continue => {}
+        ErrorPolicy::Pause => state.borrow_mut().paused = true,
+        ErrorPolicy::Stop => state.borrow_mut().stopped = true,
+    }
+}
+
+fn render_debug_overlay(renderer: &Renderer, time: f64, state: &LoopState) {
+    let timing = renderer.frame_timing();
+    let mut lines = vec![
+        format!("{:.0} ms (x{})", time, state.time_scale),
+        format!("frame {}", timing.frame()),
+        format!("{:.1} fps", timing.fps()),
+        format!(
+            "frame {:.1} ms (p95 {:.1} ms)",
+            timing.last_frame_ms(),
+            timing.p95_frame_ms()
+        ),
+        format!(
+            "update {:.1} ms (max {:.1} ms)",
+            timing.update_stats().average_ms(),
+            timing.update_stats().max_ms()
+        ),
+        format!(
+            "render {:.1} ms (max {:.1} ms)",
+            timing.render_stats().average_ms(),
+            timing.render_stats().max_ms()
+        ),
+    ];
+    if state.paused {
+        lines.push(String::from("paused"));
+    }
+    let context = renderer.context();
+    context.save();
+    context.set_fill_style(&(&Color::WHITE).into());
+    for (i, line) in lines.iter().enumerate() {
+        let position = Position::new(4.0, 12.0 * (i + 1) as f64);
+        let _ = context.fill_text(line, position.dx(), position.dy());
+    }
+    context.restore();
+}
+
+/// FrameLimiter decides which animation frames run update and render.
 #[derive(Debug)]
+pub(crate) struct FrameLimiter {
+    interval: Option<f64>,
+    last_time: Option<f64>,
+    render_every: u32,
+    frame_count: u64,
+}
+
+impl FrameLimiter {
+    // requestAnimationFrame timestamps jitter, so a frame slightly earlier than the interval runs.
+    const TOLERANCE: f64 = 1.0;
+
+    pub(crate) fn new(max_fps: Option<f64>, render_every: u32) -> Self {
+        Self {
+            interval: max_fps.filter(|fps| *fps > 0.0).map(|fps| 1000.0 / fps),
+            last_time: None,
+            render_every: render_every.max(1),
+            frame_count: 0,
+        }
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.last_time = None;
+    }
+
+    /// should_run returns true when the interval has elapsed since the last executed frame.
+    /// The remainder is carried over so that the average rate converges on max_fps.
+    pub(crate) fn should_run(&mut self, time: f64) -> bool {
+        let interval = match self.interval {
+            Some(interval) => interval,
+            None => return true,
+        };
+        let last_time = match self.last_time {
+            Some(last_time) => last_time,
+            None => {
+                self.last_time = Some(time);
+                return true;
+            }
+        };
+        if time - last_time + Self::TOLERANCE < interval {
+            return false;
+        }
+        let next_time = last_time + interval;
+        // Do not try to catch up after a long stall, e.g. when the tab was hidden.
+        self.last_time = if time - next_time >= interval {
+            Some(time)
+        } else {
+            Some(next_time)
+        };
+        true
+    }
+
+    /// should_render returns true on every render_every-th executed frame.
+    pub(crate) fn should_render(&mut self) -> bool {
+        let should_render = self.frame_count % self.render_every as u64 == 0;
+        self.frame_count += 1;
+        should_render
+    }
+}
+
+/// AppHandle enables you to control the running game from outside the loop.
+/// It is cheap to clone, so you can store it in functions exported by wasm-bindgen.
+#[derive(Debug)]
+pub struct AppHandle<T: App> {
+    app: Rc<RefCell<T>>,
+    state: Rc<RefCell<LoopState>>,
+    teardown: Rc<RefCell<Teardown>>,
+    canvas: web_sys::HtmlCanvasElement,
+    pixel_ratio: f64,
+}
+
+impl<T: App> AppHandle<T> {
+    fn new(app: T, canvas: web_sys::HtmlCanvasElement, pixel_ratio: f64) -> Self {
+        Self {
+            canvas,
+            pixel_ratio,
+            app: Rc::new(RefCell::new(app)),
+            state: Rc::new(RefCell::new(LoopState::default())),
+            teardown: Rc::new(RefCell::new(Teardown::default())),
+        }
+    }
+
+    /// with_app gives you mutable access to the running app.
+    /// It returns an error instead of panicking when the app is already borrowed,
+    /// e.g. when it is called during update or render.
+    pub fn with_app<R>(&self, f: impl FnOnce(&mut T) -> R) -> Result<R> {
+        let mut app = self
+            .app
+            .try_borrow_mut()
+            .map_err(|_| anyhow!("the app is already borrowed by the running loop"))?;
+        Ok(f(&mut app))
+    }
+
+    /// replace_app swaps the running app for new_app and returns the old one, e.g. to hot-swap
+    /// the game during development. The loop, its listeners and its state are kept.
+    /// It returns an error when the app is already borrowed, e.g. during update or render.
+    pub fn replace_app(&self, new_app: T) -> Result<T> {
+        let mut app = self
+            .app
+            .try_borrow_mut()
+            .map_err(|_| anyhow!("the app is already borrowed by the running loop"))?;
+        Ok(std::mem::replace(&mut *app, new_app))
+    }
+
+    /// canvas_position translates a position in CSS pixels of the viewport, e.g. clientX and
+    /// clientY of a mouse event, to a position in pixels of the canvas. It divides by the scale
+    /// of AppConfigBuilder::display_size, so a click maps to the internal resolution.
+    /// The position does not depend on AppConfigBuilder::pixel_ratio.
+    pub fn canvas_position(&self, client_x: f64, client_y: f64) -> Position {
+        canvas_position(&self.canvas, self.pixel_ratio, client_x, client_y)
+    }
+
+    /// pause stops calling update while keeping render running.
+    pub fn pause(&self) {
+        self.state.borrow_mut().paused = true;
+    }
+
+    /// resume restarts calling update after pause.
+    pub fn resume(&self) {
+        self.state.borrow_mut().paused = false;
+    }
+
+    /// is_paused returns true when the loop is paused.
+    pub fn is_paused(&self) -> bool {
+        self.state.borrow().paused
+    }
+
+    /// stop terminates the loop and tears it down: it cancels the scheduled animation frame or
+    /// timer, removes the event listeners and frees the app once the handles are dropped.
+    /// A stopped loop cannot be resumed, but you can call run again on the same canvas without
+    /// duplicate listeners or loops. The loop stopped by itself, e.g. by App::should_stop, keeps
+    /// its listeners until stop is called.
+    ///
+    /// To reload a rebuilt wasm module, export a function which stops the old loop and call it
+    /// from JavaScript before instantiating the new module:
+    /// ```no_run
+    /// use std::cell::RefCell;
+    ///
+    /// use anyhow::Result;
+    /// use wasm_bindgen::prelude::*;
+    ///
+    /// use retrospector::app::{run, App, AppConfig, AppHandle};
+    /// use retrospector::render::Renderer;
+    /// use retrospector::update::KeyEvent;
+    ///
+    /// struct Game;
+    ///
+    /// impl App for Game {
+    ///     fn update(&mut self, _elapsed_time: f64, _key_event: &KeyEvent) -> Result<()> {
+    ///         Ok(())
+    ///     }
+    ///
+    ///     fn render(&self, _renderer: &Renderer) -> Result<()> {
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// thread_local! {
+    ///     static HANDLE: RefCell<Option<AppHandle<Game>>> = RefCell::new(None);
+    /// }
+    ///
+    /// #[wasm_bindgen(start)]
+    /// pub fn start() -> Result<(), JsValue> {
+    ///     let config = AppConfig::new(String::from("canvas"), 352.0, 352.0);
+    ///     let handle = run(Game, config)?;
+    ///     HANDLE.with(|cell| cell.replace(Some(handle)));
+    ///     Ok(())
+    /// }
+    ///
+    /// #[wasm_bindgen]
+    /// pub fn shutdown() {
+    ///     if let Some(handle) = HANDLE.with(|cell| cell.take()) {
+    ///         handle.stop();
+    ///     }
+    /// }
+    /// ```
+    pub fn stop(&self) {
+        let scheduled = {
+            let mut state = self.state.borrow_mut();
+            state.stopped = true;
+            state.scheduled.take()
+        };
+        let window = match web_sys::window() {
+            Some(window) => window,
+            None => return,
+        };
+        if let Some(scheduled) = scheduled {
+            scheduled.cancel(&window);
+        }
+        // The cursor set by Renderer::set_cursor stays on the canvas otherwise.
+        let _ = self.canvas.style().remove_property("cursor");
+        self.teardown.borrow_mut().release(&window);
+    }
+
+    /// is_stopped returns true when the loop is stopped.
+    pub fn is_stopped(&self) -> bool {
+        self.state.borrow().stopped
+    }
+
+    /// frame returns the number of updates so far. It does not advance on skipped or paused frames.
+    pub fn frame(&self) -> u64 {
+        self.state.borrow().frame
+    }
+
+    /// is_loading returns true until App::resource_urls are fetched and the promise returned by
+    /// App::on_load is resolved. The loop renders the loading screen meanwhile.
+    pub fn is_loading(&self) -> bool {
+        self.state.borrow().loading
+    }
+
+    /// tick runs the loop for a frame at time, a timestamp in milliseconds like the one passed to
+    /// requestAnimationFrame, when the loop is driven by LoopDriver::Manual. It does nothing
+    /// after stop. It returns an error with another LoopDriver, or when it is called in the loop
+    /// or while the app is borrowed, e.g. in with_app.
+    pub fn tick(&self, time: f64) -> Result<()> {
+        ensure!(
+            self.app.try_borrow_mut().is_ok(),
+            "AppHandle::tick cannot be called while the app is borrowed"
+        );
+        let driver = self.teardown.borrow().driver();
+        match driver {
+            Some(driver) => driver.tick(time),
+            None => Ok(()),
+        }
+    }
+
+    /// step runs exactly one update followed by one render while paused.
+    /// The time passed to update advances by a frame at 60 fps.
+    pub fn step(&self) {
+        self.step_by(DEFAULT_STEP_MS);
+    }
+
+    /// step_by runs exactly one update, with the time advanced by step_ms, followed by one render
+    /// while paused. It does nothing while running.
+    pub fn step_by(&self, step_ms: f64) {
+        let mut state = self.state.borrow_mut();
+        if state.paused {
+            state.step = Some(step_ms.max(0.0));
+        }
+    }
+
+    /// set_time_scale scales the passage of the time passed to App::update.
+    /// 0.5 is slow motion, 2.0 is fast-forward, and 0.0 freezes the logic while still rendering.
+    /// Negative or non-finite values are treated as 0.0. The unscaled frame time is still
+    /// available from Renderer::frame_timing. A timing::FixedTimestep fed with the time passed
+    /// to App::update runs fewer steps in slow motion and none at 0.0.
+    pub fn set_time_scale(&self, time_scale: f64) {
+        self.state.borrow_mut().time_scale = if time_scale.is_finite() {
+            time_scale.max(0.0)
+        } else {
+            0.0
+        };
+    }
+
+    /// time_scale returns the current time scale set by set_time_scale.
+    pub fn time_scale(&self) -> f64 {
+        self.state.borrow().time_scale
+    }
+
+    /// set_debug_overlay shows(hides) the debug overlay on top of the canvas.
+    pub fn set_debug_overlay(&self, enabled: bool) {
+        self.state.borrow_mut().debug_overlay = enabled;
+    }
+
+    /// toggle_debug_overlay switches the visibility of the debug overlay.
+    pub fn toggle_debug_overlay(&self) {
+        let mut state = self.state.borrow_mut();
+        state.debug_overlay = !state.debug_overlay;
+    }
+
+    /// is_debug_overlay_enabled returns true when the debug overlay is shown.
+    pub fn is_debug_overlay_enabled(&self) -> bool {
+        self.state.borrow().debug_overlay
+    }
+}
+
+impl<T: App> Clone for AppHandle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            app: Rc::clone(&self.app),
+            state: Rc::clone(&self.state),
+            teardown: Rc::clone(&self.teardown),
+            canvas: self.canvas.clone(),
+            pixel_ratio: self.pixel_ratio,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LoopState {
+    paused: bool,
+    stopped: bool,
+    loading: bool,
+    progress: f64,
+    suspended: bool,
+    reset_baseline: bool,
+    debug_overlay: bool,
+    time_scale: f64,
+    frame: u64,
+    step: Option<f64>,
+    scheduled: Option<Scheduled>,
+}
+
+impl Default for LoopState {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            stopped: false,
+            loading: false,
+            progress: 0.0,
+            suspended: false,
+            reset_baseline: false,
+            debug_overlay: false,
+            time_scale: 1.0,
+            frame: 0,
+            step: None,
+            scheduled: None,
+        }
+    }
+}
+
+/// ErrorPolicy decides what the loop does after App::update or App::render returns an error.
+/// The error is logged to the console in any case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ErrorPolicy {
+    /// Continue keeps the loop running. It is the default.
+    #[default]
+    Continue,
+    /// Pause pauses the loop as AppHandle::pause does.
+    Pause,
+    /// Stop stops the loop as AppHandle::stop does.
+    Stop,
+}
+
+/// LoopDriver decides what calls the loop.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LoopDriver {
+    /// AnimationFrame calls the loop with requestAnimationFrame. It is the default.
+    /// Browsers stop calling it in hidden tabs.
+    #[default]
+    AnimationFrame,
+    /// Timer calls the loop with setTimeout every interval_ms milliseconds, even in hidden tabs.
+    /// Rendering is skipped while the tab is hidden. Browsers may throttle timers in hidden tabs.
+    Timer {
+        /// interval_ms is the time between calls in milliseconds.
+        interval_ms: f64,
+    },
+    /// TimerWhenHidden calls the loop with requestAnimationFrame while the tab is visible, and
+    /// switches to setTimeout every interval_ms milliseconds while the tab is hidden.
+    TimerWhenHidden {
+        /// interval_ms is the time between calls in milliseconds while the tab is hidden.
+        interval_ms: f64,
+    },
+    /// Manual never calls the loop by itself. Call AppHandle::tick to run each frame, e.g. to
+    /// advance browser tests deterministically.
+    Manual,
+}
+
+/// AppConfig is a configuration for starting the game.
+/// With the `serde` feature, it is serializable without the canvas, the AudioSystem, the Overlay,
+/// the Recorder and the on_slow_frame callback. A deserialized AppConfig uses the canvas with id
+/// "canvas", and it is validated like AppConfigBuilder::build.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "AppConfigData")
+)]
 pub struct AppConfig {
-    canvas_id: String,
+    #[cfg_attr(feature = "serde", serde(skip, default = "CanvasSource::default_id"))]
+    canvas: CanvasSource,
     canvas_width: f64,
     canvas_height: f64,
+    prevent_default_keys: bool,
+    max_fps: Option<f64>,
+    render_every: u32,
+    pause_when_hidden: bool,
+    pixel_art: bool,
+    antialiasing: bool,
+    background: Option<Color>,
+    debug_keys: Option<(u32, u32)>,
+    title: Option<String>,
+    error_policy: ErrorPolicy,
+    loop_driver: LoopDriver,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    slow_frame: Option<SlowFrame>,
+    display_size: Option<(f64, f64)>,
+    pixel_ratio: Option<f64>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    audio: Option<AudioSystem>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    overlay: Option<Overlay>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    recorder: Option<Recorder>,
 }
 
 impl AppConfig {
-    /// new returns an instantiated AppConfig.
+    /// new returns an instantiated AppConfig with the default settings.
+    /// Use AppConfig::builder to change the optional settings.
     pub fn new(canvas_id: String, canvas_width: f64, canvas_height: f64) -> Self {
+        AppConfigBuilder::new(CanvasSource::Id(canvas_id))
+            .size(canvas_width, canvas_height)
+            .config
+    }
+
+    /// builder returns an AppConfigBuilder for the canvas with canvas_id.
+    pub fn builder(canvas_id: &str) -> AppConfigBuilder {
+        AppConfigBuilder::new(CanvasSource::Id(String::from(canvas_id)))
+    }
+
+    /// with_canvas returns an AppConfigBuilder for the given canvas element.
+    /// It is useful for shadow DOM, web components, and canvases built programmatically.
+    pub fn with_canvas(element: web_sys::HtmlCanvasElement) -> AppConfigBuilder {
+        AppConfigBuilder::new(CanvasSource::Element(element))
+    }
+
+    /// create_in returns an AppConfigBuilder which makes run create a canvas element
+    /// and append it to the element matching parent_selector, e.g. "#game" or "body".
+    pub fn create_in(parent_selector: &str) -> AppConfigBuilder {
+        AppConfigBuilder::new(CanvasSource::CreateIn(String::from(parent_selector)))
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct AppConfigData {
+    canvas_width: f64,
+    canvas_height: f64,
+    prevent_default_keys: bool,
+    max_fps: Option<f64>,
+    render_every: u32,
+    pause_when_hidden: bool,
+    pixel_art: bool,
+    antialiasing: bool,
+    background: Option<Color>,
+    debug_keys: Option<(u32, u32)>,
+    title: Option<String>,
+    error_policy: ErrorPolicy,
+    loop_driver: LoopDriver,
+    display_size: Option<(f64, f64)>,
+    pixel_ratio: Option<f64>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<AppConfigData> for AppConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(data: AppConfigData) -> Result<Self> {
+        let mut builder = AppConfigBuilder::new(CanvasSource::default_id());
+        builder.config = AppConfig {
+            canvas_width: data.canvas_width,
+            canvas_height: data.canvas_height,
+            prevent_default_keys: data.prevent_default_keys,
+            max_fps: data.max_fps,
+            render_every: data.render_every,
+            pause_when_hidden: data.pause_when_hidden,
+            pixel_art: data.pixel_art,
+            antialiasing: data.antialiasing,
+            background: data.background,
+            debug_keys: data.debug_keys,
+            title: data.title,
+            error_policy: data.error_policy,
+            loop_driver: data.loop_driver,
+            display_size: data.display_size,
+            pixel_ratio: data.pixel_ratio,
+            ..builder.config
+        };
+        builder.build()
+    }
+}
+
+#[derive(Debug, Clone)]
+enum CanvasSource {
+    Id(String),
+    Element(web_sys::HtmlCanvasElement),
+    CreateIn(String),
+}
+
+impl CanvasSource {
+    #[cfg(feature = "serde")]
+    fn default_id() -> Self {
+        Self::Id(String::from("canvas"))
+    }
+
+    fn resolve(&self, document: &web_sys::Document) -> Result<web_sys::HtmlCanvasElement, JsValue> {
+        match self {
+            Self::Id(canvas_id) => document
+                .get_element_by_id(canvas_id)
+                .ok_or_else(|| {
+                    JsValue::from_str(&format!("canvas element with id '{}' not found", canvas_id))
+                })?
+                .dyn_into::<web_sys::HtmlCanvasElement>()
+                .map_err(|_| {
+                    JsValue::from_str(&format!("element '{}' is not a <canvas>", canvas_id))
+                }),
+            Self::Element(element) => Ok(element.clone()),
+            Self::CreateIn(parent_selector) => {
+                let parent = document.query_selector(parent_selector)?.ok_or_else(|| {
+                    JsValue::from_str(&format!(
+                        "parent element '{}' for the canvas not found",
+                        parent_selector
+                    ))
+                })?;
+                let canvas = document
+                    .create_element("canvas")?
+                    .dyn_into::<web_sys::HtmlCanvasElement>()?;
+                parent.append_child(&canvas)?;
+                Ok(canvas)
+            }
+        }
+    }
+}
+
+/// AppConfigBuilder builds an AppConfig with optional settings.
+#[derive(Debug, Clone)]
+pub struct AppConfigBuilder {
+    config: AppConfig,
+}
+
+impl AppConfigBuilder {
+    fn new(canvas: CanvasSource) -> Self {
         Self {
-            canvas_id,
-            canvas_width,
-            canvas_height,
+            config: AppConfig {
+                canvas,
+                // The same size as the default of HTML canvas elements.
+                canvas_width: 300.0,
+                canvas_height: 150.0,
+                prevent_default_keys: true,
+                max_fps: None,
+                render_every: 1,
+                pause_when_hidden: true,
+                pixel_art: false,
+                antialiasing: false,
+                background: None,
+                debug_keys: None,
+                title: None,
+                error_policy: ErrorPolicy::default(),
+                loop_driver: LoopDriver::default(),
+                slow_frame: None,
+                display_size: None,
+                pixel_ratio: None,
+                audio: None,
+                overlay: None,
+                recorder: None,
+            },
         }
     }
+
+    /// size sets the width and height of the canvas. The default is 300x150.
+    pub fn size(mut self, canvas_width: f64, canvas_height: f64) -> Self {
+        self.config.canvas_width = canvas_width;
+        self.config.canvas_height = canvas_height;
+        self
+    }
+
+    /// display_size sets the CSS width and height of the canvas, while size sets its resolution,
+    /// e.g. `size(320.0, 180.0).display_size(960.0, 540.0)` for chunky pixels with pixel_art.
+    /// The canvas is displayed at its resolution by default.
+    pub fn display_size(mut self, css_width: f64, css_height: f64) -> Self {
+        self.config.display_size = Some((css_width, css_height));
+        self
+    }
+
+    /// pixel_ratio renders the canvas with ratio device pixels per pixel of size, e.g.
+    /// `web_sys::window().unwrap().device_pixel_ratio()` for sharp text on HiDPI displays, or a
+    /// fixed value in tests and iframes where the reported ratio is unreliable. Draw calls keep
+    /// using the coordinates of size. None, the default, renders 1:1.
+    pub fn pixel_ratio(mut self, pixel_ratio: Option<f64>) -> Self {
+        self.config.pixel_ratio = pixel_ratio;
+        self
+    }
+
+    /// prevent_default_keys stops the browser from scrolling the page or moving the focus
+    /// when the arrow keys, Space, or Tab is down. It is true by default.
+    pub fn prevent_default_keys(mut self, prevent_default_keys: bool) -> Self {
+        self.config.prevent_default_keys = prevent_default_keys;
+        self
+    }
+
+    /// max_fps caps the number of frames per second. None means no cap, which is the default.
+    /// The loop is still called by the loop driver, and skips frames earlier than the target interval.
+    pub fn max_fps(mut self, max_fps: Option<f64>) -> Self {
+        self.config.max_fps = max_fps;
+        self
+    }
+
+    /// render_every makes the loop update every frame but render every n-th frame.
+    /// It is useful for logic-heavy and draw-light apps. The default is 1.
+    pub fn render_every(mut self, n: u32) -> Self {
+        self.config.render_every = n;
+        self
+    }
+
+    /// pause_when_hidden suspends the loop while the tab is hidden, and calls App::on_suspend and
+    /// App::on_resume. It is true by default. Set false for apps which keep simulating.
+    pub fn pause_when_hidden(mut self, pause_when_hidden: bool) -> Self {
+        self.config.pause_when_hidden = pause_when_hidden;
+        self
+    }
+
+    /// audio makes the loop suspend the sounds of audio while the loop is paused or the tab is
+    /// hidden, and resume them from where they paused. Audio is not suspended by default.
+    pub fn audio(mut self, audio: &AudioSystem) -> Self {
+        self.config.audio = Some(audio.clone());
+        self
+    }
+
+    /// overlay makes run create the div of overlay over the canvas, keep it on the canvas, and
+    /// call its click callbacks before every update. Stopping the loop removes the div.
+    pub fn overlay(mut self, overlay: &Overlay) -> Self {
+        self.config.overlay = Some(overlay.clone());
+        self
+    }
+
+    /// recorder makes the loop capture the canvas into recorder after every rendered frame while
+    /// it is enabled. If capturing fails, e.g. a frame exceeds its max_bytes, the error is logged
+    /// to the console and the recorder is disabled.
+    pub fn recorder(mut self, recorder: &Recorder) -> Self {
+        self.config.recorder = Some(recorder.clone());
+        self
+    }
+
+    /// pixel_art disables image smoothing so that scaled sprites keep their sharp pixels.
+    /// It is false by default.
+    pub fn pixel_art(mut self, pixel_art: bool) -> Self {
+        self.config.pixel_art = pixel_art;
+        self
+    }
+
+    /// antialiasing enables image smoothing, so that scaled and rotated sprites are interpolated
+    /// instead of showing their pixels. It is false by default, and pixel_art overrides it.
+    pub fn antialiasing(mut self, antialiasing: bool) -> Self {
+        self.config.antialiasing = antialiasing;
+        self
+    }
+
+    /// background sets the CSS background color of the canvas.
+    pub fn background(mut self, color: impl Into<Color>) -> Self {
+        self.config.background = Some(color.into());
+        self
+    }
+
+    /// debug_keys binds keys to toggle pause and to step a single frame while paused,
+    /// e.g. `debug_keys(web_sys::KeyEvent::DOM_VK_P, web_sys::KeyEvent::DOM_VK_N)`.
+    /// The keys are given as key codes. No key is bound by default.
+    pub fn debug_keys(mut self, pause_key_code: u32, step_key_code: u32) -> Self {
+        self.config.debug_keys = Some((pause_key_code, step_key_code));
+        self
+    }
+
+    /// title sets the title of the browser tab when the game starts.
+    pub fn title(mut self, title: &str) -> Self {
+        self.config.title = Some(String::from(title));
+        self
+    }
+
+    /// error_policy decides what the loop does after App::update or App::render returns an error.
+    /// The default is ErrorPolicy::Continue.
+    pub fn error_policy(mut self, error_policy: ErrorPolicy) -> Self {
+        self.config.error_policy = error_policy;
+        self
+    }
+
+    /// loop_driver decides what calls the loop. The default is LoopDriver::AnimationFrame.
+    /// Use LoopDriver::Timer or LoopDriver::TimerWhenHidden with pause_when_hidden(false)
+    /// for apps which keep simulating in hidden tabs.
+    pub fn loop_driver(mut self, loop_driver: LoopDriver) -> Self {
+        self.config.loop_driver = loop_driver;
+        self
+    }
+
+    /// on_slow_frame calls the callback after each frame whose update and render take longer than
+    /// threshold_ms in total, e.g. to log spikes to your own telemetry.
+    pub fn on_slow_frame(
+        mut self,
+        threshold_ms: f64,
+        callback: impl Fn(&FrameReport) + 'static,
+    ) -> Self {
+        self.config.slow_frame = Some(SlowFrame {
+            threshold_ms,
+            callback: Rc::new(callback),
+        });
+        self
+    }
+
+    /// build validates the settings and returns an AppConfig.
+    pub fn build(self) -> Result<AppConfig> {
+        let config = self.config;
+        match &config.canvas {
+            CanvasSource::Id(canvas_id) => ensure!(
+                !canvas_id.is_empty(),
+                "canvas_id should not be empty; pass the id of the canvas element to AppConfig::builder"
+            ),
+            CanvasSource::CreateIn(parent_selector) => ensure!(
+                !parent_selector.is_empty(),
+                "parent_selector should not be empty; pass e.g. \"body\" to AppConfig::create_in"
+            ),
+            CanvasSource::Element(_) => {}
+        }
+        ensure!(
+            config.canvas_width > 0.0 && config.canvas_height > 0.0,
+            "size: {}x{} should be positive",
+            config.canvas_width,
+            config.canvas_height
+        );
+        if let Some((css_width, css_height)) = config.display_size {
+            ensure!(
+                css_width > 0.0 && css_height > 0.0,
+                "display_size: {}x{} should be positive",
+                css_width,
+                css_height
+            );
+        }
+        if let Some(pixel_ratio) = config.pixel_ratio {
+            ensure!(
+                pixel_ratio.is_finite() && pixel_ratio > 0.0,
+                "pixel_ratio: {} should be positive and finite; use None for 1:1",
+                pixel_ratio
+            );
+        }
+        if let Some(max_fps) = config.max_fps {
+            ensure!(
+                max_fps.is_finite() && max_fps > 0.0,
+                "max_fps: {} should be positive and finite; use None for no cap",
+                max_fps
+            );
+        }
+        ensure!(config.render_every > 0, "render_every should be at least 1");
+        match config.loop_driver {
+            LoopDriver::AnimationFrame | LoopDriver::Manual => {}
+            LoopDriver::Timer { interval_ms } | LoopDriver::TimerWhenHidden { interval_ms } => {
+                ensure!(
+                    interval_ms.is_finite() && interval_ms > 0.0,
+                    "interval_ms: {} of the loop driver should be positive and finite",
+                    interval_ms
+                )
+            }
+        }
+        if let Some(slow_frame) = &config.slow_frame {
+            ensure!(
+                slow_frame.threshold_ms.is_finite() && slow_frame.threshold_ms >= 0.0,
+                "threshold_ms: {} of on_slow_frame should be non-negative and finite",
+                slow_frame.threshold_ms
+            );
+        }
+        Ok(config)
+    }
+}
+
+#[derive(Clone)]
+struct SlowFrame {
+    threshold_ms: f64,
+    callback: Rc<dyn Fn(&FrameReport)>,
+}
+
+impl fmt::Debug for SlowFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SlowFrame")
+            .field("threshold_ms", &self.threshold_ms)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FrameLimiter;
+
+    // JITTER is added to the timestamps of requestAnimationFrame, which are not exactly periodic.
+    const JITTER: [f64; 6] = [0.0, 0.4, -0.3, 0.2, -0.5, 0.3];
+
+    // run_frames returns which of count frames at refresh_rate Hz, starting at start, run.
+    fn run_frames(
+        limiter: &mut FrameLimiter,
+        refresh_rate: f64,
+        start: f64,
+        count: usize,
+    ) -> Vec<bool> {
+        (0..count)
+            .map(|i| {
+                let time = start + i as f64 * 1000.0 / refresh_rate + JITTER[i % JITTER.len()];
+                limiter.should_run(time)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn frame_limiter_without_cap_runs_every_frame() {
+        let mut limiter = FrameLimiter::new(None, 1);
+        assert!(run_frames(&mut limiter, 60.0, 0.0, 60)
+            .into_iter()
+            .all(|run| run));
+    }
+
+    #[test]
+    fn frame_limiter_at_display_rate_runs_every_jittered_frame() {
+        let mut limiter = FrameLimiter::new(Some(60.0), 1);
+        assert!(run_frames(&mut limiter, 60.0, 0.0, 120)
+            .into_iter()
+            .all(|run| run));
+    }
+
+    #[test]
+    fn frame_limiter_at_half_display_rate_runs_every_other_frame() {
+        let mut limiter = FrameLimiter::new(Some(30.0), 1);
+        let runs = run_frames(&mut limiter, 60.0, 0.0, 120);
+        let expected: Vec<bool> = (0..120).map(|i| i % 2 == 0).collect();
+        assert_eq!(runs, expected);
+    }
+
+    #[test]
+    fn frame_limiter_carries_the_remainder_to_converge_on_the_cap() {
+        // 144 Hz is not a multiple of 60 fps, so the frames run every 2 or 3 frames.
+        let mut limiter = FrameLimiter::new(Some(60.0), 1);
+        let runs = run_frames(&mut limiter, 144.0, 0.0, 144 * 5);
+        let count = runs.iter().filter(|run| **run).count();
+        assert!(
+            (299..=301).contains(&count),
+            "{} frames ran in 5 seconds",
+            count
+        );
+
+        let mut limiter = FrameLimiter::new(Some(30.0), 1);
+        let runs = run_frames(&mut limiter, 144.0, 0.0, 144 * 5);
+        let count = runs.iter().filter(|run| **run).count();
+        assert!(
+            (149..=151).contains(&count),
+            "{} frames ran in 5 seconds",
+            count
+        );
+    }
+
+    #[test]
+    fn frame_limiter_does_not_catch_up_after_a_long_stall() {
+        let mut limiter = FrameLimiter::new(Some(30.0), 1);
+        assert!(limiter.should_run(0.0));
+        assert!(limiter.should_run(1000.0));
+        // The stall is not made up by a burst of frames.
+        assert_eq!(
+            run_frames(&mut limiter, 60.0, 1000.0 + 1000.0 / 60.0, 4),
+            vec![false, true, false, true]
+        );
+    }
+
+    #[test]
+    fn frame_limiter_runs_the_first_frame_after_reset() {
+        let mut limiter = FrameLimiter::new(Some(30.0), 1);
+        assert!(limiter.should_run(0.0));
+        assert!(!limiter.should_run(10.0));
+        limiter.reset();
+        assert!(limiter.should_run(12.0));
+        assert!(!limiter.should_run(20.0));
+    }
+
+    #[test]
+    fn frame_limiter_renders_every_nth_executed_frame() {
+        let mut limiter = FrameLimiter::new(None, 3);
+        let renders: Vec<bool> = (0..7).map(|_| limiter.should_render()).collect();
+        assert_eq!(renders, vec![true, false, false, true, false, false, true]);
+    }
 }
@@ -0,0 +1,197 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use anyhow::{anyhow, ensure, Result};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use super::teardown::{drop_later, Listener};
+use super::{LoopDriver, LoopState};
+
+type Tick = Box<dyn FnMut(f64) -> bool>;
+type Callback<F> = RefCell<Option<Closure<F>>>;
+
+// Driver calls tick on every animation frame or timeout until tick returns false, or only when
+// AppHandle::tick is called with LoopDriver::Manual.
+pub(super) struct Driver {
+    loop_driver: LoopDriver,
+    window: web_sys::Window,
+    document: web_sys::Document,
+    state: Rc<RefCell<LoopState>>,
+    tick: RefCell<Tick>,
+    on_animation_frame: Callback<dyn FnMut(f64)>,
+    on_timeout: Callback<dyn FnMut()>,
+    visibility_listener: RefCell<Option<Listener>>,
+}
+
+impl Driver {
+    pub(super) fn start(
+        loop_driver: LoopDriver,
+        window: web_sys::Window,
+        document: web_sys::Document,
+        state: Rc<RefCell<LoopState>>,
+        tick: impl FnMut(f64) -> bool + 'static,
+    ) -> Result<Rc<Self>, JsValue> {
+        let performance = window
+            .performance()
+            .ok_or_else(|| JsValue::from_str("the window has no performance"))?;
+        let driver = Rc::new(Self {
+            loop_driver,
+            window,
+            document,
+            state,
+            tick: RefCell::new(Box::new(tick)),
+            on_animation_frame: RefCell::new(None),
+            on_timeout: RefCell::new(None),
+            visibility_listener: RefCell::new(None),
+        });
+        let animation_frame_driver = Rc::clone(&driver);
+        driver
+            .on_animation_frame
+            .replace(Some(Closure::wrap(Box::new(move |time: f64| {
+                animation_frame_driver.run(time);
+            }) as Box<dyn FnMut(f64)>)));
+        // performance.now has the same origin as the time passed to requestAnimationFrame.
+        let timeout_driver = Rc::clone(&driver);
+        driver
+            .on_timeout
+            .replace(Some(Closure::wrap(Box::new(move || {
+                timeout_driver.run(performance.now());
+            }) as Box<dyn FnMut()>)));
+        if let LoopDriver::TimerWhenHidden { .. } = loop_driver {
+            driver.switch_on_visibility_change()?;
+        }
+        driver.schedule()?;
+        Ok(driver)
+    }
+
+    // release drops the callbacks, which own the driver, so that the driver and the app are freed.
+    pub(super) fn release(&self) {
+        if let Some(listener) = self.visibility_listener.take() {
+            listener.remove(&self.window);
+        }
+        let callbacks = (self.on_animation_frame.take(), self.on_timeout.take());
+        drop_later(&self.window, callbacks);
+    }
+
+    // tick runs a frame of the manually driven loop.
+    pub(super) fn tick(&self, time: f64) -> Result<()> {
+        ensure!(
+            self.loop_driver == LoopDriver::Manual,
+            "AppHandle::tick needs LoopDriver::Manual, but the loop is driven by {:?}",
+            self.loop_driver
+        );
+        let mut tick = self
+            .tick
+            .try_borrow_mut()
+            .map_err(|_| anyhow!("AppHandle::tick cannot be called in the loop"))?;
+        (*tick)(time);
+        Ok(())
+    }
+
+    fn run(&self, time: f64) {
+        self.state.borrow_mut().scheduled = None;
+        let keep_running = (self.tick.borrow_mut())(time);
+        // AppHandle::stop may be called in the tick, e.g. by App::update or an overlay callback.
+        if !keep_running || self.state.borrow().stopped {
+            return;
+        }
+        if let Err(e) = self.schedule() {
+            web_sys::console::error_1(&JsValue::from_str(&format!(
+                "failed to schedule the next frame: {:?}",
+                e
+            )));
+            self.state.borrow_mut().stopped = true;
+        }
+    }
+
+    // schedule requests the next frame. It does nothing after release has taken the callbacks.
+    fn schedule(&self) -> Result<(), JsValue> {
+        if self.loop_driver == LoopDriver::Manual {
+            return Ok(());
+        }
+        let scheduled = match self.interval_ms() {
+            Some(interval_ms) => {
+                let on_timeout = self.on_timeout.borrow();
+                let callback = match on_timeout.as_ref() {
+                    Some(callback) => callback,
+                    None => return Ok(()),
+                };
+                Scheduled::Timeout(
+                    self.window
+                        .set_timeout_with_callback_and_timeout_and_arguments_0(
+                            callback.as_ref().unchecked_ref(),
+                            interval_ms.round() as i32,
+                        )?,
+                )
+            }
+            None => {
+                let on_animation_frame = self.on_animation_frame.borrow();
+                let callback = match on_animation_frame.as_ref() {
+                    Some(callback) => callback,
+                    None => return Ok(()),
+                };
+                Scheduled::AnimationFrame(
+                    self.window
+                        .request_animation_frame(callback.as_ref().unchecked_ref())?,
+                )
+            }
+        };
+        self.state.borrow_mut().scheduled = Some(scheduled);
+        Ok(())
+    }
+
+    fn interval_ms(&self) -> Option<f64> {
+        match self.loop_driver {
+            LoopDriver::AnimationFrame | LoopDriver::Manual => None,
+            LoopDriver::Timer { interval_ms } => Some(interval_ms),
+            LoopDriver::TimerWhenHidden { interval_ms } => {
+                if self.document.hidden() {
+                    Some(interval_ms)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    fn switch_on_visibility_change(self: &Rc<Self>) -> Result<(), JsValue> {
+        let driver = Rc::clone(self);
+        let visibility_handler = Closure::wrap(Box::new(move || {
+            // Nothing is scheduled after the loop is stopped.
+            let scheduled = driver.state.borrow_mut().scheduled.take();
+            if let Some(scheduled) = scheduled {
+                scheduled.cancel(&driver.window);
+                if let Err(e) = driver.schedule() {
+                    web_sys::console::error_1(&JsValue::from_str(&format!(
+                        "failed to switch the loop driver: {:?}",
+                        e
+                    )));
+                    driver.state.borrow_mut().stopped = true;
+                }
+            }
+        }) as Box<dyn FnMut()>);
+        let listener = Listener::add(&self.document, "visibilitychange", visibility_handler)?;
+        self.visibility_listener.replace(Some(listener));
+
+        Ok(())
+    }
+}
+
+// Scheduled is the id of the pending animation frame or timer, which is cancelled by stop.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Scheduled {
+    AnimationFrame(i32),
+    Timeout(i32),
+}
+
+impl Scheduled {
+    pub(super) fn cancel(self, window: &web_sys::Window) {
+        match self {
+            Scheduled::AnimationFrame(id) => {
+                let _ = window.cancel_animation_frame(id);
+            }
+            Scheduled::Timeout(id) => window.clear_timeout_with_handle(id),
+        }
+    }
+}
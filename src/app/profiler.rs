@@ -0,0 +1,17 @@
+use std::cell::Cell;
+
+pub use crate::timing::FrameReport as FrameStats;
+
+thread_local! {
+    static LAST_FRAME: Cell<Option<FrameStats>> = const { Cell::new(None) };
+}
+
+/// last_frame returns the durations of App::update and App::render in the last frame of the
+/// running loop, or None before the first frame.
+pub fn last_frame() -> Option<FrameStats> {
+    LAST_FRAME.with(|last_frame| last_frame.get())
+}
+
+pub(crate) fn record(stats: FrameStats) {
+    LAST_FRAME.with(|last_frame| last_frame.set(Some(stats)));
+}
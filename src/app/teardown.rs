@@ -0,0 +1,107 @@
+use std::any::Any;
+use std::fmt;
+use std::rc::Rc;
+
+use wasm_bindgen::closure::WasmClosure;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use super::driver::Driver;
+use crate::audio::AudioSystem;
+use crate::overlay::Overlay;
+
+// Teardown holds what AppHandle::stop releases, so that a fresh run after stop starts clean.
+#[derive(Default)]
+pub(super) struct Teardown {
+    listeners: Vec<Listener>,
+    driver: Option<Rc<Driver>>,
+    overlay: Option<Overlay>,
+    audio: Option<AudioSystem>,
+}
+
+impl Teardown {
+    pub(super) fn add_listener(&mut self, listener: Listener) {
+        self.listeners.push(listener);
+    }
+
+    pub(super) fn set_driver(&mut self, driver: Rc<Driver>) {
+        self.driver = Some(driver);
+    }
+
+    pub(super) fn set_overlay(&mut self, overlay: Overlay) {
+        self.overlay = Some(overlay);
+    }
+
+    pub(super) fn set_audio(&mut self, audio: AudioSystem) {
+        self.audio = Some(audio);
+    }
+
+    pub(super) fn driver(&self) -> Option<Rc<Driver>> {
+        self.driver.clone()
+    }
+
+    pub(super) fn release(&mut self, window: &web_sys::Window) {
+        for listener in self.listeners.drain(..) {
+            listener.remove(window);
+        }
+        if let Some(driver) = self.driver.take() {
+            driver.release();
+        }
+        if let Some(overlay) = self.overlay.take() {
+            overlay.detach();
+        }
+        if let Some(audio) = self.audio.take() {
+            audio.stop_listening();
+        }
+    }
+}
+
+impl fmt::Debug for Teardown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Teardown")
+            .field("listeners", &self.listeners.len())
+            .field("driver", &self.driver.is_some())
+            .field("overlay", &self.overlay.is_some())
+            .field("audio", &self.audio.is_some())
+            .finish()
+    }
+}
+
+// Listener is an event listener which keeps its closure alive until it is removed.
+pub(crate) struct Listener {
+    target: web_sys::EventTarget,
+    event_type: &'static str,
+    function: js_sys::Function,
+    closure: Box<dyn Any>,
+}
+
+impl Listener {
+    pub(crate) fn add<F: WasmClosure + ?Sized + 'static>(
+        target: &web_sys::EventTarget,
+        event_type: &'static str,
+        closure: Closure<F>,
+    ) -> Result<Self, JsValue> {
+        let function: js_sys::Function = closure.as_ref().clone().unchecked_into();
+        target.add_event_listener_with_callback(event_type, &function)?;
+        Ok(Self {
+            target: target.clone(),
+            event_type,
+            function,
+            closure: Box::new(closure),
+        })
+    }
+
+    pub(crate) fn remove(self, window: &web_sys::Window) {
+        let _ = self
+            .target
+            .remove_event_listener_with_callback(self.event_type, &self.function);
+        drop_later(window, self.closure);
+    }
+}
+
+// drop_later drops value after the current task, because the value may own the closure which is
+// running now, e.g. when stop is called inside an event listener.
+pub(crate) fn drop_later<V: 'static>(window: &web_sys::Window, value: V) {
+    let callback = Closure::once_into_js(move || drop(value));
+    let _ = window.set_timeout_with_callback(callback.unchecked_ref());
+}
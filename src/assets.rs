@@ -0,0 +1,237 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::future::Future;
+use std::rc::Rc;
+
+use anyhow::{anyhow, ensure, Context, Result};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+
+/// ResourceCache stores the resources fetched before the first frame, keyed by their URLs.
+#[derive(Debug, Default)]
+pub struct ResourceCache {
+    resources: HashMap<String, Vec<u8>>,
+}
+
+impl ResourceCache {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn insert(&mut self, url: String, bytes: Vec<u8>) {
+        self.resources.insert(url, bytes);
+    }
+
+    /// get returns the bytes fetched from url.
+    pub fn get(&self, url: &str) -> Option<&[u8]> {
+        self.resources.get(url).map(|bytes| bytes.as_slice())
+    }
+
+    /// len returns the number of the fetched resources.
+    pub fn len(&self) -> usize {
+        self.resources.len()
+    }
+
+    /// is_empty returns true when no resource is fetched.
+    pub fn is_empty(&self) -> bool {
+        self.resources.is_empty()
+    }
+}
+
+/// Assets tracks pending resources, e.g. sprite stores, audio buffers and fetched data.
+/// Return it from App::assets, and the loop calls App::render_loading instead of App::update and
+/// App::render until all the registered resources are ready. It is cheap to clone.
+#[derive(Debug, Clone, Default)]
+pub struct Assets {
+    state: Rc<RefCell<AssetsState>>,
+}
+
+#[derive(Debug, Default)]
+struct AssetsState {
+    registered: usize,
+    ready: usize,
+    error: Option<String>,
+}
+
+impl Assets {
+    /// new returns an Assets without resources.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// register adds a resource which is ready when promise is resolved,
+    /// e.g. the promise returned by SpriteStore::on_load.
+    pub fn register(&self, promise: js_sys::Promise) -> AssetHandle {
+        self.register_future(async move {
+            JsFuture::from(promise)
+                .await
+                .map_err(|e| anyhow!("the promise is rejected: {:?}", e))?;
+            Ok(())
+        })
+    }
+
+    /// register_future adds a resource which is ready when future returns Ok.
+    /// The returned AssetHandle tells the state of the resource.
+    pub fn register_future(
+        &self,
+        future: impl Future<Output = Result<()>> + 'static,
+    ) -> AssetHandle {
+        let future = self.track(future);
+        let handle = AssetHandle::default();
+        let asset = handle.clone();
+        spawn_local(async move {
+            match future.await {
+                Ok(()) => asset.state.replace(AssetState::Ready),
+                Err(e) => asset.state.replace(AssetState::Failed(format!("{:#}", e))),
+            };
+        });
+        handle
+    }
+
+    /// fetch_bytes is the module function fetch_bytes, counted as a resource until it finishes.
+    /// The returned future must be awaited, or the loop keeps waiting for it.
+    pub fn fetch_bytes(&self, url: &str) -> impl Future<Output = Result<Vec<u8>>> {
+        let url = String::from(url);
+        self.track(async move { fetch_bytes(&url).await })
+    }
+
+    /// fetch_text is the module function fetch_text, counted as a resource until it finishes.
+    /// The returned future must be awaited, or the loop keeps waiting for it.
+    pub fn fetch_text(&self, url: &str) -> impl Future<Output = Result<String>> {
+        let url = String::from(url);
+        self.track(async move { fetch_text(&url).await })
+    }
+
+    /// fetch_json is the module function fetch_json, counted as a resource until it finishes.
+    /// The returned future must be awaited, or the loop keeps waiting for it.
+    #[cfg(feature = "serde")]
+    pub fn fetch_json<T: serde::de::DeserializeOwned>(
+        &self,
+        url: &str,
+    ) -> impl Future<Output = Result<T>> {
+        let url = String::from(url);
+        self.track(async move { fetch_json(&url).await })
+    }
+
+    // track counts future as a registered resource from now, and as ready or failed when it
+    // finishes.
+    fn track<T>(&self, future: impl Future<Output = Result<T>>) -> impl Future<Output = Result<T>> {
+        self.state.borrow_mut().registered += 1;
+        let state = Rc::clone(&self.state);
+        async move {
+            let result = future.await;
+            let mut state = state.borrow_mut();
+            match &result {
+                Ok(_) => state.ready += 1,
+                Err(e) => {
+                    state.error.get_or_insert_with(|| format!("{:#}", e));
+                }
+            }
+            result
+        }
+    }
+
+    /// progress returns the ratio of the ready resources from 0.0 to 1.0.
+    /// It is 1.0 when no resource is registered.
+    pub fn progress(&self) -> f64 {
+        let state = self.state.borrow();
+        if state.registered == 0 {
+            return 1.0;
+        }
+        state.ready as f64 / state.registered as f64
+    }
+
+    /// is_ready returns true when all the registered resources are ready.
+    pub fn is_ready(&self) -> bool {
+        let state = self.state.borrow();
+        state.ready == state.registered
+    }
+
+    /// error returns the message of the first resource which failed.
+    pub fn error(&self) -> Option<String> {
+        self.state.borrow().error.clone()
+    }
+}
+
+/// AssetHandle tells the state of a resource registered to Assets. It is cheap to clone.
+#[derive(Debug, Clone, Default)]
+pub struct AssetHandle {
+    state: Rc<RefCell<AssetState>>,
+}
+
+#[derive(Debug, Default)]
+enum AssetState {
+    #[default]
+    Pending,
+    Ready,
+    Failed(String),
+}
+
+impl AssetHandle {
+    /// is_ready returns true when the resource is ready.
+    pub fn is_ready(&self) -> bool {
+        matches!(*self.state.borrow(), AssetState::Ready)
+    }
+
+    /// error returns the message if the resource failed.
+    pub fn error(&self) -> Option<String> {
+        match &*self.state.borrow() {
+            AssetState::Failed(message) => Some(message.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// fetch_bytes fetches url and returns the body of the response.
+/// A relative url is resolved against the base URL of the document, as the Fetch API does.
+/// An HTTP error status is returned as an error with the status code and url.
+/// Use Assets::fetch_bytes to show the fetch on the loading screen.
+pub async fn fetch_bytes(url: &str) -> Result<Vec<u8>> {
+    let buffer = fetch(url)
+        .await?
+        .array_buffer()
+        .map_err(|e| anyhow!("failed to read the body of {}: {:?}", url, e))?;
+    let buffer = JsFuture::from(buffer)
+        .await
+        .map_err(|e| anyhow!("failed to read the body of {}: {:?}", url, e))?;
+    Ok(js_sys::Uint8Array::new(&buffer).to_vec())
+}
+
+/// fetch_text fetches url and returns the body of the response as text, e.g. a dialogue script.
+/// Errors are the same as fetch_bytes.
+pub async fn fetch_text(url: &str) -> Result<String> {
+    let text = fetch(url)
+        .await?
+        .text()
+        .map_err(|e| anyhow!("failed to read the body of {}: {:?}", url, e))?;
+    JsFuture::from(text)
+        .await
+        .map_err(|e| anyhow!("failed to read the body of {}: {:?}", url, e))?
+        .as_string()
+        .with_context(|| format!("failed to read the body of {}: it is not text", url))
+}
+
+/// fetch_json fetches url and decodes the body of the response as JSON, e.g. level data.
+/// Errors are the same as fetch_bytes, plus an error if the JSON does not match T.
+#[cfg(feature = "serde")]
+pub async fn fetch_json<T: serde::de::DeserializeOwned>(url: &str) -> Result<T> {
+    let text = fetch_text(url).await?;
+    serde_json::from_str(&text).with_context(|| format!("failed to decode the JSON of {}", url))
+}
+
+// fetch returns the response of url if its status is successful.
+async fn fetch(url: &str) -> Result<web_sys::Response> {
+    let window = web_sys::window().context("no global window exists")?;
+    let response = JsFuture::from(window.fetch_with_str(url))
+        .await
+        .map_err(|e| anyhow!("failed to fetch {}: {:?}", url, e))?
+        .dyn_into::<web_sys::Response>()
+        .map_err(|_| anyhow!("failed to fetch {}: the response is not a Response", url))?;
+    ensure!(
+        response.ok(),
+        "failed to fetch {}: HTTP status {}",
+        url,
+        response.status()
+    );
+    Ok(response)
+}
@@ -0,0 +1,591 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use anyhow::{anyhow, Context, Result};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+
+use crate::app::Listener;
+use crate::assets::{fetch_bytes, AssetHandle, Assets};
+
+mod ambience;
+pub use ambience::Ambience;
+
+mod mixer;
+
+mod music;
+pub use music::{Music, MusicOptions};
+
+mod params;
+pub use params::PlayParams;
+
+mod sprite;
+pub use sprite::AudioSprite;
+
+mod tone;
+pub use tone::{Tone, Waveform};
+
+mod unlock;
+
+/// MUSIC_BUS is the bus which Music plays on.
+pub const MUSIC_BUS: &str = "music";
+
+/// SFX_BUS is the bus which Sound::play plays on.
+pub const SFX_BUS: &str = "sfx";
+
+/// AMBIENCE_BUS is the bus which Ambience plays on.
+pub const AMBIENCE_BUS: &str = "ambience";
+
+/// AudioSystem owns the AudioContext shared by all sounds. The context is created lazily
+/// on the first use. It is cheap to clone.
+/// Browsers do not allow audio until a user gesture, so the AudioSystem resumes the context on
+/// keydown, mousedown, pointerdown, pointerup and touchend until it is running, and plays the
+/// sounds requested before that.
+#[derive(Debug, Clone, Default)]
+pub struct AudioSystem {
+    inner: Rc<AudioInner>,
+}
+
+#[derive(Debug)]
+struct AudioInner {
+    context: RefCell<Option<web_sys::AudioContext>>,
+    music: RefCell<HashMap<String, music::Track>>,
+    mixer: RefCell<mixer::Mixer>,
+    unlock: RefCell<unlock::Unlock>,
+    preloads: RefCell<HashMap<String, AssetHandle>>,
+    buffers: RefCell<HashMap<String, web_sys::AudioBuffer>>,
+    // The context is suspended while the game or the loop suspends it.
+    suspended_by_game: Cell<bool>,
+    suspended_by_loop: Cell<bool>,
+    context_suspended: Cell<bool>,
+}
+
+impl Default for AudioInner {
+    fn default() -> Self {
+        Self {
+            context: RefCell::new(None),
+            music: RefCell::new(HashMap::new()),
+            mixer: RefCell::new(mixer::Mixer::new()),
+            unlock: RefCell::new(unlock::Unlock::new()),
+            preloads: RefCell::new(HashMap::new()),
+            buffers: RefCell::new(HashMap::new()),
+            suspended_by_game: Cell::new(false),
+            suspended_by_loop: Cell::new(false),
+            context_suspended: Cell::new(false),
+        }
+    }
+}
+
+impl AudioSystem {
+    /// new returns an AudioSystem without creating the AudioContext yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// context returns the AudioContext, creating it if it does not exist yet.
+    /// It returns an error if the browser has no AudioContext.
+    pub fn context(&self) -> Result<web_sys::AudioContext> {
+        if let Some(context) = self.inner.context.borrow().as_ref() {
+            return Ok(context.clone());
+        }
+        let context = web_sys::AudioContext::new()
+            .map_err(|e| anyhow!("failed to create an AudioContext: {:?}", e))?;
+        self.inner.context.replace(Some(context.clone()));
+        if context.state() == web_sys::AudioContextState::Running {
+            self.inner.unlock.borrow_mut().unlocked = true;
+            self.apply_suspended();
+        } else {
+            self.listen_for_gesture();
+        }
+        Ok(context)
+    }
+
+    /// is_unlocked returns true after the browser allows audio, e.g. to hide a
+    /// "click to enable sound" hint.
+    pub fn is_unlocked(&self) -> bool {
+        self.inner.unlock.borrow().unlocked
+    }
+
+    /// on_unlock calls callback once audio is unlocked, or immediately if it is already unlocked.
+    pub fn on_unlock(&self, callback: impl FnOnce() + 'static) {
+        if self.is_unlocked() {
+            callback();
+            return;
+        }
+        self.inner.unlock.borrow_mut().on_unlock = Some(Box::new(callback));
+    }
+
+    // play_when_unlocked calls play now, or queues it until audio is unlocked.
+    pub(crate) fn play_when_unlocked(
+        &self,
+        play: impl FnOnce() -> Result<()> + 'static,
+    ) -> Result<()> {
+        self.context()?;
+        let mut unlock = self.inner.unlock.borrow_mut();
+        if unlock.unlocked {
+            drop(unlock);
+            return play();
+        }
+        if unlock.listeners.is_empty() {
+            // The listeners are removed when the loop is stopped, so they are added again.
+            drop(unlock);
+            self.listen_for_gesture();
+            unlock = self.inner.unlock.borrow_mut();
+        }
+        if unlock.queue.len() < unlock::Unlock::MAX_QUEUED {
+            unlock.queue.push(Box::new(play));
+        } else {
+            web_sys::console::warn_1(&JsValue::from_str(
+                "the sound is dropped because too many sounds are waiting for audio to be unlocked",
+            ));
+        }
+        Ok(())
+    }
+
+    fn listen_for_gesture(&self) {
+        if !self.inner.unlock.borrow().listeners.is_empty() {
+            return;
+        }
+        let document = match web_sys::window().and_then(|window| window.document()) {
+            Some(document) => document,
+            None => return,
+        };
+        let mut listeners = vec![];
+        for event_type in unlock::Unlock::GESTURES {
+            let inner = Rc::downgrade(&self.inner);
+            let gesture_handler = Closure::wrap(Box::new(move || {
+                if let Some(inner) = inner.upgrade() {
+                    AudioSystem { inner }.resume_on_gesture();
+                }
+            }) as Box<dyn FnMut()>);
+            match Listener::add(&document, event_type, gesture_handler) {
+                Ok(listener) => listeners.push(listener),
+                Err(e) => web_sys::console::warn_1(&JsValue::from_str(&format!(
+                    "failed to listen to {} to unlock audio: {:?}",
+                    event_type, e
+                ))),
+            }
+        }
+        self.inner.unlock.borrow_mut().listeners = listeners;
+    }
+
+    // stop_listening removes the listeners for user gestures, e.g. when the loop is stopped.
+    // They are added again when a sound is played before audio is unlocked.
+    pub(crate) fn stop_listening(&self) {
+        self.inner.unlock.borrow_mut().stop_listening();
+    }
+
+    fn resume_on_gesture(&self) {
+        if self.is_unlocked() {
+            return;
+        }
+        let context = match self.inner.context.borrow().clone() {
+            Some(context) => context,
+            None => return,
+        };
+        let promise = match context.resume() {
+            Ok(promise) => promise,
+            Err(e) => {
+                web_sys::console::warn_1(&JsValue::from_str(&format!(
+                    "failed to resume the AudioContext: {:?}",
+                    e
+                )));
+                return;
+            }
+        };
+        let audio = self.clone();
+        spawn_local(async move {
+            // The listeners are kept, so the next gesture tries again unless it is running.
+            match JsFuture::from(promise).await {
+                Ok(_) if context.state() == web_sys::AudioContextState::Running => {
+                    audio.finish_unlock()
+                }
+                Ok(_) => {}
+                Err(e) => web_sys::console::warn_1(&JsValue::from_str(&format!(
+                    "failed to resume the AudioContext: {:?}",
+                    e
+                ))),
+            }
+        });
+    }
+
+    fn finish_unlock(&self) {
+        let (queue, on_unlock) = {
+            let mut unlock = self.inner.unlock.borrow_mut();
+            if unlock.unlocked {
+                return;
+            }
+            unlock.unlocked = true;
+            unlock.stop_listening();
+            (std::mem::take(&mut unlock.queue), unlock.on_unlock.take())
+        };
+        for play in queue {
+            if let Err(e) = play() {
+                web_sys::console::error_1(&JsValue::from_str(&format!(
+                    "failed to play the sound queued before unlocking: {:?}",
+                    e
+                )));
+            }
+        }
+        if let Some(on_unlock) = on_unlock {
+            on_unlock();
+        }
+        self.apply_suspended();
+    }
+
+    /// suspend pauses all the sounds in place, e.g. while a menu is open.
+    /// resume plays them from where they paused.
+    pub fn suspend(&self) {
+        self.inner.suspended_by_game.set(true);
+        self.apply_suspended();
+    }
+
+    /// resume plays the sounds paused by suspend. The sounds stay paused while the loop
+    /// suspends them, see AppConfigBuilder::audio.
+    pub fn resume(&self) {
+        self.inner.suspended_by_game.set(false);
+        self.apply_suspended();
+    }
+
+    /// is_suspended returns true while the sounds are paused by suspend or by the loop.
+    pub fn is_suspended(&self) -> bool {
+        self.inner.suspended_by_game.get() || self.inner.suspended_by_loop.get()
+    }
+
+    // set_suspended_by_loop pauses(resumes) the sounds with the loop.
+    pub(crate) fn set_suspended_by_loop(&self, suspended: bool) {
+        if self.inner.suspended_by_loop.replace(suspended) != suspended {
+            self.apply_suspended();
+        }
+    }
+
+    fn apply_suspended(&self) {
+        // A locked context is resumed by the first user gesture, then this is applied again.
+        if !self.is_unlocked() {
+            return;
+        }
+        let suspended = self.is_suspended();
+        if self.inner.context_suspended.replace(suspended) == suspended {
+            return;
+        }
+        let context = match self.inner.context.borrow().clone() {
+            Some(context) => context,
+            None => return,
+        };
+        let (promise, action) = if suspended {
+            (context.suspend(), "suspend")
+        } else {
+            (context.resume(), "resume")
+        };
+        let warn = move |e: JsValue| {
+            web_sys::console::warn_1(&JsValue::from_str(&format!(
+                "failed to {} the AudioContext: {:?}",
+                action, e
+            )))
+        };
+        match promise {
+            Ok(promise) => spawn_local(async move {
+                if let Err(e) = JsFuture::from(promise).await {
+                    warn(e);
+                }
+            }),
+            Err(e) => warn(e),
+        }
+    }
+
+    /// master_volume returns the volume of all the sounds from 0.0 to 1.0. It keeps the volume
+    /// before muting while muted.
+    pub fn master_volume(&self) -> f64 {
+        self.inner.mixer.borrow().master_volume()
+    }
+
+    /// set_master_volume changes the volume of all the sounds, including playing ones.
+    /// The volume is clamped to 0.0..=1.0.
+    pub fn set_master_volume(&self, volume: f64) {
+        self.inner.mixer.borrow_mut().set_master_volume(volume);
+    }
+
+    /// bus_volume returns the volume of bus, e.g. MUSIC_BUS or SFX_BUS. The default is 1.0.
+    pub fn bus_volume(&self, bus: &str) -> f64 {
+        self.inner.mixer.borrow().bus_volume(bus)
+    }
+
+    /// set_bus_volume changes the volume of the sounds on bus, including playing ones.
+    /// The volume is clamped to 0.0..=1.0.
+    pub fn set_bus_volume(&self, bus: &str, volume: f64) {
+        self.inner.mixer.borrow_mut().set_bus_volume(bus, volume);
+    }
+
+    /// is_bus_muted returns true when the sounds on bus are muted by set_bus_muted.
+    pub fn is_bus_muted(&self, bus: &str) -> bool {
+        self.inner.mixer.borrow().is_bus_muted(bus)
+    }
+
+    /// set_bus_muted silences(restores) the sounds on bus without losing its volume, e.g. to
+    /// silence MUSIC_BUS in a menu while UI clicks are still heard. Unlike suspend, the sounds
+    /// keep playing silently.
+    pub fn set_bus_muted(&self, bus: &str, muted: bool) {
+        self.inner.mixer.borrow_mut().set_bus_muted(bus, muted);
+    }
+
+    /// is_muted returns true when all the sounds are muted.
+    pub fn is_muted(&self) -> bool {
+        self.inner.mixer.borrow().is_muted()
+    }
+
+    /// set_muted silences(restores) all the sounds without losing the master volume.
+    pub fn set_muted(&self, muted: bool) {
+        self.inner.mixer.borrow_mut().set_muted(muted);
+    }
+
+    // bus_node returns the gain node which sounds on bus are connected to.
+    pub(crate) fn bus_node(&self, bus: &str) -> Result<web_sys::GainNode> {
+        let context = self.context()?;
+        self.inner.mixer.borrow_mut().bus_node(&context, bus)
+    }
+
+    /// music returns the handle of the default music channel.
+    pub fn music(&self) -> Music {
+        self.music_channel(Music::DEFAULT_CHANNEL)
+    }
+
+    /// music_channel returns the handle of a named music channel, e.g. "ambience".
+    /// Each channel plays one track at a time.
+    pub fn music_channel(&self, channel: &str) -> Music {
+        Music::new(self.clone(), channel)
+    }
+
+    /// play_music plays sound on the default music channel, and fades out the previous track.
+    pub fn play_music(&self, sound: &Sound, options: MusicOptions) -> Result<()> {
+        self.music().play(sound, options)
+    }
+
+    /// stop_music fades out the track on the default music channel in fade_out_ms milliseconds.
+    pub fn stop_music(&self, fade_out_ms: f64) -> Result<()> {
+        self.music().stop(fade_out_ms)
+    }
+
+    /// crossfade_to fades the track on the default music channel into sound in ms milliseconds.
+    pub fn crossfade_to(&self, sound: &Sound, ms: f64) -> Result<()> {
+        self.music().crossfade_to(sound, ms)
+    }
+
+    /// preload decodes bytes of format in the background and caches the Sound as name.
+    /// The loading screen waits for it through assets. Preloading the same name again returns
+    /// the handle of the first preload.
+    pub fn preload(
+        &self,
+        assets: &Assets,
+        name: &str,
+        bytes: Vec<u8>,
+        format: &str,
+    ) -> AssetHandle {
+        let format = String::from(format);
+        self.preload_with(assets, name, async move { Ok((bytes, format)) })
+    }
+
+    /// preload_url fetches url, decodes it in the background and caches the Sound as name.
+    /// The format is guessed from the extension of url.
+    pub fn preload_url(&self, assets: &Assets, name: &str, url: &str) -> AssetHandle {
+        let url = String::from(url);
+        self.preload_with(assets, name, async move {
+            let format = url.rsplit('.').next().unwrap_or_default().to_lowercase();
+            Ok((fetch_bytes(&url).await?, format))
+        })
+    }
+
+    fn preload_with(
+        &self,
+        assets: &Assets,
+        name: &str,
+        bytes: impl std::future::Future<Output = Result<(Vec<u8>, String)>> + 'static,
+    ) -> AssetHandle {
+        if let Some(handle) = self.inner.preloads.borrow().get(name) {
+            return handle.clone();
+        }
+        let audio = self.clone();
+        let key = String::from(name);
+        let handle = assets.register_future(async move {
+            let sound = async {
+                let (bytes, format) = bytes.await?;
+                audio.decode(&bytes, &format).await
+            }
+            .await
+            .with_context(|| format!("failed to preload audio: {}", key))?;
+            audio.inner.buffers.borrow_mut().insert(key, sound.buffer);
+            Ok(())
+        });
+        self.inner
+            .preloads
+            .borrow_mut()
+            .insert(String::from(name), handle.clone());
+        handle
+    }
+
+    /// sound returns the Sound preloaded as name. It returns None until the Sound is decoded.
+    pub fn sound(&self, name: &str) -> Option<Sound> {
+        self.inner.buffers.borrow().get(name).map(|buffer| Sound {
+            buffer: buffer.clone(),
+            audio: self.clone(),
+        })
+    }
+
+    /// play_by_name plays the Sound preloaded as name on SFX_BUS.
+    /// It returns an error if the Sound is not preloaded or not decoded yet.
+    pub fn play_by_name(&self, name: &str) -> Result<()> {
+        self.sound(name)
+            .with_context(|| format!("audio: {} is not preloaded", name))?
+            .play()
+    }
+
+    /// tone renders tone into a Sound, which plays like decoded ones.
+    pub fn tone(&self, tone: &Tone) -> Result<Sound> {
+        let context = self.context()?;
+        let sample_rate = context.sample_rate();
+        // An AudioBuffer needs at least one sample.
+        let mut samples = tone.render(sample_rate as f64);
+        if samples.is_empty() {
+            samples.push(0.0);
+        }
+        let buffer = context
+            .create_buffer(1, samples.len() as u32, sample_rate)
+            .map_err(|e| anyhow!("failed to create an audio buffer: {:?}", e))?;
+        buffer
+            .copy_to_channel(&samples, 0)
+            .map_err(|e| anyhow!("failed to render the tone: {:?}", e))?;
+        Ok(Sound {
+            buffer,
+            audio: self.clone(),
+        })
+    }
+
+    /// decode decodes bytes of format, e.g. "ogg", "mp3" or "wav", into a Sound.
+    pub async fn decode(&self, bytes: &[u8], format: &str) -> Result<Sound> {
+        let context = self.context()?;
+        let array_buffer = js_sys::Uint8Array::from(bytes).buffer();
+        let promise = context
+            .decode_audio_data(&array_buffer)
+            .map_err(|e| anyhow!("failed to decode {} audio: {:?}", format, e))?;
+        let buffer = JsFuture::from(promise)
+            .await
+            .map_err(|e| anyhow!("failed to decode {} audio: {:?}", format, e))?
+            .dyn_into::<web_sys::AudioBuffer>()
+            .map_err(|_| {
+                anyhow!(
+                    "failed to decode {} audio: the result is not an AudioBuffer",
+                    format
+                )
+            })?;
+        Ok(Sound {
+            buffer,
+            audio: self.clone(),
+        })
+    }
+}
+
+/// Sound is decoded audio which can be played any number of times, even overlapping.
+#[derive(Debug, Clone)]
+pub struct Sound {
+    buffer: web_sys::AudioBuffer,
+    audio: AudioSystem,
+}
+
+impl Sound {
+    /// new decodes bytes of format, e.g. "ogg", "mp3" or "wav", with the AudioContext of audio.
+    pub async fn new(audio: &AudioSystem, bytes: &[u8], format: &str) -> Result<Self> {
+        audio.decode(bytes, format).await
+    }
+
+    /// play starts the sound from the beginning on SFX_BUS. Each call plays a new copy of the
+    /// sound. Before the browser allows audio, the sound is queued until it is unlocked.
+    pub fn play(&self) -> Result<()> {
+        self.play_on(SFX_BUS)
+    }
+
+    /// play_on starts the sound from the beginning on bus, e.g. "ui" or "voice".
+    pub fn play_on(&self, bus: &str) -> Result<()> {
+        self.play_with_on(&PlayParams::new(), bus)
+    }
+
+    /// play_with starts the sound on SFX_BUS with params, e.g. panned to the left or pitched up.
+    /// Browsers without StereoPannerNode play the sound without panning.
+    pub fn play_with(&self, params: &PlayParams) -> Result<()> {
+        self.play_with_on(params, SFX_BUS)
+    }
+
+    /// play_with_on starts the sound on bus with params.
+    pub fn play_with_on(&self, params: &PlayParams, bus: &str) -> Result<()> {
+        let sound = self.clone();
+        let params = params.clamped();
+        let bus = String::from(bus);
+        self.audio
+            .play_when_unlocked(move || sound.start_on(&params, &bus, None))
+    }
+
+    // start_on plays the region of (start, duration) in seconds, or the whole sound if None.
+    pub(crate) fn start_on(
+        &self,
+        params: &PlayParams,
+        bus: &str,
+        region: Option<(f64, f64)>,
+    ) -> Result<()> {
+        let bus = self.audio.bus_node(bus)?;
+        self.start_into(params, &bus, region)
+    }
+
+    // start_into plays the region like start_on, connected to destination instead of a bus.
+    pub(crate) fn start_into(
+        &self,
+        params: &PlayParams,
+        destination: &web_sys::AudioNode,
+        region: Option<(f64, f64)>,
+    ) -> Result<()> {
+        let context = self.audio.context()?;
+        let source = context
+            .create_buffer_source()
+            .map_err(|e| anyhow!("failed to create a buffer source: {:?}", e))?;
+        source.set_buffer(Some(&self.buffer));
+        source.playback_rate().set_value(params.pitch as f32);
+        let gain = context
+            .create_gain()
+            .map_err(|e| anyhow!("failed to create a gain node: {:?}", e))?;
+        gain.gain().set_value(params.volume as f32);
+        source
+            .connect_with_audio_node(&gain)
+            .map_err(|e| anyhow!("failed to connect the buffer source: {:?}", e))?;
+        // StereoPannerNode is missing in some old browsers. Then the sound is not panned.
+        let panner = if params.pan != 0.0 {
+            context.create_stereo_panner().ok()
+        } else {
+            None
+        };
+        match panner {
+            Some(panner) => {
+                panner.pan().set_value(params.pan as f32);
+                gain.connect_with_audio_node(&panner)
+                    .and_then(|_| panner.connect_with_audio_node(destination))
+            }
+            None => gain.connect_with_audio_node(destination),
+        }
+        .map_err(|e| anyhow!("failed to connect the buffer source: {:?}", e))?;
+        match region {
+            Some((start, duration)) => {
+                source.start_with_when_and_grain_offset_and_grain_duration(0.0, start, duration)
+            }
+            None => source.start(),
+        }
+        .map_err(|e| anyhow!("failed to play the sound: {:?}", e))?;
+        Ok(())
+    }
+
+    /// duration returns the length of the sound in seconds.
+    pub fn duration(&self) -> f64 {
+        self.buffer.duration()
+    }
+
+    pub(crate) fn buffer(&self) -> &web_sys::AudioBuffer {
+        &self.buffer
+    }
+}
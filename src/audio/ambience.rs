@@ -0,0 +1,217 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use anyhow::{anyhow, Result};
+
+use crate::audio::{AudioSystem, PlayParams, Sound, AMBIENCE_BUS};
+use crate::random::Rng;
+
+/// Ambience plays a looping base sound, e.g. wind, and one-shots at random intervals,
+/// e.g. bird chirps, on AMBIENCE_BUS. Call update every frame to schedule the one-shots.
+/// The same seed and the same frame times play the same ambience, e.g. in replays.
+#[derive(Debug)]
+pub struct Ambience {
+    audio: AudioSystem,
+    base: Option<Sound>,
+    layers: Vec<Layer>,
+    volume: f64,
+    rng: Rng,
+    playing: Rc<Cell<bool>>,
+    // The number of starts and stops, so that a start waiting for audio to be unlocked knows
+    // whether it was stopped, or superseded by a later start, in the meantime.
+    generation: Rc<Cell<u64>>,
+    gain: Option<web_sys::GainNode>,
+    base_source: Rc<RefCell<Option<web_sys::AudioBufferSourceNode>>>,
+}
+
+#[derive(Debug)]
+struct Layer {
+    sound: Sound,
+    min_interval_ms: f64,
+    max_interval_ms: f64,
+    min_volume: f64,
+    max_volume: f64,
+    remaining_ms: f64,
+}
+
+impl Ambience {
+    /// new returns an Ambience without sounds.
+    pub fn new(audio: &AudioSystem) -> Self {
+        Self {
+            audio: audio.clone(),
+            base: None,
+            layers: vec![],
+            volume: 1.0,
+            rng: Rng::seeded(0),
+            playing: Rc::new(Cell::new(false)),
+            generation: Rc::new(Cell::new(0)),
+            gain: None,
+            base_source: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    /// base sets the sound which loops while the Ambience plays.
+    pub fn base(mut self, sound: &Sound) -> Self {
+        self.base = Some(sound.clone());
+        self
+    }
+
+    /// one_shot adds a sound played every min_interval_ms to max_interval_ms milliseconds at a
+    /// volume from min_volume to max_volume.
+    pub fn one_shot(
+        mut self,
+        sound: &Sound,
+        min_interval_ms: f64,
+        max_interval_ms: f64,
+        min_volume: f64,
+        max_volume: f64,
+    ) -> Self {
+        let min_interval_ms = min_interval_ms.max(0.0);
+        let min_volume = min_volume.clamp(0.0, 1.0);
+        self.layers.push(Layer {
+            sound: sound.clone(),
+            min_interval_ms,
+            max_interval_ms: max_interval_ms.max(min_interval_ms),
+            min_volume,
+            max_volume: max_volume.clamp(min_volume, 1.0),
+            remaining_ms: 0.0,
+        });
+        self
+    }
+
+    /// seed sets the seed of the random intervals and volumes.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::seeded(seed);
+        self
+    }
+
+    /// start plays the base sound and starts scheduling the one-shots.
+    /// Before the browser allows audio, the base sound waits until it is unlocked.
+    pub fn start(&mut self) -> Result<()> {
+        if self.is_playing() {
+            return Ok(());
+        }
+        let gain = self.gain()?;
+        self.playing.set(true);
+        let generation = self.generation.get() + 1;
+        self.generation.set(generation);
+        for i in 0..self.layers.len() {
+            self.layers[i].remaining_ms = self.next_interval(i);
+        }
+        let base = match &self.base {
+            Some(base) => base.clone(),
+            None => return Ok(()),
+        };
+        let audio = self.audio.clone();
+        let current_generation = Rc::clone(&self.generation);
+        let base_source = Rc::clone(&self.base_source);
+        self.audio.play_when_unlocked(move || {
+            // The Ambience may be stopped, or stopped and started again, before audio is
+            // unlocked. Only the latest start plays the base sound then.
+            if current_generation.get() != generation {
+                return Ok(());
+            }
+            let source = audio
+                .context()?
+                .create_buffer_source()
+                .map_err(|e| anyhow!("failed to create a buffer source: {:?}", e))?;
+            source.set_buffer(Some(base.buffer()));
+            source.set_loop(true);
+            source
+                .connect_with_audio_node(&gain)
+                .map_err(|e| anyhow!("failed to connect the ambience: {:?}", e))?;
+            source
+                .start()
+                .map_err(|e| anyhow!("failed to play the ambience: {:?}", e))?;
+            base_source.replace(Some(source));
+            Ok(())
+        })
+    }
+
+    /// stop stops the base sound and the scheduling. Playing one-shots are played to the end.
+    pub fn stop(&mut self) -> Result<()> {
+        self.playing.set(false);
+        self.generation.set(self.generation.get() + 1);
+        if let Some(source) = self.base_source.take() {
+            source
+                .stop()
+                .map_err(|e| anyhow!("failed to stop the ambience: {:?}", e))?;
+        }
+        Ok(())
+    }
+
+    /// is_playing returns true between start and stop.
+    pub fn is_playing(&self) -> bool {
+        self.playing.get()
+    }
+
+    /// update advances the one-shots by dt milliseconds and plays the due ones.
+    /// One-shots due before audio is unlocked are skipped instead of queued.
+    pub fn update(&mut self, dt: f64) -> Result<()> {
+        if !self.is_playing() {
+            return Ok(());
+        }
+        for i in 0..self.layers.len() {
+            self.layers[i].remaining_ms -= dt.max(0.0);
+            if self.layers[i].remaining_ms > 0.0 {
+                continue;
+            }
+            // A long frame plays a one-shot once instead of a burst.
+            self.layers[i].remaining_ms = self.next_interval(i);
+            let layer = &self.layers[i];
+            let volume = self.rng.range_f64(layer.min_volume..layer.max_volume);
+            if !self.audio.is_unlocked() {
+                continue;
+            }
+            let gain = self.gain()?;
+            let params = PlayParams {
+                volume,
+                ..PlayParams::new()
+            };
+            self.layers[i].sound.start_into(&params, &gain, None)?;
+        }
+        Ok(())
+    }
+
+    /// volume returns the overall volume of the Ambience from 0.0 to 1.0.
+    pub fn volume(&self) -> f64 {
+        self.volume
+    }
+
+    /// set_volume changes the overall volume of the Ambience, including playing sounds.
+    /// The volume is clamped to 0.0..=1.0.
+    pub fn set_volume(&mut self, volume: f64) {
+        self.volume = if volume.is_nan() {
+            0.0
+        } else {
+            volume.clamp(0.0, 1.0)
+        };
+        if let Some(gain) = &self.gain {
+            gain.gain().set_value(self.volume as f32);
+        }
+    }
+
+    // gain returns the gain node of the overall volume, creating it if needed.
+    fn gain(&mut self) -> Result<web_sys::GainNode> {
+        if let Some(gain) = &self.gain {
+            return Ok(gain.clone());
+        }
+        let gain = self
+            .audio
+            .context()?
+            .create_gain()
+            .map_err(|e| anyhow!("failed to create a gain node: {:?}", e))?;
+        gain.gain().set_value(self.volume as f32);
+        let bus = self.audio.bus_node(AMBIENCE_BUS)?;
+        gain.connect_with_audio_node(&bus)
+            .map_err(|e| anyhow!("failed to connect the ambience: {:?}", e))?;
+        self.gain = Some(gain.clone());
+        Ok(gain)
+    }
+
+    fn next_interval(&mut self, i: usize) -> f64 {
+        let layer = &self.layers[i];
+        self.rng
+            .range_f64(layer.min_interval_ms..layer.max_interval_ms)
+    }
+}
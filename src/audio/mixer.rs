@@ -0,0 +1,129 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{anyhow, Result};
+
+// Mixer keeps the volumes and the gain nodes which all the sounds are routed through:
+// source -> bus gain -> master gain -> destination.
+#[derive(Debug)]
+pub(crate) struct Mixer {
+    master_volume: f64,
+    muted: bool,
+    bus_volumes: HashMap<String, f64>,
+    muted_buses: HashSet<String>,
+    master: Option<web_sys::GainNode>,
+    buses: HashMap<String, web_sys::GainNode>,
+}
+
+impl Mixer {
+    pub(crate) fn new() -> Self {
+        Self {
+            master_volume: 1.0,
+            muted: false,
+            bus_volumes: HashMap::new(),
+            muted_buses: HashSet::new(),
+            master: None,
+            buses: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn master_volume(&self) -> f64 {
+        self.master_volume
+    }
+
+    pub(crate) fn set_master_volume(&mut self, volume: f64) {
+        self.master_volume = clamp_volume(volume);
+        self.apply_master();
+    }
+
+    pub(crate) fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    pub(crate) fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+        self.apply_master();
+    }
+
+    pub(crate) fn bus_volume(&self, bus: &str) -> f64 {
+        self.bus_volumes.get(bus).copied().unwrap_or(1.0)
+    }
+
+    pub(crate) fn set_bus_volume(&mut self, bus: &str, volume: f64) {
+        self.bus_volumes
+            .insert(String::from(bus), clamp_volume(volume));
+        self.apply_bus(bus);
+    }
+
+    pub(crate) fn is_bus_muted(&self, bus: &str) -> bool {
+        self.muted_buses.contains(bus)
+    }
+
+    pub(crate) fn set_bus_muted(&mut self, bus: &str, muted: bool) {
+        if muted {
+            self.muted_buses.insert(String::from(bus));
+        } else {
+            self.muted_buses.remove(bus);
+        }
+        self.apply_bus(bus);
+    }
+
+    // bus_node returns the gain node of bus, creating it and the master node if needed.
+    pub(crate) fn bus_node(
+        &mut self,
+        context: &web_sys::AudioContext,
+        bus: &str,
+    ) -> Result<web_sys::GainNode> {
+        if let Some(node) = self.buses.get(bus) {
+            return Ok(node.clone());
+        }
+        let master = match &self.master {
+            Some(master) => master.clone(),
+            None => {
+                let master = create_gain(context)?;
+                master
+                    .connect_with_audio_node(&context.destination())
+                    .map_err(|e| anyhow!("failed to connect the master gain: {:?}", e))?;
+                self.master = Some(master.clone());
+                self.apply_master();
+                master
+            }
+        };
+        let node = create_gain(context)?;
+        node.connect_with_audio_node(&master)
+            .map_err(|e| anyhow!("failed to connect the {} bus: {:?}", bus, e))?;
+        self.buses.insert(String::from(bus), node.clone());
+        self.apply_bus(bus);
+        Ok(node)
+    }
+
+    fn apply_bus(&self, bus: &str) {
+        if let Some(node) = self.buses.get(bus) {
+            let volume = if self.is_bus_muted(bus) {
+                0.0
+            } else {
+                self.bus_volume(bus)
+            };
+            node.gain().set_value(volume as f32);
+        }
+    }
+
+    fn apply_master(&self) {
+        if let Some(master) = &self.master {
+            let volume = if self.muted { 0.0 } else { self.master_volume };
+            master.gain().set_value(volume as f32);
+        }
+    }
+}
+
+fn create_gain(context: &web_sys::AudioContext) -> Result<web_sys::GainNode> {
+    context
+        .create_gain()
+        .map_err(|e| anyhow!("failed to create a gain node: {:?}", e))
+}
+
+fn clamp_volume(volume: f64) -> f64 {
+    if volume.is_nan() {
+        return 0.0;
+    }
+    volume.clamp(0.0, 1.0)
+}
@@ -0,0 +1,155 @@
+use anyhow::{anyhow, Result};
+
+use crate::audio::{AudioSystem, Sound, MUSIC_BUS};
+
+/// MusicOptions decides how Music plays a track.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MusicOptions {
+    looped: bool,
+    fade_in_ms: f64,
+    loop_region: Option<(f64, f64)>,
+}
+
+impl MusicOptions {
+    /// new returns MusicOptions which loop the whole track without fading in.
+    pub fn new() -> Self {
+        Self {
+            looped: true,
+            fade_in_ms: 0.0,
+            loop_region: None,
+        }
+    }
+
+    /// looped repeats the track. It is true by default.
+    pub fn looped(mut self, looped: bool) -> Self {
+        self.looped = looped;
+        self
+    }
+
+    /// fade_in_ms fades in the track, and fades out the previous one, in milliseconds.
+    pub fn fade_in_ms(mut self, fade_in_ms: f64) -> Self {
+        self.fade_in_ms = fade_in_ms.max(0.0);
+        self
+    }
+
+    /// loop_region loops only from start to end in seconds, e.g. after an intro which plays once.
+    pub fn loop_region(mut self, start: f64, end: f64) -> Self {
+        self.loop_region = Some((start, end));
+        self
+    }
+}
+
+impl Default for MusicOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Track {
+    source: web_sys::AudioBufferSourceNode,
+    gain: web_sys::GainNode,
+}
+
+/// Music is a handle of a music channel, which plays one track at a time.
+/// It is returned by AudioSystem::music and AudioSystem::music_channel.
+#[derive(Debug, Clone)]
+pub struct Music {
+    audio: AudioSystem,
+    channel: String,
+}
+
+impl Music {
+    pub(crate) const DEFAULT_CHANNEL: &'static str = "music";
+
+    pub(crate) fn new(audio: AudioSystem, channel: &str) -> Self {
+        Self {
+            audio,
+            channel: String::from(channel),
+        }
+    }
+
+    /// play starts sound on the channel, and fades out the previous track in the same time
+    /// as the new track fades in. Before the browser allows audio, the track is queued until
+    /// it is unlocked, e.g. for the music of the title screen.
+    pub fn play(&self, sound: &Sound, options: MusicOptions) -> Result<()> {
+        let music = self.clone();
+        let sound = sound.clone();
+        self.audio
+            .play_when_unlocked(move || music.start(&sound, options))
+    }
+
+    fn start(&self, sound: &Sound, options: MusicOptions) -> Result<()> {
+        let context = self.audio.context()?;
+        self.stop(options.fade_in_ms)?;
+
+        let source = context
+            .create_buffer_source()
+            .map_err(|e| anyhow!("failed to create a buffer source: {:?}", e))?;
+        source.set_buffer(Some(sound.buffer()));
+        source.set_loop(options.looped);
+        if let Some((start, end)) = options.loop_region {
+            source.set_loop_start(start);
+            source.set_loop_end(end);
+        }
+        let gain = context
+            .create_gain()
+            .map_err(|e| anyhow!("failed to create a gain node: {:?}", e))?;
+        let bus = self.audio.bus_node(MUSIC_BUS)?;
+        source
+            .connect_with_audio_node(&gain)
+            .and_then(|_| gain.connect_with_audio_node(&bus))
+            .map_err(|e| anyhow!("failed to connect the music: {:?}", e))?;
+        if options.fade_in_ms > 0.0 {
+            let now = context.current_time();
+            let volume = gain.gain();
+            volume
+                .set_value_at_time(0.0, now)
+                .and_then(|_| {
+                    volume.linear_ramp_to_value_at_time(1.0, now + options.fade_in_ms / 1000.0)
+                })
+                .map_err(|e| anyhow!("failed to fade in the music: {:?}", e))?;
+        }
+        source
+            .start()
+            .map_err(|e| anyhow!("failed to play the music: {:?}", e))?;
+        self.audio
+            .inner
+            .music
+            .borrow_mut()
+            .insert(self.channel.clone(), Track { source, gain });
+        Ok(())
+    }
+
+    /// stop fades out the track on the channel in fade_out_ms milliseconds and stops it.
+    pub fn stop(&self, fade_out_ms: f64) -> Result<()> {
+        let track = match self.audio.inner.music.borrow_mut().remove(&self.channel) {
+            Some(track) => track,
+            None => return Ok(()),
+        };
+        let context = self.audio.context()?;
+        let now = context.current_time();
+        let end = now + fade_out_ms.max(0.0) / 1000.0;
+        let volume = track.gain.gain();
+        volume
+            .cancel_scheduled_values(now)
+            .and_then(|_| volume.set_value_at_time(volume.value(), now))
+            .and_then(|_| volume.linear_ramp_to_value_at_time(0.0, end))
+            .map_err(|e| anyhow!("failed to fade out the music: {:?}", e))?;
+        track
+            .source
+            .stop_with_when(end)
+            .map_err(|e| anyhow!("failed to stop the music: {:?}", e))?;
+        Ok(())
+    }
+
+    /// crossfade_to fades the current track into sound, which loops, in ms milliseconds.
+    pub fn crossfade_to(&self, sound: &Sound, ms: f64) -> Result<()> {
+        self.play(sound, MusicOptions::new().fade_in_ms(ms))
+    }
+
+    /// is_playing returns true when the channel has a track.
+    pub fn is_playing(&self) -> bool {
+        self.audio.inner.music.borrow().contains_key(&self.channel)
+    }
+}
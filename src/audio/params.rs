@@ -0,0 +1,67 @@
+use crate::render::Position;
+
+/// PlayParams decides how Sound::play_with plays a copy of the sound.
+/// Out-of-range values are clamped when the sound is played.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlayParams {
+    /// pan is from -1.0 (left) to 1.0 (right). 0.0 is the center.
+    pub pan: f64,
+    /// volume is from 0.0 to 1.0, relative to the bus volume.
+    pub volume: f64,
+    /// pitch is the playback rate from 0.25 to 4.0. 1.0 is the original pitch and speed.
+    pub pitch: f64,
+}
+
+impl PlayParams {
+    const MIN_PITCH: f64 = 0.25;
+    const MAX_PITCH: f64 = 4.0;
+
+    /// new returns PlayParams which play the sound as it is.
+    pub fn new() -> Self {
+        Self {
+            pan: 0.0,
+            volume: 1.0,
+            pitch: 1.0,
+        }
+    }
+
+    /// at returns PlayParams for a sound at source heard at listener, e.g. the player or the
+    /// center of the Viewport. The sound pans with the horizontal offset, and fades out
+    /// linearly until it is silent at max_distance.
+    pub fn at(source: &Position, listener: &Position, max_distance: f64) -> Self {
+        if max_distance <= 0.0 {
+            return Self::new();
+        }
+        let dx = source.dx() - listener.dx();
+        let dy = source.dy() - listener.dy();
+        let distance = (dx * dx + dy * dy).sqrt();
+        Self {
+            pan: dx / max_distance,
+            volume: 1.0 - distance / max_distance,
+            pitch: 1.0,
+        }
+        .clamped()
+    }
+
+    // clamped returns the params in the valid ranges. NaN falls back to the default value.
+    pub(crate) fn clamped(&self) -> Self {
+        let clamp = |value: f64, min: f64, max: f64, default: f64| {
+            if value.is_nan() {
+                default
+            } else {
+                value.clamp(min, max)
+            }
+        };
+        Self {
+            pan: clamp(self.pan, -1.0, 1.0, 0.0),
+            volume: clamp(self.volume, 0.0, 1.0, 0.0),
+            pitch: clamp(self.pitch, Self::MIN_PITCH, Self::MAX_PITCH, 1.0),
+        }
+    }
+}
+
+impl Default for PlayParams {
+    fn default() -> Self {
+        Self::new()
+    }
+}
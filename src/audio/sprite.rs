@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, ensure, Result};
+
+use crate::audio::{PlayParams, Sound, SFX_BUS};
+
+/// AudioSprite is a Sound which packs many short effects, like SpriteStore packs sprites.
+/// Each effect is a named region of (start, duration) in seconds.
+#[derive(Debug, Clone)]
+pub struct AudioSprite {
+    sound: Sound,
+    regions: HashMap<String, (f64, f64)>,
+}
+
+impl AudioSprite {
+    /// new returns an AudioSprite of sound with regions, e.g. `"jump" => (0.0, 0.4)`.
+    /// It returns an error if a region is out of the sound.
+    pub fn new(sound: Sound, regions: HashMap<String, (f64, f64)>) -> Result<Self> {
+        for (name, &(start, duration)) in &regions {
+            ensure!(
+                start >= 0.0 && duration > 0.0 && start + duration <= sound.duration(),
+                "region: {} of ({}, {}) is out of the sound of {} seconds",
+                name,
+                start,
+                duration,
+                sound.duration()
+            );
+        }
+        Ok(Self { sound, regions })
+    }
+
+    /// from_json returns an AudioSprite of sound with regions loaded from a manifest,
+    /// e.g. `{"jump":[0.0,0.4],"coin":[0.5,0.3]}`.
+    #[cfg(feature = "serde")]
+    pub fn from_json(sound: Sound, json: &str) -> Result<Self> {
+        let regions = serde_json::from_str(json)
+            .map_err(|e| anyhow!("invalid audio sprite manifest: {}", e))?;
+        Self::new(sound, regions)
+    }
+
+    /// play starts the region of name on SFX_BUS. Regions can be played overlapping.
+    pub fn play(&self, name: &str) -> Result<()> {
+        self.play_with_on(name, &PlayParams::new(), SFX_BUS)
+    }
+
+    /// play_with starts the region of name on SFX_BUS with params.
+    pub fn play_with(&self, name: &str, params: &PlayParams) -> Result<()> {
+        self.play_with_on(name, params, SFX_BUS)
+    }
+
+    /// play_with_on starts the region of name on bus with params.
+    /// It returns an error if the region does not exist.
+    pub fn play_with_on(&self, name: &str, params: &PlayParams, bus: &str) -> Result<()> {
+        let region = self.region(name)?;
+        let sound = self.sound.clone();
+        let params = params.clamped();
+        let bus = String::from(bus);
+        self.sound
+            .audio
+            .play_when_unlocked(move || sound.start_on(&params, &bus, Some(region)))
+    }
+
+    /// region returns (start, duration) in seconds of name.
+    pub fn region(&self, name: &str) -> Result<(f64, f64)> {
+        self.regions
+            .get(name)
+            .copied()
+            .ok_or_else(|| anyhow!("audio sprite region: {} does not exist", name))
+    }
+
+    /// names returns the names of the regions.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.regions.keys().map(|name| name.as_str())
+    }
+
+    /// sound returns the packed Sound.
+    pub fn sound(&self) -> &Sound {
+        &self.sound
+    }
+}
@@ -0,0 +1,152 @@
+use crate::random::Rng;
+
+/// Waveform is the shape of the wave which Tone repeats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Waveform {
+    /// Square is the classic chiptune sound.
+    Square,
+    /// Sine is a soft and pure sound.
+    Sine,
+    /// Triangle is between Square and Sine, e.g. for bass.
+    Triangle,
+    /// Sawtooth is a buzzy sound.
+    Sawtooth,
+    /// Noise is random, e.g. for hits and explosions. Higher frequencies sound brighter.
+    Noise,
+}
+
+/// Tone is a procedural sound effect, like the blips of sfxr.
+/// Turn it into a Sound with AudioSystem::tone.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tone {
+    waveform: Waveform,
+    frequency_hz: f64,
+    slide_to_hz: Option<f64>,
+    duration_ms: f64,
+    attack_ms: f64,
+    decay_ms: f64,
+    volume: f64,
+}
+
+impl Tone {
+    /// new returns a Tone of waveform at frequency_hz for duration_ms milliseconds.
+    pub fn new(waveform: Waveform, frequency_hz: f64, duration_ms: f64) -> Self {
+        Self {
+            waveform,
+            frequency_hz: frequency_hz.max(0.0),
+            slide_to_hz: None,
+            duration_ms: duration_ms.max(0.0),
+            attack_ms: 0.0,
+            decay_ms: 0.0,
+            volume: 0.5,
+        }
+    }
+
+    /// jump is a rising blip.
+    pub fn jump() -> Self {
+        Self::new(Waveform::Square, 300.0, 180.0)
+            .slide_to(700.0)
+            .envelope(5.0, 80.0)
+    }
+
+    /// hit is a short burst of noise.
+    pub fn hit() -> Self {
+        Self::new(Waveform::Noise, 2000.0, 120.0)
+            .slide_to(500.0)
+            .envelope(0.0, 110.0)
+    }
+
+    /// pickup is a high chime.
+    pub fn pickup() -> Self {
+        Self::new(Waveform::Square, 900.0, 150.0)
+            .slide_to(1400.0)
+            .envelope(0.0, 100.0)
+    }
+
+    /// explosion is a long rumble of noise.
+    pub fn explosion() -> Self {
+        Self::new(Waveform::Noise, 800.0, 700.0)
+            .slide_to(60.0)
+            .envelope(0.0, 650.0)
+            .volume(0.8)
+    }
+
+    /// slide_to changes the frequency linearly to frequency_hz by the end.
+    pub fn slide_to(mut self, frequency_hz: f64) -> Self {
+        self.slide_to_hz = Some(frequency_hz.max(0.0));
+        self
+    }
+
+    /// envelope fades in for attack_ms and fades out for the last decay_ms milliseconds.
+    pub fn envelope(mut self, attack_ms: f64, decay_ms: f64) -> Self {
+        self.attack_ms = attack_ms.max(0.0);
+        self.decay_ms = decay_ms.max(0.0);
+        self
+    }
+
+    /// volume sets the peak volume from 0.0 to 1.0. The default is 0.5.
+    pub fn volume(mut self, volume: f64) -> Self {
+        self.volume = volume.clamp(0.0, 1.0);
+        self
+    }
+
+    /// duration_ms returns the length of the tone in milliseconds.
+    pub fn duration_ms(&self) -> f64 {
+        self.duration_ms
+    }
+
+    /// render returns the mono samples of the tone at sample_rate in Hz.
+    pub fn render(&self, sample_rate: f64) -> Vec<f32> {
+        let len = (self.duration_ms / 1000.0 * sample_rate).round() as usize;
+        let mut samples = Vec::with_capacity(len);
+        let mut phase: f64 = 0.0;
+        // A fixed seed renders the same noise every time.
+        let mut rng = Rng::seeded(0);
+        let mut noise = rng.range_f64(-1.0..1.0);
+        for i in 0..len {
+            let progress = i as f64 / len as f64;
+            let frequency = match self.slide_to_hz {
+                Some(to) => self.frequency_hz + (to - self.frequency_hz) * progress,
+                None => self.frequency_hz,
+            };
+            let wave = match self.waveform {
+                Waveform::Square => {
+                    if phase < 0.5 {
+                        1.0
+                    } else {
+                        -1.0
+                    }
+                }
+                Waveform::Sine => (phase * std::f64::consts::TAU).sin(),
+                Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+                Waveform::Sawtooth => phase * 2.0 - 1.0,
+                Waveform::Noise => noise,
+            };
+            let time_ms = i as f64 * 1000.0 / sample_rate;
+            samples.push((wave * self.envelope_at(time_ms) * self.volume) as f32);
+
+            phase += frequency / sample_rate;
+            if phase >= 1.0 {
+                phase = phase.fract();
+                // Noise holds a random value for each period, so the frequency shapes its color.
+                noise = rng.range_f64(-1.0..1.0);
+            }
+        }
+        samples
+    }
+
+    // envelope_at returns the gain from 0.0 to 1.0 at time_ms.
+    fn envelope_at(&self, time_ms: f64) -> f64 {
+        let attack = if self.attack_ms > 0.0 {
+            (time_ms / self.attack_ms).min(1.0)
+        } else {
+            1.0
+        };
+        let decay = if self.decay_ms > 0.0 {
+            ((self.duration_ms - time_ms) / self.decay_ms).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        attack.min(decay)
+    }
+}
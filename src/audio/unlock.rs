@@ -0,0 +1,65 @@
+use std::fmt;
+
+use crate::app::Listener;
+
+// Unlock holds the plays requested before the browser allows audio, i.e. before the first user
+// gesture, and the callback fired when audio is unlocked.
+pub(crate) struct Unlock {
+    pub(crate) unlocked: bool,
+    // The listeners for user gestures, which are kept until the context is running.
+    pub(crate) listeners: Vec<Listener>,
+    pub(crate) queue: Vec<Box<dyn FnOnce() -> anyhow::Result<()>>>,
+    pub(crate) on_unlock: Option<Box<dyn FnOnce()>>,
+}
+
+impl Unlock {
+    // The number of plays kept until unlocked. Later plays are dropped with a warning.
+    pub(crate) const MAX_QUEUED: usize = 32;
+    // The events which may count as a user activation. Browsers differ, e.g. a touch counts on
+    // pointerup or touchend rather than pointerdown, and Escape does not count at all, so audio
+    // is resumed on each of them until it is running.
+    pub(crate) const GESTURES: [&'static str; 5] = [
+        "keydown",
+        "mousedown",
+        "pointerdown",
+        "pointerup",
+        "touchend",
+    ];
+
+    pub(crate) fn new() -> Self {
+        Self {
+            unlocked: false,
+            listeners: vec![],
+            queue: vec![],
+            on_unlock: None,
+        }
+    }
+
+    // stop_listening removes the listeners for user gestures.
+    pub(crate) fn stop_listening(&mut self) {
+        if self.listeners.is_empty() {
+            return;
+        }
+        if let Some(window) = web_sys::window() {
+            for listener in self.listeners.drain(..) {
+                listener.remove(&window);
+            }
+        }
+    }
+}
+
+impl Drop for Unlock {
+    fn drop(&mut self) {
+        self.stop_listening();
+    }
+}
+
+impl fmt::Debug for Unlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Unlock")
+            .field("unlocked", &self.unlocked)
+            .field("listeners", &self.listeners.len())
+            .field("queue", &self.queue.len())
+            .finish()
+    }
+}
@@ -0,0 +1,52 @@
+use anyhow::{anyhow, Context, Result};
+
+use crate::render::{sprite_data_url, Sprite};
+
+/// set_title sets the title of the page, e.g. "(3 lives) My Game" to show the score in the tab.
+pub fn set_title(title: &str) -> Result<()> {
+    document()?.set_title(title);
+    Ok(())
+}
+
+/// set_favicon_from_sprite sets sprite as the icon of the page, e.g. to show that it is the
+/// turn of the player in a game played in another tab. The sprite must be loaded.
+/// It returns an error if the atlas of the sprite is from another origin without CORS, because
+/// such an image cannot be exported.
+pub fn set_favicon_from_sprite(sprite: &Sprite) -> Result<()> {
+    let data_url = sprite_data_url(sprite)?;
+    set_favicon(&data_url)
+}
+
+// set_favicon updates the href of the first icon link of the page, or appends a new one to the
+// head if the page has none.
+fn set_favicon(url: &str) -> Result<()> {
+    let document = document()?;
+    let link = match document
+        .query_selector("link[rel~='icon']")
+        .map_err(|e| anyhow!("failed to find the icon link: {:?}", e))?
+    {
+        Some(link) => link,
+        None => {
+            let head = document
+                .query_selector("head")
+                .map_err(|e| anyhow!("failed to find the head: {:?}", e))?
+                .context("the document has no head for the icon link")?;
+            let link = document
+                .create_element("link")
+                .map_err(|e| anyhow!("failed to create the icon link: {:?}", e))?;
+            link.set_attribute("rel", "icon")
+                .map_err(|e| anyhow!("failed to set the rel of the icon link: {:?}", e))?;
+            head.append_child(&link)
+                .map_err(|e| anyhow!("failed to append the icon link: {:?}", e))?;
+            link
+        }
+    };
+    link.set_attribute("href", url)
+        .map_err(|e| anyhow!("failed to set the href of the icon link: {:?}", e))
+}
+
+fn document() -> Result<web_sys::Document> {
+    web_sys::window()
+        .and_then(|window| window.document())
+        .context("no document exists")
+}
@@ -0,0 +1,313 @@
+use anyhow::{ensure, Result};
+
+use crate::render::{Position, Sprite};
+
+/// Rect is an axis-aligned box from the top-left corner at (x, y) to
+/// (x + width, y + height).
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "RectData")
+)]
+pub struct Rect {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+impl Rect {
+    /// new returns a Rect. It returns an error if width or height is negative or not finite.
+    pub fn new(x: f64, y: f64, width: f64, height: f64) -> Result<Self> {
+        ensure!(
+            x.is_finite() && y.is_finite(),
+            "the position of a rect should be finite: ({}, {})",
+            x,
+            y
+        );
+        ensure!(
+            width.is_finite() && width >= 0.0 && height.is_finite() && height >= 0.0,
+            "the size of a rect should not be negative: {}x{}",
+            width,
+            height
+        );
+        Ok(Self {
+            x,
+            y,
+            width,
+            height,
+        })
+    }
+
+    /// of_sprite returns the bounding box of sprite at position.
+    pub fn of_sprite(sprite: &Sprite, position: &Position) -> Self {
+        Self {
+            x: position.dx(),
+            y: position.dy(),
+            width: sprite.width(),
+            height: sprite.height(),
+        }
+    }
+
+    /// x returns the left edge.
+    pub fn x(&self) -> f64 {
+        self.x
+    }
+
+    /// y returns the top edge.
+    pub fn y(&self) -> f64 {
+        self.y
+    }
+
+    /// width returns the width.
+    pub fn width(&self) -> f64 {
+        self.width
+    }
+
+    /// height returns the height.
+    pub fn height(&self) -> f64 {
+        self.height
+    }
+
+    /// is_empty returns true when width or height is 0.
+    pub fn is_empty(&self) -> bool {
+        self.width == 0.0 || self.height == 0.0
+    }
+
+    /// right returns the right edge.
+    pub fn right(&self) -> f64 {
+        self.x + self.width
+    }
+
+    /// bottom returns the bottom edge.
+    pub fn bottom(&self) -> f64 {
+        self.y + self.height
+    }
+}
+
+// RectData is a Rect before the validation of Rect::new, so that deserialized rects are valid.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RectData {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RectData> for Rect {
+    type Error = anyhow::Error;
+
+    fn try_from(data: RectData) -> Result<Self> {
+        Self::new(data.x, data.y, data.width, data.height)
+    }
+}
+
+/// Circle is a circle of radius around the center at (x, y).
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "CircleData")
+)]
+pub struct Circle {
+    x: f64,
+    y: f64,
+    radius: f64,
+}
+
+impl Circle {
+    /// new returns a Circle. It returns an error if radius is negative or not finite.
+    pub fn new(x: f64, y: f64, radius: f64) -> Result<Self> {
+        ensure!(
+            x.is_finite() && y.is_finite(),
+            "the center of a circle should be finite: ({}, {})",
+            x,
+            y
+        );
+        ensure!(
+            radius.is_finite() && radius >= 0.0,
+            "the radius of a circle should not be negative: {}",
+            radius
+        );
+        Ok(Self { x, y, radius })
+    }
+
+    /// x returns the x of the center.
+    pub fn x(&self) -> f64 {
+        self.x
+    }
+
+    /// y returns the y of the center.
+    pub fn y(&self) -> f64 {
+        self.y
+    }
+
+    /// radius returns the radius.
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct CircleData {
+    x: f64,
+    y: f64,
+    radius: f64,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<CircleData> for Circle {
+    type Error = anyhow::Error;
+
+    fn try_from(data: CircleData) -> Result<Self> {
+        Self::new(data.x, data.y, data.radius)
+    }
+}
+
+/// Hit is the result of swept_rect_rect.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hit {
+    time: f64,
+    normal: (f64, f64),
+}
+
+impl Hit {
+    /// time returns when the moving rect hits, from 0.0 (the start) to 1.0 (the full velocity).
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// normal returns the direction the hit surface faces, e.g. (-1.0, 0.0) for the left side
+    /// of the target. It is (0.0, 0.0) when the rects overlap from the start.
+    pub fn normal(&self) -> (f64, f64) {
+        self.normal
+    }
+}
+
+// Shapes which only touch each other on their edges do not overlap, as Sprite::overlaps_with.
+// Zero-size rects and circles overlap nothing, but swept_rect_rect hits a zero-width wall.
+
+/// rect_rect returns true when a and b overlap.
+pub fn rect_rect(a: &Rect, b: &Rect) -> bool {
+    if a.is_empty() || b.is_empty() {
+        return false;
+    }
+    a.x < b.right() && b.x < a.right() && a.y < b.bottom() && b.y < a.bottom()
+}
+
+/// circle_circle returns true when a and b overlap.
+pub fn circle_circle(a: &Circle, b: &Circle) -> bool {
+    let (dx, dy) = (a.x - b.x, a.y - b.y);
+    let radii = a.radius + b.radius;
+    dx * dx + dy * dy < radii * radii
+}
+
+/// rect_circle returns true when rect and circle overlap.
+pub fn rect_circle(rect: &Rect, circle: &Circle) -> bool {
+    if rect.is_empty() {
+        return false;
+    }
+    let closest_x = circle.x.clamp(rect.x, rect.right());
+    let closest_y = circle.y.clamp(rect.y, rect.bottom());
+    let (dx, dy) = (circle.x - closest_x, circle.y - closest_y);
+    dx * dx + dy * dy < circle.radius * circle.radius
+}
+
+/// point_in_rect returns true when point is in rect. The left and top edges are inside, and the
+/// right and bottom edges are outside, so that a point is in exactly one of adjacent tiles.
+pub fn point_in_rect(point: &Position, rect: &Rect) -> bool {
+    rect.x <= point.dx()
+        && point.dx() < rect.right()
+        && rect.y <= point.dy()
+        && point.dy() < rect.bottom()
+}
+
+/// point_in_circle returns true when point is inside circle, not on its edge.
+pub fn point_in_circle(point: &Position, circle: &Circle) -> bool {
+    let (dx, dy) = (point.dx() - circle.x, point.dy() - circle.y);
+    dx * dx + dy * dy < circle.radius * circle.radius
+}
+
+/// segment_rect returns where the segment from start to end first touches rect, from 0.0 at
+/// start to 1.0 at end, or None if it misses. A segment starting inside rect returns 0.0.
+pub fn segment_rect(start: &Position, end: &Position, rect: &Rect) -> Option<f64> {
+    if rect.is_empty() {
+        return None;
+    }
+    let (entry, exit) = slab_times(
+        (start.dx(), start.dy()),
+        (end.dx() - start.dx(), end.dy() - start.dy()),
+        rect,
+        true,
+    )?;
+    let entry = entry.0.max(entry.1);
+    let exit = exit.0.min(exit.1);
+    if entry > exit || exit < 0.0 || entry > 1.0 {
+        return None;
+    }
+    Some(entry.max(0.0))
+}
+
+/// swept_rect_rect returns when moving, moved by velocity in a frame, hits target, so that fast
+/// objects do not tunnel through thin walls. Move by velocity * hit.time() to stop at the hit.
+/// Sliding along or moving away from a touching target is not a hit.
+pub fn swept_rect_rect(moving: &Rect, velocity: (f64, f64), target: &Rect) -> Option<Hit> {
+    // The top-left corner of moving hits target expanded by the size of moving.
+    let expanded = Rect {
+        x: target.x - moving.width,
+        y: target.y - moving.height,
+        width: target.width + moving.width,
+        height: target.height + moving.height,
+    };
+    let (entry, exit) = slab_times((moving.x, moving.y), velocity, &expanded, false)?;
+    let entry_time = entry.0.max(entry.1);
+    let exit_time = exit.0.min(exit.1);
+    if entry_time >= exit_time || exit_time <= 0.0 || entry_time > 1.0 {
+        return None;
+    }
+    if entry_time < 0.0 {
+        return Some(Hit {
+            time: 0.0,
+            normal: (0.0, 0.0),
+        });
+    }
+    let normal = if entry.0 > entry.1 {
+        (-velocity.0.signum(), 0.0)
+    } else {
+        (0.0, -velocity.1.signum())
+    };
+    Some(Hit {
+        time: entry_time,
+        normal,
+    })
+}
+
+// slab_times returns the times when the ray from origin along direction enters and exits the
+// slabs of rect on x and y. On an axis without movement, the origin must be between the edges,
+// including them when inclusive.
+fn slab_times(
+    origin: (f64, f64),
+    direction: (f64, f64),
+    rect: &Rect,
+    inclusive: bool,
+) -> Option<((f64, f64), (f64, f64))> {
+    let axis = |origin: f64, direction: f64, min: f64, max: f64| {
+        if direction == 0.0 {
+            let inside = if inclusive {
+                min <= origin && origin <= max
+            } else {
+                min < origin && origin < max
+            };
+            return inside.then_some((f64::NEG_INFINITY, f64::INFINITY));
+        }
+        let (t1, t2) = ((min - origin) / direction, (max - origin) / direction);
+        Some((t1.min(t2), t1.max(t2)))
+    };
+    let (entry_x, exit_x) = axis(origin.0, direction.0, rect.x, rect.right())?;
+    let (entry_y, exit_y) = axis(origin.1, direction.1, rect.y, rect.bottom())?;
+    Some(((entry_x, entry_y), (exit_x, exit_y)))
+}
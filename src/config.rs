@@ -0,0 +1,152 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use anyhow::{anyhow, Context, Result};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// query_params returns the parameters of the query string of the page, e.g. level and debug of
+/// `?level=7&debug=1`. It is empty when the page has no query string.
+pub fn query_params() -> HashMap<String, String> {
+    let search = web_sys::window()
+        .and_then(|window| window.location().search().ok())
+        .unwrap_or_default();
+    parse_query(&search)
+}
+
+/// get_u32 returns the query parameter name as u32, or None if it is missing or not a number.
+pub fn get_u32(name: &str) -> Option<u32> {
+    query_params().get(name)?.trim().parse().ok()
+}
+
+/// get_bool returns the query parameter name as bool, or None if it is missing or not a bool.
+/// "1", "true", "yes", "on" and a parameter without a value, e.g. `?debug`, are true.
+/// "0", "false", "no" and "off" are false.
+pub fn get_bool(name: &str) -> Option<bool> {
+    match query_params()
+        .get(name)?
+        .trim()
+        .to_ascii_lowercase()
+        .as_str()
+    {
+        "" | "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// parse_query parses a query string, with or without the leading '?', into the parameters.
+/// Names and values are percent-decoded, and '+' is decoded as a space.
+/// A name without '=' has an empty value, and the last one wins if a name is repeated.
+pub fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .strip_prefix('?')
+        .unwrap_or(query)
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .filter_map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let name = percent_decode(&name.replace('+', " "));
+            if name.is_empty() {
+                return None;
+            }
+            Some((name, percent_decode(&value.replace('+', " "))))
+        })
+        .collect()
+}
+
+// percent_decode decodes %XX escapes. Invalid escapes are kept as they are, and invalid UTF-8 is
+// replaced with U+FFFD.
+// Only a query decodes '+' as a space, so it replaces '+' before the escapes, e.g. of "%2B".
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let high = bytes.get(i + 1).and_then(|&byte| hex_value(byte));
+                let low = bytes.get(i + 2).and_then(|&byte| hex_value(byte));
+                match (high, low) {
+                    (Some(high), Some(low)) => {
+                        decoded.push(high << 4 | low);
+                        i += 2;
+                    }
+                    _ => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn hex_value(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|digit| digit as u8)
+}
+
+/// get_hash returns the hash of the page without the leading '#', percent-decoded, e.g. a shared
+/// puzzle state. '+' is kept as it is. It is empty when the page has no hash.
+pub fn get_hash() -> String {
+    let hash = web_sys::window()
+        .and_then(|window| window.location().hash().ok())
+        .unwrap_or_default();
+    percent_decode(hash.strip_prefix('#').unwrap_or(&hash))
+}
+
+/// set_hash replaces the hash of the page with hash, e.g. to share the current puzzle state.
+/// get_hash returns the same hash. It does not reload the page, but a HashListener is notified.
+pub fn set_hash(hash: &str) -> Result<()> {
+    // The browser escapes e.g. spaces but not '%', so escape it for get_hash to decode.
+    web_sys::window()
+        .context("no global window exists")?
+        .location()
+        .set_hash(&hash.replace('%', "%25"))
+        .map_err(|e| anyhow!("failed to set the hash: {:?}", e))
+}
+
+/// HashListener queues the hash every time it changes, e.g. by the back button of the browser.
+/// Call poll in App::update to handle the changes on the game loop.
+/// The listener is removed when the HashListener is dropped.
+#[derive(Debug)]
+pub struct HashListener {
+    window: web_sys::Window,
+    hashes: Rc<RefCell<Vec<String>>>,
+    handler: Closure<dyn FnMut()>,
+}
+
+impl HashListener {
+    /// new starts listening to hashchange.
+    pub fn new() -> Result<Self> {
+        let window = web_sys::window().context("no global window exists")?;
+        let hashes = Rc::new(RefCell::new(vec![]));
+        let handler_hashes = Rc::clone(&hashes);
+        let handler = Closure::wrap(Box::new(move || {
+            handler_hashes.borrow_mut().push(get_hash());
+        }) as Box<dyn FnMut()>);
+        window
+            .add_event_listener_with_callback("hashchange", handler.as_ref().unchecked_ref())
+            .map_err(|e| anyhow!("failed to listen to hashchange: {:?}", e))?;
+        Ok(Self {
+            window,
+            hashes,
+            handler,
+        })
+    }
+
+    /// poll returns the hashes since the last poll, from the oldest, and clears them.
+    pub fn poll(&self) -> Vec<String> {
+        self.hashes.borrow_mut().drain(..).collect()
+    }
+}
+
+impl Drop for HashListener {
+    fn drop(&mut self) {
+        let _ = self.window.remove_event_listener_with_callback(
+            "hashchange",
+            self.handler.as_ref().unchecked_ref(),
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use std::fmt;
+
+#[cfg(feature = "console")]
+use wasm_bindgen::JsValue;
+
+/// log_info! writes a message to console.log with the module path, e.g.
+/// `log_info!("score: {}", score)`. It does nothing without the `console` feature.
+#[macro_export]
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        $crate::console::info(module_path!(), format_args!($($arg)*))
+    };
+}
+
+/// log_warn! writes a message to console.warn with the module path.
+/// It does nothing without the `console` feature.
+#[macro_export]
+macro_rules! log_warn {
+    ($($arg:tt)*) => {
+        $crate::console::warn(module_path!(), format_args!($($arg)*))
+    };
+}
+
+/// log_error! writes a message to console.error with the module path.
+/// It does nothing without the `console` feature.
+#[macro_export]
+macro_rules! log_error {
+    ($($arg:tt)*) => {
+        $crate::console::error(module_path!(), format_args!($($arg)*))
+    };
+}
+
+/// info is called by log_info!.
+#[inline]
+pub fn info(module_path: &str, args: fmt::Arguments<'_>) {
+    #[cfg(feature = "console")]
+    web_sys::console::log_1(&message(module_path, args));
+    #[cfg(not(feature = "console"))]
+    let _ = (module_path, args);
+}
+
+/// warn is called by log_warn!.
+#[inline]
+pub fn warn(module_path: &str, args: fmt::Arguments<'_>) {
+    #[cfg(feature = "console")]
+    web_sys::console::warn_1(&message(module_path, args));
+    #[cfg(not(feature = "console"))]
+    let _ = (module_path, args);
+}
+
+/// error is called by log_error!.
+#[inline]
+pub fn error(module_path: &str, args: fmt::Arguments<'_>) {
+    #[cfg(feature = "console")]
+    web_sys::console::error_1(&message(module_path, args));
+    #[cfg(not(feature = "console"))]
+    let _ = (module_path, args);
+}
+
+#[cfg(feature = "console")]
+fn message(module_path: &str, args: fmt::Arguments<'_>) -> JsValue {
+    JsValue::from_str(&format!("[{}] {}", module_path, args))
+}
+
+/// install_panic_hook forwards panic messages to console.error.
+/// It is installed only once even if you have already installed console_error_panic_hook.
+pub(crate) fn install_panic_hook() {
+    #[cfg(feature = "console")]
+    console_error_panic_hook::set_once();
+}
@@ -0,0 +1,78 @@
+use anyhow::Result;
+
+use crate::render::{Position, Renderer, Sprite};
+use crate::test::DrawCommand;
+
+/// draw_sprite_bounds outlines the bounding box of sprite at position with a CSS color.
+/// It is a no-op in release builds.
+pub fn draw_sprite_bounds(
+    renderer: &Renderer,
+    sprite: &Sprite,
+    position: Position,
+    color: &str,
+) -> Result<()> {
+    if !cfg!(debug_assertions) {
+        return Ok(());
+    }
+    let recorded = renderer.record(|| DrawCommand::StrokeRect {
+        dx: position.dx(),
+        dy: position.dy(),
+        dw: sprite.width(),
+        dh: sprite.height(),
+        color: String::from(color),
+    });
+    if recorded {
+        return Ok(());
+    }
+
+    let context = renderer.context();
+    context.save();
+    context.set_stroke_style(&color.into());
+    context.stroke_rect(
+        position.dx(),
+        position.dy(),
+        sprite.width(),
+        sprite.height(),
+    );
+    context.restore();
+    Ok(())
+}
+
+/// draw_position_marker draws a cross-hair of radius at position with a CSS color.
+/// It is a no-op in release builds.
+pub fn draw_position_marker(
+    renderer: &Renderer,
+    position: Position,
+    radius: f64,
+    color: &str,
+) -> Result<()> {
+    if !cfg!(debug_assertions) {
+        return Ok(());
+    }
+    let (x, y) = (position.dx(), position.dy());
+    draw_line(renderer, x - radius, y, x + radius, y, color);
+    draw_line(renderer, x, y - radius, x, y + radius, color);
+    Ok(())
+}
+
+fn draw_line(renderer: &Renderer, x1: f64, y1: f64, x2: f64, y2: f64, color: &str) {
+    let recorded = renderer.record(|| DrawCommand::Line {
+        x1,
+        y1,
+        x2,
+        y2,
+        color: String::from(color),
+    });
+    if recorded {
+        return;
+    }
+
+    let context = renderer.context();
+    context.save();
+    context.set_stroke_style(&color.into());
+    context.begin_path();
+    context.move_to(x1, y1);
+    context.line_to(x2, y2);
+    context.stroke();
+    context.restore();
+}
@@ -1,6 +1,7 @@
 //! Retrospector is a super simple 2D game engine for WebAssembly.
 //! The minimum implementation is the following:
 //! ```
+//! use anyhow::Result;
 //! use wasm_bindgen::prelude::*;
 //!
 //! use retrospector::app::{run, App, AppConfig};
@@ -11,7 +12,9 @@
 //! pub fn start() -> Result<(), JsValue> {
 //!     let app = TestMock::new();
 //!     let config = AppConfig::new(String::from("canvas"), 352.0, 352.0);
-//!     run(app, config)
+//!     // run returns an AppHandle. You can keep it to control the game from outside the loop.
+//!     run(app, config)?;
+//!     Ok(())
 //! }
 //!
 //! struct TestMock {
@@ -34,7 +37,7 @@
 //!
 //! // Implement App trait for your game objects.
 //! impl App for TestMock {
-//!     fn update(&mut self, elapsed_time: f64, key_event: &KeyEvent) {
+//!     fn update(&mut self, elapsed_time: f64, key_event: &KeyEvent) -> Result<()> {
 //!         self.elapsed_time = elapsed_time;
 //!         if key_event.is_arrow_right_down() {
 //!             self.text += "->";
@@ -42,31 +45,35 @@
 //!         if key_event.is_enter_down() {
 //!             self.text = String::from("test hello");
 //!         }
+//!         Ok(())
 //!     }
 //!
-//!     fn render(&self, renderer: &Renderer) {
+//!     // Errors returned from update and render are logged to the console by default.
+//!     fn render(&self, renderer: &Renderer) -> Result<()> {
 //!         // Before rendering, clear the canvas first.
 //!         clear(renderer);
 //!
 //!         // You can get a sprite from a sprite_store.
-//!         if let Ok(sprite) = self.sprite_store.sprite(0) {
-//!             // You can use the draw_image to draw sprites at the specified position on the canvas.
-//!             draw_image(renderer, &sprite, Position::new(0.0, 0.0));
-//!         }
+//!         let sprite = self.sprite_store.sprite(0)?;
+//!         // You can use the draw_image to draw sprites at the specified position on the canvas.
+//!         draw_image(renderer, &sprite, Position::new(0.0, 0.0))?;
 //!
 //!         let text = format!("elapsed time: {} ms", self.elapsed_time);
 //!         // You can use your own rendering functions.
 //!         fill_text(renderer, &text, Position::new(0.0, 50.0));
 //!         fill_text(renderer, &self.text, Position::new(0.0, 100.0));
+//!         Ok(())
 //!     }
 //! }
 //!
 //! // You can define your own rendering functions like this.
+//! // try_context is None on the headless renderer of TestHarness.
 //! fn fill_text(renderer: &Renderer, text: &str, position: Position) {
-//!     renderer
-//!         .context()
-//!         .fill_text(text, position.dx(), position.dy())
-//!         .unwrap();
+//!     if let Some(context) = renderer.try_context() {
+//!         context
+//!             .fill_text(text, position.dx(), position.dy())
+//!             .unwrap();
+//!     }
 //! }
 //! ```
 
@@ -79,8 +86,68 @@
 /// It also has run function. That is an entry point for starting the game.
 pub mod app;
 
+/// assets is about loading resources, e.g. fetching files before the first frame.
+pub mod assets;
+
+/// audio is about playing sounds with the Web Audio API.
+pub mod audio;
+
+/// browser is about the page around the game, e.g. the title and the favicon.
+pub mod browser;
+
+/// collision is about overlap tests of rects, circles, points and segments, e.g. hit boxes.
+pub mod collision;
+
+/// config is about configuring the game from the URL, e.g. `?level=7&debug=1` and the hash.
+pub mod config;
+
+/// console is about logging to the browser console.
+/// Enable the `console` feature to use log_info!, log_warn!, log_error!, and the panic hook.
+pub mod console;
+
+/// debug is about visualizing sprites and positions while debugging.
+/// Its helpers are no-ops in release builds.
+pub mod debug;
+
+/// net is about talking to a server over WebSocket, e.g. in multiplayer prototypes.
+pub mod net;
+
+/// overlay is about HTML over the canvas, e.g. menus which screen readers can read.
+pub mod overlay;
+
+/// pathfinding is about finding paths on grids, e.g. for enemies chasing the player.
+pub mod pathfinding;
+
+/// random is about reproducible pseudo-random numbers, e.g. for replays and daily challenges.
+pub mod random;
+
+/// recording is about keeping the last frames of the canvas, e.g. to share clips as a zip of PNGs
+/// or, with the `gif` feature, as an animated GIF.
+pub mod recording;
+
 /// render is about rendering module.
 pub mod render;
 
+/// scene is about switching screens of the game, e.g. title, gameplay and pause.
+/// SceneStack implements App, so you can pass it to run.
+pub mod scene;
+
+/// schedule is about running callbacks after a delay or at an interval, e.g. spawning enemies.
+pub mod schedule;
+
+/// storage is about saving settings and progress in localStorage.
+/// Enable the `serde` feature to save and load any serializable values.
+pub mod storage;
+
+/// test is about testing apps without a browser.
+/// It has MockRenderer, which records draw commands, and TestHarness, which drives an App.
+pub mod test;
+
+/// timing is about measuring frames, e.g. fps and frame time.
+pub mod timing;
+
+/// tween is about easing values over time, e.g. sliding menus, camera pans and score counters.
+pub mod tween;
+
 /// update is about data-updating module. It is almost about KeyEvent.
 pub mod update;
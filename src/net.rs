@@ -0,0 +1,353 @@
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::rc::{Rc, Weak};
+
+use anyhow::{anyhow, ensure, Context, Result};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+use crate::app::drop_later;
+
+/// Message is an event of a Socket, returned by Socket::poll in the order it happened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    /// Open tells that the connection is established, including after a reconnection.
+    Open,
+    /// Text is a text message from the server.
+    Text(String),
+    /// Binary is a binary message from the server.
+    Binary(Vec<u8>),
+    /// Close tells that the connection is closed with the code and the reason from the server.
+    Close {
+        /// code is the status code of the close, e.g. 1000 for a normal closure.
+        code: u16,
+        /// reason is the text the server sent with the close. It may be empty.
+        reason: String,
+    },
+    /// Error tells that the connection failed. Close follows it.
+    Error(String),
+}
+
+/// SocketState is the state of the connection of a Socket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SocketState {
+    /// Connecting is before the connection is established, including while waiting to reconnect.
+    Connecting,
+    /// Open is while messages can be sent.
+    Open,
+    /// Closing is after close is called until the connection is closed.
+    Closing,
+    /// Closed is after the connection is closed for good.
+    Closed,
+}
+
+/// Socket is a WebSocket client whose events are queued instead of calling back into the app.
+/// Call poll in App::update to handle the messages on the game loop. It is cheap to clone, and
+/// the connection is closed when the last clone is dropped.
+#[derive(Debug, Clone)]
+pub struct Socket {
+    inner: Rc<SocketInner>,
+}
+
+#[derive(Debug)]
+struct SocketInner {
+    url: String,
+    socket: RefCell<Option<web_sys::WebSocket>>,
+    handlers: RefCell<Option<Handlers>>,
+    queue: RefCell<VecDeque<Message>>,
+    max_queued: Cell<usize>,
+    dropped: Cell<usize>,
+    backoff: Option<Backoff>,
+    next_delay_ms: Cell<f64>,
+    reconnect_timer: Cell<Option<i32>>,
+    closed_by_user: Cell<bool>,
+}
+
+#[derive(Debug)]
+struct Handlers {
+    on_open: Closure<dyn FnMut()>,
+    on_message: Closure<dyn FnMut(web_sys::MessageEvent)>,
+    on_error: Closure<dyn FnMut()>,
+    on_close: Closure<dyn FnMut(web_sys::CloseEvent)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Backoff {
+    initial_delay_ms: f64,
+    max_delay_ms: f64,
+}
+
+impl Socket {
+    const DEFAULT_MAX_QUEUED: usize = 1024;
+
+    /// connect starts connecting to url, e.g. "wss://example.com/game".
+    /// Message::Open is queued when the connection is established.
+    pub fn connect(url: &str) -> Result<Self> {
+        Self::start(url, None)
+    }
+
+    /// connect_with_backoff is connect which reconnects when the connection is closed by the
+    /// server or lost. The delay starts from initial_delay_ms and doubles up to max_delay_ms
+    /// until a connection is established again.
+    pub fn connect_with_backoff(
+        url: &str,
+        initial_delay_ms: f64,
+        max_delay_ms: f64,
+    ) -> Result<Self> {
+        ensure!(
+            initial_delay_ms.is_finite() && initial_delay_ms > 0.0,
+            "initial_delay_ms: {} should be positive and finite",
+            initial_delay_ms
+        );
+        ensure!(
+            max_delay_ms.is_finite() && max_delay_ms >= initial_delay_ms,
+            "max_delay_ms: {} should be at least initial_delay_ms: {}",
+            max_delay_ms,
+            initial_delay_ms
+        );
+        Self::start(
+            url,
+            Some(Backoff {
+                initial_delay_ms,
+                max_delay_ms,
+            }),
+        )
+    }
+
+    fn start(url: &str, backoff: Option<Backoff>) -> Result<Self> {
+        let inner = Rc::new(SocketInner {
+            url: String::from(url),
+            socket: RefCell::new(None),
+            handlers: RefCell::new(None),
+            queue: RefCell::new(VecDeque::new()),
+            max_queued: Cell::new(Self::DEFAULT_MAX_QUEUED),
+            dropped: Cell::new(0),
+            backoff,
+            next_delay_ms: Cell::new(backoff.map_or(0.0, |backoff| backoff.initial_delay_ms)),
+            reconnect_timer: Cell::new(None),
+            closed_by_user: Cell::new(false),
+        });
+        open(&inner)?;
+        Ok(Self { inner })
+    }
+
+    /// poll returns the queued messages and clears the queue.
+    pub fn poll(&self) -> Vec<Message> {
+        self.inner.queue.borrow_mut().drain(..).collect()
+    }
+
+    /// send_text sends text to the server. It returns an error unless the state is Open.
+    pub fn send_text(&self, text: &str) -> Result<()> {
+        self.open_socket()?
+            .send_with_str(text)
+            .map_err(|e| anyhow!("failed to send to {}: {:?}", self.inner.url, e))
+    }
+
+    /// send_bytes sends bytes to the server. It returns an error unless the state is Open.
+    pub fn send_bytes(&self, bytes: &[u8]) -> Result<()> {
+        self.open_socket()?
+            .send_with_u8_array(bytes)
+            .map_err(|e| anyhow!("failed to send to {}: {:?}", self.inner.url, e))
+    }
+
+    /// state returns the state of the connection.
+    pub fn state(&self) -> SocketState {
+        match &*self.inner.socket.borrow() {
+            Some(socket) => match socket.ready_state() {
+                web_sys::WebSocket::CONNECTING => SocketState::Connecting,
+                web_sys::WebSocket::OPEN => SocketState::Open,
+                web_sys::WebSocket::CLOSING => SocketState::Closing,
+                _ if self.inner.reconnect_timer.get().is_some() => SocketState::Connecting,
+                _ => SocketState::Closed,
+            },
+            None => SocketState::Closed,
+        }
+    }
+
+    /// close closes the connection and stops reconnecting. Message::Close is queued when the
+    /// connection is closed.
+    pub fn close(&self) -> Result<()> {
+        self.inner.closed_by_user.set(true);
+        self.inner.cancel_reconnect();
+        match &*self.inner.socket.borrow() {
+            Some(socket) => socket
+                .close()
+                .map_err(|e| anyhow!("failed to close {}: {:?}", self.inner.url, e)),
+            None => Ok(()),
+        }
+    }
+
+    /// url returns the url passed to connect.
+    pub fn url(&self) -> &str {
+        &self.inner.url
+    }
+
+    /// set_max_queued caps the number of the queued messages, 1024 by default, so that the queue
+    /// does not grow without bound while the app does not poll, e.g. while it is paused.
+    /// The oldest messages are dropped when the queue is full.
+    pub fn set_max_queued(&self, max_queued: usize) {
+        self.inner.max_queued.set(max_queued.max(1));
+        self.inner.trim_queue();
+    }
+
+    /// dropped_messages returns the number of the messages dropped because the queue was full.
+    pub fn dropped_messages(&self) -> usize {
+        self.inner.dropped.get()
+    }
+
+    fn open_socket(&self) -> Result<web_sys::WebSocket> {
+        ensure!(
+            self.state() == SocketState::Open,
+            "failed to send to {}: the socket is not open",
+            self.inner.url
+        );
+        self.inner
+            .socket
+            .borrow()
+            .clone()
+            .context("the socket is not open")
+    }
+}
+
+impl SocketInner {
+    fn push(&self, message: Message) {
+        self.queue.borrow_mut().push_back(message);
+        self.trim_queue();
+    }
+
+    fn trim_queue(&self) {
+        let mut queue = self.queue.borrow_mut();
+        while queue.len() > self.max_queued.get() {
+            queue.pop_front();
+            self.dropped.set(self.dropped.get() + 1);
+        }
+    }
+
+    // schedule_reconnect opens a new connection after the delay of the backoff, if any.
+    fn schedule_reconnect(self: &Rc<Self>) {
+        let backoff = match self.backoff {
+            Some(backoff) if !self.closed_by_user.get() => backoff,
+            _ => return,
+        };
+        let window = match web_sys::window() {
+            Some(window) => window,
+            None => return,
+        };
+        let delay_ms = self.next_delay_ms.get();
+        self.next_delay_ms
+            .set((delay_ms * 2.0).min(backoff.max_delay_ms));
+        let inner = Rc::downgrade(self);
+        let callback = Closure::once_into_js(move || {
+            if let Some(inner) = inner.upgrade() {
+                inner.reconnect_timer.set(None);
+                if let Err(e) = open(&inner) {
+                    inner.push(Message::Error(format!("{:#}", e)));
+                    inner.schedule_reconnect();
+                }
+            }
+        });
+        match window.set_timeout_with_callback_and_timeout_and_arguments_0(
+            callback.unchecked_ref(),
+            delay_ms.round() as i32,
+        ) {
+            Ok(id) => self.reconnect_timer.set(Some(id)),
+            Err(e) => self.push(Message::Error(format!(
+                "failed to schedule reconnecting to {}: {:?}",
+                self.url, e
+            ))),
+        }
+    }
+
+    fn cancel_reconnect(&self) {
+        if let (Some(id), Some(window)) = (self.reconnect_timer.take(), web_sys::window()) {
+            window.clear_timeout_with_handle(id);
+        }
+    }
+}
+
+impl Drop for SocketInner {
+    fn drop(&mut self) {
+        self.cancel_reconnect();
+        if let Some(socket) = self.socket.take() {
+            detach(&socket);
+            let _ = socket.close();
+        }
+        // The last clone may be dropped inside a handler, which must not be freed while running.
+        if let (Some(handlers), Some(window)) = (self.handlers.take(), web_sys::window()) {
+            drop_later(&window, handlers);
+        }
+    }
+}
+
+// open connects a new WebSocket to the url of inner, replacing the previous one.
+fn open(inner: &Rc<SocketInner>) -> Result<()> {
+    let socket = web_sys::WebSocket::new(&inner.url)
+        .map_err(|e| anyhow!("failed to connect to {}: {:?}", inner.url, e))?;
+    socket.set_binary_type(web_sys::BinaryType::Arraybuffer);
+    let handlers = Handlers::new(Rc::downgrade(inner));
+    socket.set_onopen(Some(handlers.on_open.as_ref().unchecked_ref()));
+    socket.set_onmessage(Some(handlers.on_message.as_ref().unchecked_ref()));
+    socket.set_onerror(Some(handlers.on_error.as_ref().unchecked_ref()));
+    socket.set_onclose(Some(handlers.on_close.as_ref().unchecked_ref()));
+    if let Some(previous) = inner.socket.replace(Some(socket)) {
+        detach(&previous);
+    }
+    // open is called from a timeout, not from the handlers, so they are not running now.
+    inner.handlers.replace(Some(handlers));
+    Ok(())
+}
+
+fn detach(socket: &web_sys::WebSocket) {
+    socket.set_onopen(None);
+    socket.set_onmessage(None);
+    socket.set_onerror(None);
+    socket.set_onclose(None);
+}
+
+impl Handlers {
+    fn new(inner: Weak<SocketInner>) -> Self {
+        let open_inner = inner.clone();
+        let on_open = Closure::wrap(Box::new(move || {
+            if let Some(inner) = open_inner.upgrade() {
+                if let Some(backoff) = inner.backoff {
+                    inner.next_delay_ms.set(backoff.initial_delay_ms);
+                }
+                inner.push(Message::Open);
+            }
+        }) as Box<dyn FnMut()>);
+        let message_inner = inner.clone();
+        let on_message = Closure::wrap(Box::new(move |event: web_sys::MessageEvent| {
+            if let Some(inner) = message_inner.upgrade() {
+                let data = event.data();
+                if let Some(text) = data.as_string() {
+                    inner.push(Message::Text(text));
+                } else if let Some(buffer) = data.dyn_ref::<js_sys::ArrayBuffer>() {
+                    inner.push(Message::Binary(js_sys::Uint8Array::new(buffer).to_vec()));
+                }
+            }
+        }) as Box<dyn FnMut(web_sys::MessageEvent)>);
+        let error_inner = inner.clone();
+        let on_error = Closure::wrap(Box::new(move || {
+            if let Some(inner) = error_inner.upgrade() {
+                // The browser hides the cause of WebSocket errors from scripts.
+                let message = format!("the connection to {} failed", inner.url);
+                inner.push(Message::Error(message));
+            }
+        }) as Box<dyn FnMut()>);
+        let on_close = Closure::wrap(Box::new(move |event: web_sys::CloseEvent| {
+            if let Some(inner) = inner.upgrade() {
+                inner.push(Message::Close {
+                    code: event.code(),
+                    reason: event.reason(),
+                });
+                inner.schedule_reconnect();
+            }
+        }) as Box<dyn FnMut(web_sys::CloseEvent)>);
+        Self {
+            on_open,
+            on_message,
+            on_error,
+            on_close,
+        }
+    }
+}
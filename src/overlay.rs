@@ -0,0 +1,273 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use wasm_bindgen::{JsCast, JsValue};
+
+/// Overlay is a layer of HTML over the canvas, e.g. for menus which screen readers can read and
+/// whose text can be selected. Pass it to AppConfigBuilder::overlay, and run creates a div of
+/// the size of the game over the canvas. The div follows the canvas however it is moved or
+/// scaled by CSS, so the coordinates in the overlay are the coordinates of the game.
+/// It is cheap to clone, so keep a clone in your app to change the HTML.
+#[derive(Debug, Clone, Default)]
+pub struct Overlay {
+    inner: Rc<OverlayInner>,
+}
+
+#[derive(Default)]
+struct OverlayInner {
+    html: RefCell<String>,
+    hidden: Cell<bool>,
+    pass_through: Cell<bool>,
+    callbacks: RefCell<HashMap<String, Box<dyn FnMut()>>>,
+    // The ids of the clicked elements, which are dispatched before the next update.
+    clicks: RefCell<Vec<String>>,
+    attached: RefCell<Option<Attached>>,
+}
+
+struct Attached {
+    element: web_sys::HtmlElement,
+    canvas: web_sys::HtmlCanvasElement,
+    game_size: (f64, f64),
+    // The rectangle of the canvas in the document when the div was last moved.
+    synced: Option<(f64, f64, f64, f64)>,
+}
+
+impl fmt::Debug for OverlayInner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OverlayInner")
+            .field("html", &self.html.borrow().len())
+            .field("hidden", &self.hidden.get())
+            .field("pass_through", &self.pass_through.get())
+            .field("callbacks", &self.callbacks.borrow().len())
+            .field("clicks", &self.clicks.borrow())
+            .field("attached", &self.attached.borrow().is_some())
+            .finish()
+    }
+}
+
+impl Overlay {
+    /// new returns an empty, visible Overlay which catches the pointer events over the canvas.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// set_html replaces the content of the overlay with html, e.g. `<button id="start">`.
+    /// Give ids to the elements to handle their clicks with on_click.
+    pub fn set_html(&self, html: &str) {
+        self.inner.html.replace(String::from(html));
+        if let Some(attached) = &*self.inner.attached.borrow() {
+            attached.element.set_inner_html(html);
+        }
+    }
+
+    /// html returns the content of the overlay.
+    pub fn html(&self) -> String {
+        self.inner.html.borrow().clone()
+    }
+
+    /// show shows the overlay.
+    pub fn show(&self) {
+        self.inner.hidden.set(false);
+        self.apply_style();
+    }
+
+    /// hide hides the overlay, and the canvas under it gets the pointer events.
+    pub fn hide(&self) {
+        self.inner.hidden.set(true);
+        self.apply_style();
+    }
+
+    /// is_visible returns true unless the overlay is hidden.
+    pub fn is_visible(&self) -> bool {
+        !self.inner.hidden.get()
+    }
+
+    /// set_pointer_events sets whether the overlay catches the pointer events over the whole
+    /// canvas. It is true by default. With false, the pointer events go through the overlay to
+    /// the canvas, e.g. for a HUD over a game played with the mouse, and its elements cannot be
+    /// clicked either.
+    pub fn set_pointer_events(&self, enabled: bool) {
+        self.inner.pass_through.set(!enabled);
+        self.apply_style();
+    }
+
+    /// on_click sets callback to be called when the element with element_id, or an element in
+    /// it, is clicked. The callback is not called in the event listener but right before the
+    /// next App::update, so it runs between frames like keyboard input does. Capture an
+    /// Rc<Cell<_>> in it to tell the app, e.g. which menu item was chosen.
+    pub fn on_click(&self, element_id: &str, callback: impl FnMut() + 'static) {
+        self.inner
+            .callbacks
+            .borrow_mut()
+            .insert(String::from(element_id), Box::new(callback));
+    }
+
+    /// remove_on_click removes the callback of the element with element_id.
+    pub fn remove_on_click(&self, element_id: &str) {
+        self.inner.callbacks.borrow_mut().remove(element_id);
+    }
+
+    /// click clicks the element with element_id as if it was clicked by the user, e.g. to
+    /// choose a menu item with the keyboard or in tests. Elements without a callback are ignored.
+    pub fn click(&self, element_id: &str) {
+        if self.inner.callbacks.borrow().contains_key(element_id) {
+            self.inner
+                .clicks
+                .borrow_mut()
+                .push(String::from(element_id));
+        }
+    }
+
+    // attach creates the div over canvas, whose game size is game_size, and returns it so that
+    // the caller listens to its clicks.
+    pub(crate) fn attach(
+        &self,
+        document: &web_sys::Document,
+        canvas: &web_sys::HtmlCanvasElement,
+        game_size: (f64, f64),
+    ) -> Result<web_sys::HtmlElement, JsValue> {
+        let body = document
+            .body()
+            .ok_or_else(|| JsValue::from_str("the document has no body for the overlay"))?;
+        let element = document
+            .create_element("div")?
+            .dyn_into::<web_sys::HtmlElement>()
+            .map_err(|_| JsValue::from_str("the overlay is not an HTML element"))?;
+        let style = element.style();
+        style.set_property("position", "absolute")?;
+        style.set_property("width", &format!("{}px", game_size.0))?;
+        style.set_property("height", &format!("{}px", game_size.1))?;
+        style.set_property("overflow", "hidden")?;
+        style.set_property("transform-origin", "0 0")?;
+        element.set_inner_html(&self.inner.html.borrow());
+        body.append_child(&element)?;
+        if let Some(previous) = self.inner.attached.replace(Some(Attached {
+            element: element.clone(),
+            canvas: canvas.clone(),
+            game_size,
+            synced: None,
+        })) {
+            previous.element.remove();
+        }
+        self.apply_style();
+        Ok(element)
+    }
+
+    // detach removes the div, e.g. when the loop is stopped.
+    pub(crate) fn detach(&self) {
+        if let Some(attached) = self.inner.attached.take() {
+            attached.element.remove();
+        }
+    }
+
+    // handle_click queues the click of the closest element with an id which the event
+    // targets in the overlay.
+    pub(crate) fn handle_click(&self, event: &web_sys::Event) {
+        let target = match event
+            .target()
+            .and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+        {
+            Some(target) => target,
+            None => return,
+        };
+        let mut current = target.closest("[id]").ok().flatten();
+        while let Some(element) = current {
+            let inside = match &*self.inner.attached.borrow() {
+                Some(attached) => {
+                    attached.element.contains(Some(&element))
+                        && !attached.element.is_same_node(Some(&element))
+                }
+                None => false,
+            };
+            if !inside {
+                return;
+            }
+            if self.inner.callbacks.borrow().contains_key(&element.id()) {
+                self.click(&element.id());
+                return;
+            }
+            current = element
+                .parent_element()
+                .and_then(|parent| parent.closest("[id]").ok().flatten());
+        }
+    }
+
+    // dispatch_clicks calls the callbacks of the clicks since the last call.
+    pub(crate) fn dispatch_clicks(&self) {
+        let clicks = self.inner.clicks.take();
+        for id in clicks {
+            // The callback is taken out while it runs, so that it can call on_click or click.
+            let callback = self.inner.callbacks.borrow_mut().remove(&id);
+            if let Some(mut callback) = callback {
+                callback();
+                self.inner
+                    .callbacks
+                    .borrow_mut()
+                    .entry(id)
+                    .or_insert(callback);
+            }
+        }
+    }
+
+    // sync moves and scales the div onto the canvas if the canvas moved or was resized.
+    pub(crate) fn sync(&self) {
+        let mut attached = self.inner.attached.borrow_mut();
+        let attached = match &mut *attached {
+            Some(attached) if !self.inner.hidden.get() => attached,
+            _ => return,
+        };
+        let rect = attached.canvas.get_bounding_client_rect();
+        let (scroll_x, scroll_y) = web_sys::window()
+            .map(|window| {
+                (
+                    window.scroll_x().unwrap_or(0.0),
+                    window.scroll_y().unwrap_or(0.0),
+                )
+            })
+            .unwrap_or((0.0, 0.0));
+        let synced = (
+            rect.left() + scroll_x,
+            rect.top() + scroll_y,
+            rect.width(),
+            rect.height(),
+        );
+        if attached.synced == Some(synced) {
+            return;
+        }
+        attached.synced = Some(synced);
+        let (game_width, game_height) = attached.game_size;
+        let scale_x = if game_width > 0.0 {
+            synced.2 / game_width
+        } else {
+            1.0
+        };
+        let scale_y = if game_height > 0.0 {
+            synced.3 / game_height
+        } else {
+            1.0
+        };
+        let style = attached.element.style();
+        let _ = style.set_property("left", &format!("{}px", synced.0));
+        let _ = style.set_property("top", &format!("{}px", synced.1));
+        let _ = style.set_property("transform", &format!("scale({}, {})", scale_x, scale_y));
+    }
+
+    fn apply_style(&self) {
+        if let Some(attached) = &mut *self.inner.attached.borrow_mut() {
+            let style = attached.element.style();
+            let display = if self.inner.hidden.get() { "none" } else { "" };
+            let _ = style.set_property("display", display);
+            let pointer_events = if self.inner.pass_through.get() {
+                "none"
+            } else {
+                "auto"
+            };
+            let _ = style.set_property("pointer-events", pointer_events);
+            // The canvas may have moved while hidden.
+            attached.synced = None;
+        }
+        self.sync();
+    }
+}
@@ -0,0 +1,258 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::f64::consts::SQRT_2;
+
+use crate::render::Grid;
+
+/// PathFinder finds the cheapest paths on a grid of cols x rows by A*, e.g. for enemies chasing
+/// the player around walls. Cells are (col, row) as in Grid and SpriteStore.
+/// It keeps its buffers between searches, so reuse one PathFinder for the same grid.
+#[derive(Debug, Clone)]
+pub struct PathFinder {
+    cols: usize,
+    rows: usize,
+    diagonal: bool,
+    corner_cutting: bool,
+    // The buffers are valid for a cell only when its generation is the current one, so that they
+    // need not be cleared for every search.
+    generation: u32,
+    generations: Vec<u32>,
+    costs: Vec<f64>,
+    came_from: Vec<usize>,
+    closed: Vec<bool>,
+    open: BinaryHeap<Node>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    estimate: f64,
+    cost: f64,
+    index: usize,
+}
+
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Node {}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Node {
+    // BinaryHeap is a max-heap, so the lowest estimate is the greatest. Of equal estimates, the
+    // node which went farther is nearer to the goal.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .estimate
+            .total_cmp(&self.estimate)
+            .then(self.cost.total_cmp(&other.cost))
+    }
+}
+
+impl PathFinder {
+    /// new returns a PathFinder for a grid of cols x rows which moves in 4 directions.
+    pub fn new(cols: usize, rows: usize) -> Self {
+        let cells = cols * rows;
+        Self {
+            cols,
+            rows,
+            diagonal: false,
+            corner_cutting: false,
+            generation: 0,
+            generations: vec![0; cells],
+            costs: vec![0.0; cells],
+            came_from: vec![0; cells],
+            closed: vec![false; cells],
+            open: BinaryHeap::new(),
+        }
+    }
+
+    /// diagonal allows moving in 8 directions. A diagonal step costs √2 times a straight one.
+    /// It is false by default.
+    pub fn diagonal(mut self, diagonal: bool) -> Self {
+        self.diagonal = diagonal;
+        self
+    }
+
+    /// corner_cutting allows diagonal steps past a blocked corner. It is false by default, so
+    /// that a diagonal step needs both of the cells beside it to be walkable.
+    pub fn corner_cutting(mut self, corner_cutting: bool) -> Self {
+        self.corner_cutting = corner_cutting;
+        self
+    }
+
+    /// find returns the cheapest path from start to goal, both included, or None if there is no
+    /// path. cost returns the cost to enter the cell at col and row, or None if it is blocked.
+    /// Costs below 1.0 count as 1.0, so that the search stays correct and fast.
+    pub fn find(
+        &mut self,
+        start: (usize, usize),
+        goal: (usize, usize),
+        cost: impl Fn(usize, usize) -> Option<f64>,
+    ) -> Option<Vec<(usize, usize)>> {
+        if !self.contains(start) || !self.contains(goal) {
+            return None;
+        }
+        cost(goal.0, goal.1)?;
+        self.next_generation();
+        let start_index = self.index(start);
+        let goal_index = self.index(goal);
+        self.visit(start_index, 0.0, start_index);
+        self.open.push(Node {
+            estimate: self.heuristic(start, goal),
+            cost: 0.0,
+            index: start_index,
+        });
+        while let Some(node) = self.open.pop() {
+            if node.index == goal_index {
+                self.open.clear();
+                return Some(self.path(goal_index));
+            }
+            if self.closed[node.index] || node.cost > self.costs[node.index] {
+                continue;
+            }
+            self.closed[node.index] = true;
+            let cell = self.cell(node.index);
+            for &(dc, dr) in self.directions() {
+                let next = match self.neighbor(cell, dc, dr) {
+                    Some(next) => next,
+                    None => continue,
+                };
+                let next_index = self.index(next);
+                if self.is_visited(next_index) && self.closed[next_index] {
+                    continue;
+                }
+                let step = match cost(next.0, next.1) {
+                    Some(step) => step.max(1.0),
+                    None => continue,
+                };
+                let diagonal = dc != 0 && dr != 0;
+                if diagonal && !self.corner_cutting {
+                    let side_a = cost(next.0, cell.1).is_some();
+                    let side_b = cost(cell.0, next.1).is_some();
+                    if !side_a || !side_b {
+                        continue;
+                    }
+                }
+                let next_cost = node.cost + if diagonal { step * SQRT_2 } else { step };
+                if self.is_visited(next_index) && self.costs[next_index] <= next_cost {
+                    continue;
+                }
+                self.visit(next_index, next_cost, node.index);
+                self.open.push(Node {
+                    estimate: next_cost + self.heuristic(next, goal),
+                    cost: next_cost,
+                    index: next_index,
+                });
+            }
+        }
+        None
+    }
+
+    /// find_in_grid is find on a grid of walkable cells, where true is walkable and every step
+    /// costs the same. It returns None if the grid is not of the size of the PathFinder.
+    pub fn find_in_grid(
+        &mut self,
+        walkable: &Grid<bool>,
+        start: (usize, usize),
+        goal: (usize, usize),
+    ) -> Option<Vec<(usize, usize)>> {
+        if walkable.cols() != self.cols || walkable.rows() != self.rows {
+            return None;
+        }
+        self.find(start, goal, |col, row| match walkable.get(col, row) {
+            Some(true) => Some(1.0),
+            _ => None,
+        })
+    }
+
+    fn next_generation(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+        if self.generation == 0 {
+            // The generations wrapped around, so the old ones would look current.
+            self.generations
+                .iter_mut()
+                .for_each(|generation| *generation = 0);
+            self.generation = 1;
+        }
+        self.open.clear();
+    }
+
+    fn visit(&mut self, index: usize, cost: f64, from: usize) {
+        if !self.is_visited(index) {
+            self.generations[index] = self.generation;
+            self.closed[index] = false;
+        }
+        self.costs[index] = cost;
+        self.came_from[index] = from;
+    }
+
+    fn is_visited(&self, index: usize) -> bool {
+        self.generations[index] == self.generation
+    }
+
+    fn path(&self, goal_index: usize) -> Vec<(usize, usize)> {
+        let mut path = vec![self.cell(goal_index)];
+        let mut index = goal_index;
+        while self.came_from[index] != index {
+            index = self.came_from[index];
+            path.push(self.cell(index));
+        }
+        path.reverse();
+        path
+    }
+
+    // heuristic returns the cost from a to b without walls, which never overestimates.
+    fn heuristic(&self, a: (usize, usize), b: (usize, usize)) -> f64 {
+        let dc = a.0.abs_diff(b.0) as f64;
+        let dr = a.1.abs_diff(b.1) as f64;
+        if self.diagonal {
+            dc.max(dr) + (SQRT_2 - 1.0) * dc.min(dr)
+        } else {
+            dc + dr
+        }
+    }
+
+    fn directions(&self) -> &'static [(isize, isize)] {
+        const STRAIGHT: [(isize, isize); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+        const ALL: [(isize, isize); 8] = [
+            (1, 0),
+            (0, 1),
+            (-1, 0),
+            (0, -1),
+            (1, 1),
+            (-1, 1),
+            (-1, -1),
+            (1, -1),
+        ];
+        if self.diagonal {
+            &ALL
+        } else {
+            &STRAIGHT
+        }
+    }
+
+    fn neighbor(&self, cell: (usize, usize), dc: isize, dr: isize) -> Option<(usize, usize)> {
+        let col = cell.0.checked_add_signed(dc)?;
+        let row = cell.1.checked_add_signed(dr)?;
+        self.contains((col, row)).then_some((col, row))
+    }
+
+    fn contains(&self, cell: (usize, usize)) -> bool {
+        cell.0 < self.cols && cell.1 < self.rows
+    }
+
+    fn index(&self, cell: (usize, usize)) -> usize {
+        cell.0 + cell.1 * self.cols
+    }
+
+    fn cell(&self, index: usize) -> (usize, usize) {
+        (index % self.cols, index / self.cols)
+    }
+}
@@ -0,0 +1,127 @@
+use std::cell::RefCell;
+use std::ops::Range;
+
+/// Rng is a seedable pseudo-random number generator (xoshiro256**) written in pure Rust.
+/// The same seed generates the same numbers on every platform, e.g. for replays and
+/// daily-challenge seeds. It is not suitable for cryptography.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+    state: [u64; 4],
+}
+
+impl Rng {
+    /// seeded returns an Rng seeded with seed. Any seed, including 0, is fine.
+    pub fn seeded(seed: u64) -> Self {
+        // splitmix64 spreads the seed over the state, which must not be all zero.
+        let mut x = seed;
+        let mut next = || {
+            x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = x;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        };
+        Self {
+            state: [next(), next(), next(), next()],
+        }
+    }
+
+    /// next_u64 returns a pseudo-random u64.
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    /// next_f64 returns a pseudo-random number in [0.0, 1.0).
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// range_i32 returns a pseudo-random number in range, e.g. `rng.range_i32(1..7)` for a die.
+    /// An empty range returns its start.
+    pub fn range_i32(&mut self, range: Range<i32>) -> i32 {
+        if range.is_empty() {
+            return range.start;
+        }
+        let span = (range.end as i64 - range.start as i64) as u64;
+        (range.start as i64 + self.below(span) as i64) as i32
+    }
+
+    /// range_f64 returns a pseudo-random number in range. An empty range returns its start.
+    pub fn range_f64(&mut self, range: Range<f64>) -> f64 {
+        if range.is_empty() {
+            return range.start;
+        }
+        range.start + (range.end - range.start) * self.next_f64()
+    }
+
+    /// chance returns true with the probability p from 0.0 to 1.0.
+    pub fn chance(&mut self, p: f64) -> bool {
+        self.next_f64() < p
+    }
+
+    /// pick returns a pseudo-randomly chosen item of items, or None if items is empty.
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            return None;
+        }
+        items.get(self.below(items.len() as u64) as usize)
+    }
+
+    /// shuffle reorders items pseudo-randomly, every order equally likely.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        // Fisher-Yates.
+        for i in (1..items.len()).rev() {
+            let j = self.below(i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+
+    // below returns a pseudo-random number in [0, bound) without the bias of the modulo.
+    fn below(&mut self, bound: u64) -> u64 {
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let x = self.next_u64();
+            if x < zone {
+                return x % bound;
+            }
+        }
+    }
+}
+
+thread_local! {
+    static GLOBAL: RefCell<Option<Rng>> = const { RefCell::new(None) };
+}
+
+/// with_global calls f with the global Rng, which is seeded from performance.now on the first
+/// use. Use it when the numbers need not be reproducible, e.g. for particles.
+pub fn with_global<R>(f: impl FnOnce(&mut Rng) -> R) -> R {
+    GLOBAL.with(|global| {
+        let mut global = global.borrow_mut();
+        f(global.get_or_insert_with(|| Rng::seeded(entropy())))
+    })
+}
+
+/// seed_global seeds the global Rng, e.g. to reproduce a bug.
+pub fn seed_global(seed: u64) {
+    GLOBAL.with(|global| global.replace(Some(Rng::seeded(seed))));
+}
+
+// entropy returns a seed which differs between page loads.
+fn entropy() -> u64 {
+    #[cfg(target_arch = "wasm32")]
+    if let Some(performance) = web_sys::window().and_then(|window| window.performance()) {
+        // performance.now starts from 0 on every page load, so the date is mixed in.
+        return performance.now().to_bits() ^ js_sys::Date::now().to_bits().rotate_left(32);
+    }
+    // Without a browser, e.g. in cargo test, the global Rng is reproducible.
+    0x9e37_79b9_7f4a_7c15
+}
@@ -0,0 +1,321 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use anyhow::{anyhow, ensure, Context, Result};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+use crate::render::{context_2d, offscreen_canvas};
+
+#[cfg(feature = "gif")]
+mod gif;
+#[cfg(feature = "gif")]
+pub use gif::encode_gif;
+#[cfg(feature = "gif")]
+use gif::GifEncoder;
+
+mod zip;
+pub use zip::encode_zip;
+use zip::ZipEncoder;
+
+/// DEFAULT_MAX_BYTES is the memory which a Recorder may use for its frames by default, 64 MiB.
+pub const DEFAULT_MAX_BYTES: usize = 64 * 1024 * 1024;
+
+/// Recorder keeps the last frames of the canvas, e.g. to share a clip of the last 5 seconds or to
+/// compare frames in visual regression tests. Pass it to AppConfigBuilder::recorder, and the
+/// loop captures the canvas after every rendered frame while it is enabled.
+/// The frames are kept in a ring of offscreen canvases, which are reused once the ring is full.
+/// It is cheap to clone, so keep a clone in your app to export the frames.
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    max_frames: usize,
+    scale: f64,
+    max_bytes: usize,
+    state: Rc<RecorderState>,
+}
+
+#[derive(Debug, Default)]
+struct RecorderState {
+    enabled: Cell<bool>,
+    // Capturing is suspended while exporting, so that the exported frames are not overwritten.
+    exporting: Cell<bool>,
+    ring: RefCell<Ring>,
+}
+
+#[derive(Debug, Default)]
+struct Ring {
+    slots: Vec<Slot>,
+    capacity: usize,
+    // The slot which the next frame is captured into.
+    next: usize,
+    len: usize,
+    frame_size: (u32, u32),
+}
+
+#[derive(Debug)]
+struct Slot {
+    canvas: web_sys::HtmlCanvasElement,
+    context: web_sys::CanvasRenderingContext2d,
+    time: f64,
+}
+
+impl Recorder {
+    /// new returns an enabled Recorder which keeps up to max_frames frames, e.g. 300 for the
+    /// last 5 seconds at 60 fps, in full size and DEFAULT_MAX_BYTES at most.
+    pub fn new(max_frames: usize) -> Self {
+        let state = RecorderState::default();
+        state.enabled.set(true);
+        Self {
+            max_frames,
+            scale: 1.0,
+            max_bytes: DEFAULT_MAX_BYTES,
+            state: Rc::new(state),
+        }
+    }
+
+    /// scale sets the scale of the captured frames from 0.0 to 1.0, e.g. 0.5 to keep four times as
+    /// many frames in the same memory. It is 1.0 by default.
+    pub fn scale(mut self, scale: f64) -> Self {
+        self.scale = scale.clamp(f64::MIN_POSITIVE, 1.0);
+        self
+    }
+
+    /// max_bytes sets the memory which the captured frames may use. A frame uses width x height x 4
+    /// bytes after scaling, and the ring keeps fewer frames than max_frames if they do not fit.
+    /// Exporting needs memory beyond max_bytes for the exported file and a single frame.
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// set_enabled starts or stops capturing. The captured frames are kept while stopped.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.state.enabled.set(enabled);
+    }
+
+    /// is_enabled returns true while capturing.
+    pub fn is_enabled(&self) -> bool {
+        self.state.enabled.get()
+    }
+
+    /// len returns the number of the captured frames.
+    pub fn len(&self) -> usize {
+        self.state.ring.borrow().len
+    }
+
+    /// is_empty returns true when no frames are captured.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// capacity returns the number of the frames which the ring keeps, which is limited by both
+    /// max_frames and max_bytes. It is 0 until the first frame is captured.
+    pub fn capacity(&self) -> usize {
+        self.state.ring.borrow().capacity
+    }
+
+    /// memory_bytes returns the memory used by the offscreen canvases of the ring.
+    pub fn memory_bytes(&self) -> usize {
+        let ring = self.state.ring.borrow();
+        ring.slots.len() * frame_bytes(ring.frame_size)
+    }
+
+    /// clear forgets the captured frames and releases their canvases.
+    pub fn clear(&self) {
+        self.state.ring.replace(Ring::default());
+    }
+
+    // capture copies canvas into the ring at time in milliseconds. When the canvas is resized,
+    // the frames of the old size are cleared. It fails if a single frame exceeds max_bytes.
+    pub(crate) fn capture(&self, canvas: &web_sys::HtmlCanvasElement, time: f64) -> Result<()> {
+        if !self.state.enabled.get() || self.state.exporting.get() {
+            return Ok(());
+        }
+        let frame_size = (
+            ((canvas.width() as f64 * self.scale).round() as u32).max(1),
+            ((canvas.height() as f64 * self.scale).round() as u32).max(1),
+        );
+        let mut ring = self.state.ring.borrow_mut();
+        if ring.frame_size != frame_size {
+            let capacity = self
+                .max_frames
+                .min(self.max_bytes / frame_bytes(frame_size));
+            ensure!(
+                capacity > 0,
+                "a frame of {}x{} needs {} bytes, which exceeds max_bytes {}",
+                frame_size.0,
+                frame_size.1,
+                frame_bytes(frame_size),
+                self.max_bytes
+            );
+            *ring = Ring {
+                capacity,
+                frame_size,
+                ..Ring::default()
+            };
+        }
+        let index = ring.next;
+        if index == ring.slots.len() {
+            let (width, height) = frame_size;
+            let canvas = offscreen_canvas(width, height)?;
+            let context = context_2d(&canvas)?;
+            ring.slots.push(Slot {
+                canvas,
+                context,
+                time,
+            });
+        }
+        let (width, height) = frame_size;
+        let slot = &mut ring.slots[index];
+        slot.time = time;
+        slot.context
+            .draw_image_with_html_canvas_element_and_dw_and_dh(
+                canvas,
+                0.0,
+                0.0,
+                width as f64,
+                height as f64,
+            )
+            .map_err(|e| anyhow!("failed to capture the canvas: {:?}", e))?;
+        ring.next = (index + 1) % ring.capacity;
+        ring.len = (ring.len + 1).min(ring.capacity);
+        Ok(())
+    }
+
+    /// export_png_zip returns a zip of the captured frames as PNGs from the oldest, named
+    /// frame_0000.png, frame_0001.png and so on. It yields to the browser between frames, and
+    /// capturing is suspended until it finishes.
+    /// The frames are encoded one at a time, so it needs the memory of the zip and a single PNG.
+    pub async fn export_png_zip(&self) -> Result<web_sys::Blob> {
+        let _exporting = Exporting::start(&self.state)?;
+        let mut zip = ZipEncoder::default();
+        for i in 0..self.len() {
+            let data_url = self
+                .frame(i, |slot| slot.canvas.to_data_url())?
+                .map_err(|e| anyhow!("failed to encode frame {}: {:?}", i, e))?;
+            let base64 = data_url
+                .split_once(',')
+                .map(|(_, base64)| base64)
+                .context("the data URL of a frame has no data")?;
+            let png = base64::decode(base64).context("failed to decode the PNG of a frame")?;
+            zip.add_file(&format!("frame_{:04}.png", i), &png);
+            next_task().await?;
+        }
+        to_blob(&zip.finish(), "application/zip")
+    }
+
+    /// export_gif returns an animated GIF of the captured frames from the oldest, which plays at
+    /// the speed they were captured. It yields to the browser between frames, and capturing is
+    /// suspended until it finishes. It needs the `gif` feature.
+    /// The frames are encoded one at a time, so it needs the memory of the GIF and the pixels of a
+    /// single frame.
+    #[cfg(feature = "gif")]
+    pub async fn export_gif(&self) -> Result<web_sys::Blob> {
+        let _exporting = Exporting::start(&self.state)?;
+        let (width, height) = self.state.ring.borrow().frame_size;
+        let (width, height) = (
+            u16::try_from(width).context("the frames are too wide for a GIF")?,
+            u16::try_from(height).context("the frames are too high for a GIF")?,
+        );
+        let mut gif = GifEncoder::new(width, height);
+        for i in 0..self.len() {
+            let (rgba, time) = self
+                .frame(i, |slot| {
+                    slot.context
+                        .get_image_data(0.0, 0.0, width as f64, height as f64)
+                        .map(|image_data| (image_data.data(), slot.time))
+                })?
+                .map_err(|e| anyhow!("failed to read frame {}: {:?}", i, e))?;
+            // Each frame is shown until the next one was captured, and the last one for a frame
+            // at 60 fps.
+            let delay_ms = if i + 1 < self.len() {
+                self.frame(i + 1, |slot| slot.time)? - time
+            } else {
+                1000.0 / 60.0
+            };
+            gif.add_frame(&rgba, delay_ms)?;
+            next_task().await?;
+        }
+        to_blob(&gif.finish(), "image/gif")
+    }
+
+    // frame calls f with the slot of the i-th oldest frame. It fails if the frames were cleared.
+    fn frame<R>(&self, i: usize, f: impl FnOnce(&Slot) -> R) -> Result<R> {
+        let ring = self.state.ring.borrow();
+        ensure!(i < ring.len, "the frames were cleared while exporting");
+        let oldest = if ring.len < ring.capacity {
+            0
+        } else {
+            ring.next
+        };
+        Ok(f(&ring.slots[(oldest + i) % ring.capacity]))
+    }
+}
+
+/// download makes the browser download blob as filename, e.g. "clip.gif", through an object
+/// URL which is revoked afterwards.
+pub fn download(blob: &web_sys::Blob, filename: &str) -> Result<()> {
+    let window = web_sys::window().context("no global window exists")?;
+    let document = window.document().context("the window has no document")?;
+    let url = web_sys::Url::create_object_url_with_blob(blob)
+        .map_err(|e| anyhow!("failed to create an object URL: {:?}", e))?;
+    let anchor = document
+        .create_element("a")
+        .map_err(|e| anyhow!("failed to create a link: {:?}", e))?
+        .dyn_into::<web_sys::HtmlAnchorElement>()
+        .map_err(|_| anyhow!("the created element is not a link"))?;
+    anchor.set_href(&url);
+    anchor.set_download(filename);
+    anchor.click();
+    // The download has started by the next task, so the URL can be revoked then.
+    let revoke = wasm_bindgen::closure::Closure::once_into_js(move || {
+        let _ = web_sys::Url::revoke_object_url(&url);
+    });
+    window
+        .set_timeout_with_callback(revoke.unchecked_ref())
+        .map_err(|e| anyhow!("failed to revoke the object URL: {:?}", e))?;
+    Ok(())
+}
+
+fn frame_bytes((width, height): (u32, u32)) -> usize {
+    width as usize * height as usize * 4
+}
+
+// Exporting suspends capturing until it is dropped.
+struct Exporting<'a> {
+    state: &'a RecorderState,
+}
+
+impl<'a> Exporting<'a> {
+    fn start(state: &'a RecorderState) -> Result<Self> {
+        ensure!(!state.exporting.get(), "the recorder is already exporting");
+        state.exporting.set(true);
+        Ok(Self { state })
+    }
+}
+
+impl Drop for Exporting<'_> {
+    fn drop(&mut self) {
+        self.state.exporting.set(false);
+    }
+}
+
+fn to_blob(bytes: &[u8], mime_type: &str) -> Result<web_sys::Blob> {
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes));
+    let mut options = web_sys::BlobPropertyBag::new();
+    options.type_(mime_type);
+    web_sys::Blob::new_with_u8_array_sequence_and_options(&parts, &options)
+        .map_err(|e| anyhow!("failed to create a blob: {:?}", e))
+}
+
+// next_task waits for a timeout, so that the browser can render and handle input.
+async fn next_task() -> Result<()> {
+    let window = web_sys::window().context("no global window exists")?;
+    let promise = js_sys::Promise::new(&mut |resolve, _| {
+        let _ = window.set_timeout_with_callback(&resolve);
+    });
+    JsFuture::from(promise)
+        .await
+        .map(|_: JsValue| ())
+        .map_err(|e| anyhow!("failed to wait for the next task: {:?}", e))
+}
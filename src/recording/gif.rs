@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+use anyhow::{ensure, Result};
+
+/// encode_gif returns an animated GIF of frames which loops forever. Each frame is a pair of
+/// its RGBA pixels of width x height and its delay in milliseconds.
+/// The colors are reduced to a fixed palette of 252 colors without dithering, which is enough
+/// for clips of retro games. It returns an error if a frame is not of width x height.
+pub fn encode_gif(width: u16, height: u16, frames: &[(Vec<u8>, f64)]) -> Result<Vec<u8>> {
+    let mut encoder = GifEncoder::new(width, height);
+    for (rgba, delay_ms) in frames {
+        encoder.add_frame(rgba, *delay_ms)?;
+    }
+    Ok(encoder.finish())
+}
+
+// GifEncoder encodes an animated GIF a frame at a time, so that the frames need not be kept in
+// memory together, e.g. while exporting a Recorder.
+pub(crate) struct GifEncoder {
+    width: u16,
+    height: u16,
+    // The delays are in centiseconds, so the rounding errors are carried over to the next frame.
+    error_cs: f64,
+    out: Vec<u8>,
+}
+
+impl GifEncoder {
+    // new starts a GIF of width x height which loops forever.
+    pub(crate) fn new(width: u16, height: u16) -> Self {
+        let mut out = b"GIF89a".to_vec();
+        out.extend_from_slice(&width.to_le_bytes());
+        out.extend_from_slice(&height.to_le_bytes());
+        // The global color table of 256 colors, the background color and the aspect ratio.
+        out.extend_from_slice(&[0xf7, 0, 0]);
+        for index in 0..=255u8 {
+            out.extend_from_slice(&palette_color(index));
+        }
+        // The NETSCAPE2.0 extension makes the animation loop forever.
+        out.extend_from_slice(&[0x21, 0xff, 0x0b]);
+        out.extend_from_slice(b"NETSCAPE2.0");
+        out.extend_from_slice(&[0x03, 0x01, 0x00, 0x00, 0x00]);
+        Self {
+            width,
+            height,
+            error_cs: 0.0,
+            out,
+        }
+    }
+
+    // add_frame appends a frame of the RGBA pixels of width x height shown for delay_ms.
+    pub(crate) fn add_frame(&mut self, rgba: &[u8], delay_ms: f64) -> Result<()> {
+        let (width, height) = (self.width, self.height);
+        let bytes = width as usize * height as usize * 4;
+        ensure!(
+            rgba.len() == bytes,
+            "a frame of {}x{} must have {} bytes, but it has {}",
+            width,
+            height,
+            bytes,
+            rgba.len()
+        );
+        let exact_cs = delay_ms.max(0.0) / 10.0 + self.error_cs;
+        let delay_cs = exact_cs.round().min(u16::MAX as f64) as u16;
+        self.error_cs = exact_cs - delay_cs as f64;
+        let out = &mut self.out;
+        // The graphic control extension, which keeps the frame under the next one.
+        out.extend_from_slice(&[0x21, 0xf9, 0x04, 0x04]);
+        out.extend_from_slice(&delay_cs.to_le_bytes());
+        out.extend_from_slice(&[0x00, 0x00]);
+        // The image descriptor of the whole screen without a local color table.
+        out.push(0x2c);
+        out.extend_from_slice(&[0, 0, 0, 0]);
+        out.extend_from_slice(&width.to_le_bytes());
+        out.extend_from_slice(&height.to_le_bytes());
+        out.push(0);
+        let indices: Vec<u8> = rgba
+            .chunks_exact(4)
+            .map(|pixel| palette_index(pixel[0], pixel[1], pixel[2]))
+            .collect();
+        out.push(MIN_CODE_SIZE);
+        for block in lzw(&indices).chunks(255) {
+            out.push(block.len() as u8);
+            out.extend_from_slice(block);
+        }
+        out.push(0);
+        Ok(())
+    }
+
+    // finish returns the GIF of the added frames.
+    pub(crate) fn finish(mut self) -> Vec<u8> {
+        self.out.push(0x3b);
+        self.out
+    }
+}
+
+const MIN_CODE_SIZE: u8 = 8;
+
+// The palette has 6 levels of red, 7 of green and 6 of blue, because eyes tell greens apart best.
+const LEVELS: (u8, u8, u8) = (6, 7, 6);
+
+// palette_index returns the index of the palette color nearest to the color.
+fn palette_index(r: u8, g: u8, b: u8) -> u8 {
+    let level = |value: u8, levels: u8| ((value as u32 * (levels as u32 - 1) + 127) / 255) as u8;
+    let (rl, gl, bl) = LEVELS;
+    level(r, rl) * gl * bl + level(g, gl) * bl + level(b, bl)
+}
+
+// palette_color returns the color of index. The indices beyond the palette are black.
+fn palette_color(index: u8) -> [u8; 3] {
+    let (rl, gl, bl) = LEVELS;
+    if index as u32 >= rl as u32 * gl as u32 * bl as u32 {
+        return [0, 0, 0];
+    }
+    let value = |level: u8, levels: u8| (level as u32 * 255 / (levels as u32 - 1)) as u8;
+    [
+        value(index / (gl * bl), rl),
+        value(index / bl % gl, gl),
+        value(index % bl, bl),
+    ]
+}
+
+// lzw compresses indices with the variable-length LZW of GIF, and packs the codes from the
+// least significant bit.
+fn lzw(indices: &[u8]) -> Vec<u8> {
+    let clear = 1u16 << MIN_CODE_SIZE;
+    let end = clear + 1;
+    let mut writer = BitWriter::default();
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut code_size = MIN_CODE_SIZE as u32 + 1;
+    let mut next = end + 1;
+    writer.put(clear, code_size);
+
+    let mut indices = indices.iter();
+    let mut prefix = match indices.next() {
+        Some(&index) => index as u16,
+        None => {
+            writer.put(end, code_size);
+            return writer.finish();
+        }
+    };
+    for &index in indices {
+        if let Some(&code) = table.get(&(prefix, index)) {
+            prefix = code;
+            continue;
+        }
+        writer.put(prefix, code_size);
+        if next < 4096 {
+            if next == 1 << code_size {
+                code_size += 1;
+            }
+            table.insert((prefix, index), next);
+            next += 1;
+        } else {
+            // The table is full, so it starts over.
+            writer.put(clear, code_size);
+            table.clear();
+            code_size = MIN_CODE_SIZE as u32 + 1;
+            next = end + 1;
+        }
+        prefix = index as u16;
+    }
+    writer.put(prefix, code_size);
+    writer.put(end, code_size);
+    writer.finish()
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn put(&mut self, code: u16, size: u32) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
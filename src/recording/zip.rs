@@ -0,0 +1,102 @@
+/// encode_zip returns a zip archive of files, each of which is a pair of a name and its bytes.
+/// The files are stored without compression, because PNGs and GIFs are compressed already.
+pub fn encode_zip(files: &[(String, Vec<u8>)]) -> Vec<u8> {
+    let mut encoder = ZipEncoder::default();
+    for (name, bytes) in files {
+        encoder.add_file(name, bytes);
+    }
+    encoder.finish()
+}
+
+// ZipEncoder stores files in a zip archive a file at a time, so that the files need not be kept
+// in memory together, e.g. while exporting a Recorder.
+#[derive(Default)]
+pub(crate) struct ZipEncoder {
+    archive: Vec<u8>,
+    directory: Vec<u8>,
+    files: u16,
+}
+
+impl ZipEncoder {
+    // add_file appends a file of name and bytes.
+    pub(crate) fn add_file(&mut self, name: &str, bytes: &[u8]) {
+        let (archive, directory) = (&mut self.archive, &mut self.directory);
+        let offset = archive.len() as u32;
+        let crc = crc32(bytes);
+        // The local file header.
+        put_u32(archive, 0x0403_4b50);
+        put_entry(archive, name, bytes, crc);
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(bytes);
+        // The central directory header.
+        put_u32(directory, 0x0201_4b50);
+        put_u16(directory, 20);
+        put_entry(directory, name, bytes, crc);
+        // The comment length, the disk number, the internal and external attributes.
+        put_u16(directory, 0);
+        put_u16(directory, 0);
+        put_u16(directory, 0);
+        put_u32(directory, 0);
+        put_u32(directory, offset);
+        directory.extend_from_slice(name.as_bytes());
+        self.files += 1;
+    }
+
+    // finish returns the archive of the added files.
+    pub(crate) fn finish(self) -> Vec<u8> {
+        let Self {
+            mut archive,
+            directory,
+            files,
+        } = self;
+        let directory_offset = archive.len() as u32;
+        archive.extend_from_slice(&directory);
+        // The end of the central directory.
+        put_u32(&mut archive, 0x0605_4b50);
+        put_u16(&mut archive, 0);
+        put_u16(&mut archive, 0);
+        put_u16(&mut archive, files);
+        put_u16(&mut archive, files);
+        put_u32(&mut archive, directory.len() as u32);
+        put_u32(&mut archive, directory_offset);
+        put_u16(&mut archive, 0);
+        archive
+    }
+}
+
+// put_entry puts the fields shared by the local file header and the central directory header,
+// from the version needed to extract to the extra field length.
+fn put_entry(out: &mut Vec<u8>, name: &str, bytes: &[u8], crc: u32) {
+    put_u16(out, 20);
+    // The flags, the stored method, and the time and the date of 1980-01-01 00:00.
+    put_u16(out, 0);
+    put_u16(out, 0);
+    put_u16(out, 0);
+    put_u16(out, 0x21);
+    put_u32(out, crc);
+    put_u32(out, bytes.len() as u32);
+    put_u32(out, bytes.len() as u32);
+    put_u16(out, name.len() as u16);
+    put_u16(out, 0);
+}
+
+fn put_u16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+// crc32 returns the CRC-32 of bytes with the polynomial of zip.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
@@ -1,37 +1,242 @@
+use std::cell::RefCell;
 use std::rc::Rc;
 
 use anyhow::{anyhow, ensure, Context, Result};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::{future_to_promise, JsFuture};
 
-/// draw_image depicts a given sprite at a specified position on the canvas.
+use crate::test::DrawCommand;
+use crate::timing::FrameTiming;
+use color::Color;
+
+/// batch is about accumulating draws and issuing them grouped by atlas or sorted by depth.
+pub mod batch;
+pub use batch::Batch;
+
+/// color is about typed colors for drawing.
+pub mod color;
+
+/// pattern is about repeating a sprite across a region.
+pub mod pattern;
+
+mod arc;
+pub use arc::{draw_arc, draw_pie_slice};
+
+mod bitmap_font;
+pub use bitmap_font::BitmapFont;
+
+mod polygon;
+pub use polygon::draw_polygon;
+
+mod scaled;
+pub use scaled::{draw_scaled_sprite, ScaledSprite};
+
+mod node;
+pub use node::{Node, NodeId, SceneGraph};
+
+mod particles;
+pub use particles::{Particle, ParticleEmitter, ParticleLook};
+
+mod pass;
+pub use pass::RenderPassId;
+
+mod screen_shake;
+pub use screen_shake::ScreenShake;
+
+mod scrolling;
+pub use scrolling::ScrollingBackground;
+
+mod canvas_ext;
+pub use canvas_ext::Canvas2dExt;
+
+mod cursor;
+pub use cursor::{Cursor, CursorImage};
+
+mod dashed_line;
+pub use dashed_line::draw_dashed_line;
+
+mod font;
+pub use font::Font;
+
+mod glow;
+pub use glow::GlowEffect;
+
+mod grid;
+pub use grid::Grid;
+
+mod nine_patch;
+pub use nine_patch::NinePatch;
+
+mod sprite_outline;
+pub use sprite_outline::draw_sprite_outline;
+
+mod text_box;
+pub use text_box::TextBox;
+
+mod tileset;
+pub use tileset::Tileset;
+
+mod transform;
+pub use transform::{with_transform, Transform};
+
+mod viewport;
+pub use viewport::{draw_image_in_viewport, Viewport};
+
+/// draw_image depicts a given sprite at a specified position on the canvas. A fractional
+/// position is drawn as it is, so that slow movements look smooth.
+/// It returns an error if the sprite does not overlap or touch the canvas.
 pub fn draw_image(renderer: &Renderer, sprite: &Sprite, position: Position) -> Result<()> {
     ensure!(
-        0.0 <= position.dx() + sprite.width()
-            && position.dx() <= renderer.canvas_width()
-            && 0.0 <= position.dy() + sprite.height()
-            && position.dy() <= renderer.canvas_height(),
+        overlaps_canvas(
+            renderer,
+            position.dx(),
+            position.dy(),
+            sprite.width(),
+            sprite.height()
+        ),
         "the sprite to draw is out of canvas"
     );
 
+    draw_sprite(
+        renderer,
+        sprite,
+        position.dx(),
+        position.dy(),
+        sprite.width(),
+        sprite.height(),
+    )
+}
+
+/// draw_image_pixel_perfect depicts a given sprite at a specified position rounded to the
+/// nearest integer pixels, so that sprites moving at fractional positions do not shimmer.
+pub fn draw_image_pixel_perfect(
+    renderer: &Renderer,
+    sprite: &Sprite,
+    position: Position,
+) -> Result<()> {
+    draw_image(
+        renderer,
+        sprite,
+        Position::new(position.dx().round(), position.dy().round()),
+    )
+}
+
+/// overlaps_canvas returns true when the destination rectangle overlaps or touches the canvas.
+/// The canvas clips the rest of the rectangle.
+pub(crate) fn overlaps_canvas(renderer: &Renderer, dx: f64, dy: f64, dw: f64, dh: f64) -> bool {
+    overlaps_area(
+        dx,
+        dy,
+        dw,
+        dh,
+        renderer.canvas_width(),
+        renderer.canvas_height(),
+    )
+}
+
+/// overlaps_area returns true when the rectangle of dw x dh at (dx, dy) overlaps or touches the
+/// area of width x height at the origin. Every visibility check of the draws shares it.
+pub(crate) fn overlaps_area(dx: f64, dy: f64, dw: f64, dh: f64, width: f64, height: f64) -> bool {
+    0.0 <= dx + dw && dx <= width && 0.0 <= dy + dh && dy <= height
+}
+
+/// draw_sprite depicts sprite in the destination rectangle, or records it on a headless renderer.
+pub(crate) fn draw_sprite(
+    renderer: &Renderer,
+    sprite: &Sprite,
+    dx: f64,
+    dy: f64,
+    dw: f64,
+    dh: f64,
+) -> Result<()> {
+    let recorded = renderer.record(|| DrawCommand::Image {
+        sx: sprite.sx(),
+        sy: sprite.sy(),
+        sw: sprite.width(),
+        sh: sprite.height(),
+        dx,
+        dy,
+        dw,
+        dh,
+    });
+    if recorded {
+        return Ok(());
+    }
+
     renderer
         .context()
         .draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
-            &sprite.atlas(),
+            &sprite.atlas,
             sprite.sx(),
             sprite.sy(),
             sprite.width(),
             sprite.height(),
-            position.dx(),
-            position.dy(),
-            sprite.width(),
-            sprite.height(),
+            dx,
+            dy,
+            dw,
+            dh,
         )
         .map_err(|e| anyhow!("failed to draw image: {:?}", e))?;
 
     Ok(())
 }
 
+/// draw_text fills text at position with a CSS font, or records it on a headless renderer.
+pub(crate) fn draw_text(
+    renderer: &Renderer,
+    text: &str,
+    position: Position,
+    font: &str,
+    color: Color,
+) -> Result<()> {
+    let recorded = renderer.record(|| DrawCommand::Text {
+        text: String::from(text),
+        dx: position.dx(),
+        dy: position.dy(),
+        font: String::from(font),
+        color,
+    });
+    if recorded {
+        return Ok(());
+    }
+
+    // The font is set before save, so that restore keeps it and the next text skips setting it.
+    renderer.apply_font(font, None);
+    let context = renderer.context();
+    context.save();
+    context.set_fill_style(&(&color).into());
+    let result = context
+        .fill_text(text, position.dx(), position.dy())
+        .map_err(|e| anyhow!("failed to draw text: {:?}", e));
+    context.restore();
+    result
+}
+
+/// fill_rect fills the destination rectangle with color, or records it on a headless renderer.
+pub(crate) fn fill_rect(renderer: &Renderer, dx: f64, dy: f64, dw: f64, dh: f64, color: Color) {
+    let recorded = renderer.record(|| DrawCommand::FillRect {
+        dx,
+        dy,
+        dw,
+        dh,
+        color,
+    });
+    if recorded {
+        return;
+    }
+
+    let context = renderer.context();
+    context.save();
+    context.set_fill_style(&(&color).into());
+    context.fill_rect(dx, dy, dw, dh);
+    context.restore();
+}
+
 /// clear clears the canvas.
 pub fn clear(renderer: &Renderer) {
+    if renderer.record(|| DrawCommand::Clear) {
+        return;
+    }
     renderer
         .context()
         .clear_rect(0.0, 0.0, renderer.canvas_width(), renderer.canvas_height());
@@ -43,6 +248,20 @@ pub struct Renderer {
     context: web_sys::CanvasRenderingContext2d,
     canvas_width: f64,
     canvas_height: f64,
+    frame_timing: FrameTiming,
+    recorder: Option<RefCell<Vec<DrawCommand>>>,
+    font: RefCell<Option<AppliedFont>>,
+    cursor: RefCell<Cursor>,
+}
+
+// AppliedFont is the font which set_font applied to the context.
+#[derive(Debug)]
+struct AppliedFont {
+    css: String,
+    font: Option<Font>,
+    // The font which the context reported right after it was set. The context reports another
+    // font once restore or a direct set has changed it, which invalidates the cache.
+    reported: String,
 }
 
 impl Renderer {
@@ -55,14 +274,132 @@ impl Renderer {
             context,
             canvas_width,
             canvas_height,
+            frame_timing: FrameTiming::new(),
+            recorder: None,
+            font: RefCell::new(None),
+            cursor: RefCell::new(Cursor::Default),
         }
     }
 
-    /// context enalbes you to use the rendering context on the canvas.
+    /// headless returns a Renderer without a canvas, which records draw commands instead.
+    /// Its context must not be used because it is not a real rendering context.
+    pub(crate) fn headless(canvas_width: f64, canvas_height: f64) -> Self {
+        Self {
+            recorder: Some(RefCell::new(vec![])),
+            ..Self::new(JsValue::NULL.unchecked_into(), canvas_width, canvas_height)
+        }
+    }
+
+    /// record pushes a draw command and returns true when the renderer is headless.
+    pub(crate) fn record(&self, command: impl FnOnce() -> DrawCommand) -> bool {
+        match &self.recorder {
+            Some(recorder) => {
+                recorder.borrow_mut().push(command());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// is_headless returns true when the renderer records draw commands instead of drawing them.
+    pub(crate) fn is_headless(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    pub(crate) fn take_commands(&self) -> Vec<DrawCommand> {
+        self.recorder
+            .as_ref()
+            .map(|recorder| recorder.take())
+            .unwrap_or_default()
+    }
+
+    /// context enables you to use the rendering context on the canvas.
+    /// A headless renderer, e.g. of TestHarness or MockRenderer, has no real context, and calling
+    /// methods on it panics. Use try_context in code which also runs headless.
     pub fn context(&self) -> &web_sys::CanvasRenderingContext2d {
         &self.context
     }
 
+    /// try_context returns the rendering context on the canvas, or None on a headless renderer.
+    pub fn try_context(&self) -> Option<&web_sys::CanvasRenderingContext2d> {
+        if self.is_headless() {
+            return None;
+        }
+        Some(&self.context)
+    }
+
+    /// set_font sets font to the context. It skips the context call if font is already set,
+    /// because the browser parses the CSS font on every set. The cache notices when the font of
+    /// the context is changed by restore or through Renderer::context.
+    pub fn set_font(&self, font: &Font) {
+        self.apply_font(font.css_string(), Some(font));
+    }
+
+    /// font returns the font set by set_font while the context still has it.
+    pub fn font(&self) -> Option<Font> {
+        let applied = self.font.borrow();
+        let applied = applied.as_ref()?;
+        if !self.is_headless() && self.context.font() != applied.reported {
+            return None;
+        }
+        applied.font.clone()
+    }
+
+    /// reset_font forgets the font set by set_font, so that the next set_font calls the context.
+    pub fn reset_font(&self) {
+        self.font.replace(None);
+    }
+
+    // apply_font sets css to the context unless the context already has it. font is the Font
+    // which css comes from, if any.
+    pub(crate) fn apply_font(&self, css: &str, font: Option<&Font>) {
+        if self.is_headless() {
+            self.font.replace(Some(AppliedFont {
+                css: String::from(css),
+                font: font.cloned(),
+                reported: String::new(),
+            }));
+            return;
+        }
+        if let Some(applied) = &*self.font.borrow() {
+            // Reading the font is much cheaper than setting it, which parses it.
+            if applied.css == css && self.context.font() == applied.reported {
+                return;
+            }
+        }
+        self.context.set_font(css);
+        self.font.replace(Some(AppliedFont {
+            css: String::from(css),
+            font: font.cloned(),
+            reported: self.context.font(),
+        }));
+    }
+
+    /// set_cursor sets the appearance of the mouse cursor over the canvas, e.g. Cursor::Hidden to
+    /// draw a crosshair sprite instead, or a resize arrow over a handle. The cursor is back to
+    /// normal outside the canvas, and the default one is restored when the loop is stopped.
+    /// A custom cursor of a sprite is encoded on every call, so call it when the cursor changes
+    /// rather than every frame. It returns an error if the sprite cannot be encoded.
+    pub fn set_cursor(&self, cursor: Cursor) -> Result<()> {
+        if !self.is_headless() {
+            let canvas = self
+                .context
+                .canvas()
+                .context("the context has no canvas to set the cursor")?;
+            canvas
+                .style()
+                .set_property("cursor", &cursor.css_value()?)
+                .map_err(|e| anyhow!("failed to set the cursor: {:?}", e))?;
+        }
+        self.cursor.replace(cursor);
+        Ok(())
+    }
+
+    /// cursor returns the cursor set by set_cursor.
+    pub fn cursor(&self) -> Cursor {
+        self.cursor.borrow().clone()
+    }
+
     /// canvas_width enalbes you to refer to that.
     pub fn canvas_width(&self) -> f64 {
         self.canvas_width
@@ -72,10 +409,19 @@ impl Renderer {
     pub fn canvas_height(&self) -> f64 {
         self.canvas_height
     }
+
+    /// frame_timing enables you to refer to fps and frame time measured by the loop.
+    pub fn frame_timing(&self) -> &FrameTiming {
+        &self.frame_timing
+    }
+
+    pub(crate) fn frame_timing_mut(&mut self) -> &mut FrameTiming {
+        &mut self.frame_timing
+    }
 }
 
 /// Sprite is responsible for representing a sprite.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Sprite {
     atlas: Rc<web_sys::HtmlImageElement>,
     sx: f64,
@@ -85,7 +431,7 @@ pub struct Sprite {
 }
 
 impl Sprite {
-    fn new(
+    pub(crate) fn new(
         atlas: Rc<web_sys::HtmlImageElement>,
         sx: f64,
         sy: f64,
@@ -101,6 +447,26 @@ impl Sprite {
         }
     }
 
+    /// from_image_element wraps an image loaded outside of retrospector, e.g. in JavaScript.
+    /// The whole image becomes the sprite, so the image should be loaded before calling this
+    /// because its natural width and height are 0 until then.
+    pub fn from_image_element(element: web_sys::HtmlImageElement) -> Self {
+        let width = element.natural_width() as f64;
+        let height = element.natural_height() as f64;
+        Self::new(Rc::new(element), 0.0, 0.0, width, height)
+    }
+
+    /// from_image_element_region wraps a region of an image loaded outside of retrospector.
+    pub fn from_image_element_region(
+        element: web_sys::HtmlImageElement,
+        sx: f64,
+        sy: f64,
+        width: f64,
+        height: f64,
+    ) -> Self {
+        Self::new(Rc::new(element), sx, sy, width, height)
+    }
+
     /// atlas is a set of sprites.
     pub fn atlas(&self) -> Rc<web_sys::HtmlImageElement> {
         Rc::clone(&self.atlas)
@@ -125,14 +491,85 @@ impl Sprite {
     pub fn height(&self) -> f64 {
         self.height
     }
+
+    /// bounding_box returns the top-left and bottom-right corners of the sprite at position.
+    pub fn bounding_box(&self, position: &Position) -> (Position, Position) {
+        (
+            Position::new(position.dx(), position.dy()),
+            Position::new(position.dx() + self.width, position.dy() + self.height),
+        )
+    }
+
+    /// overlaps_with returns true when the sprite at position overlaps other at other_position.
+    /// Sprites which only touch each other on their edges do not overlap.
+    pub fn overlaps_with(
+        &self,
+        other: &Sprite,
+        other_position: &Position,
+        position: &Position,
+    ) -> bool {
+        let (top_left, bottom_right) = self.bounding_box(position);
+        let (other_top_left, other_bottom_right) = other.bounding_box(other_position);
+        top_left.dx() < other_bottom_right.dx()
+            && other_top_left.dx() < bottom_right.dx()
+            && top_left.dy() < other_bottom_right.dy()
+            && other_top_left.dy() < bottom_right.dy()
+    }
+
+    /// pixel_at returns the RGBA of the pixel at x and y from the top-left corner of the sprite.
+    /// The pixel is copied to the top-left corner of canvas to read it back, so pass a scratch
+    /// canvas which is not shown, and reuse it for many queries. It is too slow to call for
+    /// every pixel every frame.
+    pub fn pixel_at(&self, x: u32, y: u32, canvas: &web_sys::HtmlCanvasElement) -> Result<[u8; 4]> {
+        ensure!(
+            (x as f64) < self.width && (y as f64) < self.height,
+            "the pixel ({}, {}) is out of the sprite of {}x{}",
+            x,
+            y,
+            self.width,
+            self.height
+        );
+        let context = context_2d(canvas)?;
+        context.clear_rect(0.0, 0.0, 1.0, 1.0);
+        context
+            .draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+                &self.atlas,
+                self.sx + x as f64,
+                self.sy + y as f64,
+                1.0,
+                1.0,
+                0.0,
+                0.0,
+                1.0,
+                1.0,
+            )
+            .map_err(|e| anyhow!("failed to draw image: {:?}", e))?;
+        let pixel = context
+            .get_image_data(0.0, 0.0, 1.0, 1.0)
+            .map_err(|e| anyhow!("failed to read the pixel: {:?}", e))?
+            .data();
+        Ok([pixel[0], pixel[1], pixel[2], pixel[3]])
+    }
+
+    /// scale_to returns a ScaledSprite which draws the sprite in width x height.
+    pub fn scale_to(&self, width: f64, height: f64) -> ScaledSprite<'_> {
+        ScaledSprite::new(self, width, height)
+    }
 }
 
 /// SpriteStore builds Sprites with the same atlas, width, and height.
 #[derive(Debug)]
 pub struct SpriteStore {
+    atlas: Rc<web_sys::HtmlImageElement>,
     store: Vec<Sprite>,
     width_in_tile: usize,
     height_in_tile: usize,
+    tile_width: u32,
+    tile_height: u32,
+    color_key: Option<(u8, u8, u8)>,
+    // keyed_load is the promise of keying the atlas, shared by every on_load so that the atlas is
+    // keyed only once.
+    keyed_load: RefCell<Option<js_sys::Promise>>,
 }
 
 impl SpriteStore {
@@ -186,12 +623,63 @@ impl SpriteStore {
         }
 
         Ok(Self {
+            atlas,
             store,
             width_in_tile: width_in_tile as usize,
             height_in_tile: height_in_tile as usize,
+            tile_width,
+            tile_height,
+            color_key: None,
+            keyed_load: RefCell::new(None),
         })
     }
 
+    /// with_color_key makes the pixels of the color r, g and b transparent, e.g. 0xff00ff of a
+    /// GIF or BMP atlas without an alpha channel. The atlas is re-encoded when it is loaded, so
+    /// draw the sprites after the promise returned by on_load is resolved.
+    pub fn with_color_key(mut self, r: u8, g: u8, b: u8) -> Self {
+        self.color_key = Some((r, g, b));
+        self.keyed_load = RefCell::new(None);
+        self
+    }
+
+    /// atlas_dimensions returns the width and height of the atlas.
+    pub fn atlas_dimensions(&self) -> (u32, u32) {
+        (
+            self.width_in_tile as u32 * self.tile_width,
+            self.height_in_tile as u32 * self.tile_height,
+        )
+    }
+
+    /// tile_dimensions returns the width and height of each sprite on the atlas.
+    pub fn tile_dimensions(&self) -> (u32, u32) {
+        (self.tile_width, self.tile_height)
+    }
+
+    /// on_load returns a promise which is resolved when the atlas is decoded and ready to draw.
+    /// With a color key, every call returns the same promise, which keys the atlas once.
+    pub fn on_load(&self) -> js_sys::Promise {
+        let color_key = match self.color_key {
+            Some(color_key) => color_key,
+            None => return self.atlas.decode(),
+        };
+        if let Some(keyed_load) = self.keyed_load.borrow().as_ref() {
+            return keyed_load.clone();
+        }
+        let atlas = Rc::clone(&self.atlas);
+        let (width, height) = self.atlas_dimensions();
+        let keyed_load = future_to_promise(async move {
+            JsFuture::from(atlas.decode()).await?;
+            // The sprites share the atlas, so replacing its source keys all of them.
+            let src = apply_color_key(&atlas, width, height, color_key)
+                .map_err(|e| JsValue::from_str(&format!("{:?}", e)))?;
+            atlas.set_src(&src);
+            JsFuture::from(atlas.decode()).await
+        });
+        self.keyed_load.replace(Some(keyed_load.clone()));
+        keyed_load
+    }
+
     /// sprite returns a specified Sprite on the atlas.
     pub fn sprite(&self, index: usize) -> Result<&Sprite> {
         self.store.get(index).with_context(|| {
@@ -219,10 +707,228 @@ impl SpriteStore {
         let index = col + row * self.width_in_tile;
         self.sprite(index)
     }
+
+    /// trim_transparency returns the tightest box around the non-transparent pixels of the sprite
+    /// at col and row, as the offset from the top-left corner of the tile, the width and the
+    /// height. A fully transparent sprite returns a box of 0x0.
+    /// It reads the pixels back from a canvas, so call it once after on_load, not every frame.
+    pub fn trim_transparency(&self, col: usize, row: usize) -> Result<(Position, f64, f64)> {
+        let sprite = self.sprite_by_col_and_row(col, row)?;
+        let (width, height) = (self.tile_width, self.tile_height);
+        let canvas = offscreen_canvas(width, height)?;
+        let context = context_2d(&canvas)?;
+        context
+            .draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+                &self.atlas,
+                sprite.sx(),
+                sprite.sy(),
+                sprite.width(),
+                sprite.height(),
+                0.0,
+                0.0,
+                sprite.width(),
+                sprite.height(),
+            )
+            .map_err(|e| anyhow!("failed to draw image: {:?}", e))?;
+        let pixels = context
+            .get_image_data(0.0, 0.0, width as f64, height as f64)
+            .map_err(|e| anyhow!("failed to read the pixels: {:?}", e))?
+            .data();
+        Ok(
+            match opaque_bounds(&pixels, width as usize, height as usize) {
+                Some((left, top, right, bottom)) => (
+                    Position::new(left as f64, top as f64),
+                    (right - left + 1) as f64,
+                    (bottom - top + 1) as f64,
+                ),
+                None => (Position::new(0.0, 0.0), 0.0, 0.0),
+            },
+        )
+    }
+
+    /// create_composite_sprite layers the sprites at indices, each at the offset of the same
+    /// index from the top-left corner, into a new Sprite, e.g. a body with armor and a weapon.
+    /// The first sprite is the bottom. The size covers all the layers.
+    /// It draws on an offscreen canvas and encodes it as a data URL, so call it once per change
+    /// after on_load, not every frame. Await the decode of Sprite::atlas before drawing it.
+    pub fn create_composite_sprite(
+        &self,
+        indices: &[usize],
+        offsets: &[Position],
+    ) -> Result<Sprite> {
+        ensure!(
+            !indices.is_empty(),
+            "no sprite is given to create a composite sprite"
+        );
+        ensure!(
+            indices.len() == offsets.len(),
+            "indices: {} and offsets: {} should have the same length",
+            indices.len(),
+            offsets.len()
+        );
+        ensure!(
+            offsets
+                .iter()
+                .all(|offset| offset.dx() >= 0.0 && offset.dy() >= 0.0),
+            "offsets should not be negative"
+        );
+        let (tile_width, tile_height) = (self.tile_width as f64, self.tile_height as f64);
+        let width = offsets
+            .iter()
+            .map(|offset| offset.dx() + tile_width)
+            .fold(0.0, f64::max)
+            .ceil();
+        let height = offsets
+            .iter()
+            .map(|offset| offset.dy() + tile_height)
+            .fold(0.0, f64::max)
+            .ceil();
+        let canvas = offscreen_canvas(width as u32, height as u32)?;
+        let context = context_2d(&canvas)?;
+        for (&index, offset) in indices.iter().zip(offsets) {
+            let sprite = self.sprite(index)?;
+            context
+                .draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+                    &self.atlas,
+                    sprite.sx(),
+                    sprite.sy(),
+                    sprite.width(),
+                    sprite.height(),
+                    offset.dx(),
+                    offset.dy(),
+                    sprite.width(),
+                    sprite.height(),
+                )
+                .map_err(|e| anyhow!("failed to draw image: {:?}", e))?;
+        }
+        let src = canvas
+            .to_data_url()
+            .map_err(|e| anyhow!("failed to encode the composite sprite: {:?}", e))?;
+        let image = web_sys::HtmlImageElement::new()
+            .map_err(|e| anyhow!("failed to create a new html image element: {:?}", e))?;
+        image.set_src(&src);
+        Ok(Sprite::new(Rc::new(image), 0.0, 0.0, width, height))
+    }
+}
+
+// offscreen_canvas returns a canvas of width x height which is not attached to the document.
+pub(crate) fn offscreen_canvas(width: u32, height: u32) -> Result<web_sys::HtmlCanvasElement> {
+    let canvas = web_sys::window()
+        .and_then(|window| window.document())
+        .context("no document exists to create a canvas")?
+        .create_element("canvas")
+        .map_err(|e| anyhow!("failed to create a canvas: {:?}", e))?
+        .dyn_into::<web_sys::HtmlCanvasElement>()
+        .map_err(|_| anyhow!("the created element is not a canvas"))?;
+    canvas.set_width(width);
+    canvas.set_height(height);
+    Ok(canvas)
+}
+
+// context_2d returns the 2d context of canvas, e.g. to read pixels back.
+pub(crate) fn context_2d(
+    canvas: &web_sys::HtmlCanvasElement,
+) -> Result<web_sys::CanvasRenderingContext2d> {
+    canvas
+        .get_context("2d")
+        .map_err(|e| anyhow!("failed to get a 2d context: {:?}", e))?
+        .context("the canvas has no 2d context")?
+        .dyn_into::<web_sys::CanvasRenderingContext2d>()
+        .map_err(|_| anyhow!("the context is not a 2d context"))
+}
+
+// sprite_data_url draws the region of sprite on a canvas of its size, and returns it as a data
+// URL of a PNG, e.g. for cursors and favicons.
+pub(crate) fn sprite_data_url(sprite: &Sprite) -> Result<String> {
+    let (width, height) = (sprite.width().ceil(), sprite.height().ceil());
+    let canvas = offscreen_canvas(width as u32, height as u32)?;
+    let context = context_2d(&canvas)?;
+    context
+        .draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+            &sprite.atlas,
+            sprite.sx(),
+            sprite.sy(),
+            sprite.width(),
+            sprite.height(),
+            0.0,
+            0.0,
+            sprite.width(),
+            sprite.height(),
+        )
+        .map_err(|e| anyhow!("failed to draw image: {:?}", e))?;
+    // An atlas from another origin without CORS taints the canvas, which cannot be exported.
+    canvas.to_data_url().map_err(|e| {
+        anyhow!(
+            "failed to encode the sprite; its atlas may be from another origin without CORS: {:?}",
+            e
+        )
+    })
+}
+
+// apply_color_key draws atlas of width x height with the pixels of color_key made transparent,
+// and returns it as a data URL of a PNG.
+fn apply_color_key(
+    atlas: &web_sys::HtmlImageElement,
+    width: u32,
+    height: u32,
+    color_key: (u8, u8, u8),
+) -> Result<String> {
+    let canvas = offscreen_canvas(width, height)?;
+    let context = context_2d(&canvas)?;
+    context
+        .draw_image_with_html_image_element(atlas, 0.0, 0.0)
+        .map_err(|e| anyhow!("failed to draw image: {:?}", e))?;
+    let mut pixels = context
+        .get_image_data(0.0, 0.0, width as f64, height as f64)
+        .map_err(|e| anyhow!("failed to read the pixels: {:?}", e))?
+        .data();
+    let (r, g, b) = color_key;
+    for pixel in pixels.chunks_exact_mut(4) {
+        if pixel[..3] == [r, g, b] {
+            pixel.copy_from_slice(&[0, 0, 0, 0]);
+        }
+    }
+    let image_data = web_sys::ImageData::new_with_u8_clamped_array_and_sh(
+        wasm_bindgen::Clamped(&pixels),
+        width,
+        height,
+    )
+    .map_err(|e| anyhow!("failed to create the pixels: {:?}", e))?;
+    context
+        .put_image_data(&image_data, 0.0, 0.0)
+        .map_err(|e| anyhow!("failed to write the pixels: {:?}", e))?;
+    canvas
+        .to_data_url()
+        .map_err(|e| anyhow!("failed to encode the atlas: {:?}", e))
+}
+
+// opaque_bounds returns the inclusive left, top, right and bottom of the pixels whose alpha is
+// not 0 in RGBA pixels of width x height.
+fn opaque_bounds(
+    pixels: &[u8],
+    width: usize,
+    height: usize,
+) -> Option<(usize, usize, usize, usize)> {
+    let mut bounds: Option<(usize, usize, usize, usize)> = None;
+    for y in 0..height {
+        for x in 0..width {
+            if pixels[(y * width + x) * 4 + 3] == 0 {
+                continue;
+            }
+            bounds = Some(match bounds {
+                Some((left, top, right, bottom)) => {
+                    (left.min(x), top.min(y), right.max(x), bottom.max(y))
+                }
+                None => (x, y, x, y),
+            });
+        }
+    }
+    bounds
 }
 
 /// Position is responsible for specifing a position on a canvas.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Position {
     dx: f64,
     dy: f64,
@@ -243,4 +949,63 @@ impl Position {
     pub fn dy(&self) -> f64 {
         self.dy
     }
+
+    /// snap_to_grid returns the nearest tile-aligned Position.
+    pub fn snap_to_grid(&self, tile_w: f64, tile_h: f64) -> Position {
+        Position::new(
+            (self.dx / tile_w).round() * tile_w,
+            (self.dy / tile_h).round() * tile_h,
+        )
+    }
+
+    /// floor_to_grid returns the tile-aligned Position on the top-left side.
+    pub fn floor_to_grid(&self, tile_w: f64, tile_h: f64) -> Position {
+        Position::new(
+            (self.dx / tile_w).floor() * tile_w,
+            (self.dy / tile_h).floor() * tile_h,
+        )
+    }
+
+    /// ceil_to_grid returns the tile-aligned Position on the bottom-right side.
+    pub fn ceil_to_grid(&self, tile_w: f64, tile_h: f64) -> Position {
+        Position::new(
+            (self.dx / tile_w).ceil() * tile_w,
+            (self.dy / tile_h).ceil() * tile_h,
+        )
+    }
+
+    /// to_tile_index returns the index of the tile at the Position on a grid of grid_width tiles
+    /// of tile_w x tile_h. It returns None if the Position is left of or above the grid, or right
+    /// of its grid_width tiles, or if the grid or the tiles have no size.
+    pub fn to_tile_index(&self, tile_w: f64, tile_h: f64, grid_width: usize) -> Option<usize> {
+        if !(tile_w > 0.0 && tile_h > 0.0) || self.dx < 0.0 || self.dy < 0.0 {
+            return None;
+        }
+        let (col, row) = ((self.dx / tile_w) as usize, (self.dy / tile_h) as usize);
+        if col >= grid_width {
+            return None;
+        }
+        row.checked_mul(grid_width)?.checked_add(col)
+    }
+
+    /// from_tile_index returns the Position of the top-left corner of the tile at index on a grid
+    /// of grid_width tiles of tile_w x tile_h. It returns None if grid_width is 0.
+    pub fn from_tile_index(
+        index: usize,
+        tile_w: f64,
+        tile_h: f64,
+        grid_width: usize,
+    ) -> Option<Self> {
+        if grid_width == 0 {
+            return None;
+        }
+        Some(Self::new(
+            (index % grid_width) as f64 * tile_w,
+            (index / grid_width) as f64 * tile_h,
+        ))
+    }
 }
+
+/// Location is a deprecated alias of Position.
+#[deprecated(note = "use Position instead; Location is kept so that older tutorials compile")]
+pub type Location = Position;
@@ -0,0 +1,87 @@
+use anyhow::{anyhow, ensure, Result};
+
+use crate::render::color::Color;
+use crate::render::{Position, Renderer};
+use crate::test::DrawCommand;
+
+/// draw_arc strokes the arc of radius around center from start_rad to end_rad clockwise, width
+/// pixels wide, e.g. for progress rings and radar sweeps. 0.0 is to the right of the center.
+/// It returns an error if radius is not positive.
+pub fn draw_arc(
+    renderer: &Renderer,
+    center: Position,
+    radius: f64,
+    start_rad: f64,
+    end_rad: f64,
+    color: Color,
+    width: f64,
+) -> Result<()> {
+    ensure!(radius > 0.0, "radius: {} should be positive", radius);
+    let recorded = renderer.record(|| DrawCommand::Arc {
+        dx: center.dx(),
+        dy: center.dy(),
+        radius,
+        start_rad,
+        end_rad,
+        color,
+        filled: false,
+    });
+    if recorded {
+        return Ok(());
+    }
+
+    let context = renderer.context();
+    context.save();
+    context.begin_path();
+    let result = context
+        .arc(center.dx(), center.dy(), radius, start_rad, end_rad)
+        .map_err(|e| anyhow!("failed to add an arc of radius {}: {:?}", radius, e));
+    if result.is_ok() {
+        context.set_stroke_style(&(&color).into());
+        context.set_line_width(width);
+        context.stroke();
+    }
+    context.restore();
+    result
+}
+
+/// draw_pie_slice fills the slice of the circle of radius around center from start_rad to
+/// end_rad clockwise, e.g. for cooldown timers. 0.0 is to the right of the center.
+/// It returns an error if radius is not positive.
+pub fn draw_pie_slice(
+    renderer: &Renderer,
+    center: Position,
+    radius: f64,
+    start_rad: f64,
+    end_rad: f64,
+    color: Color,
+) -> Result<()> {
+    ensure!(radius > 0.0, "radius: {} should be positive", radius);
+    let recorded = renderer.record(|| DrawCommand::Arc {
+        dx: center.dx(),
+        dy: center.dy(),
+        radius,
+        start_rad,
+        end_rad,
+        color,
+        filled: true,
+    });
+    if recorded {
+        return Ok(());
+    }
+
+    let context = renderer.context();
+    context.save();
+    context.begin_path();
+    context.move_to(center.dx(), center.dy());
+    let result = context
+        .arc(center.dx(), center.dy(), radius, start_rad, end_rad)
+        .map_err(|e| anyhow!("failed to add an arc of radius {}: {:?}", radius, e));
+    if result.is_ok() {
+        context.close_path();
+        context.set_fill_style(&(&color).into());
+        context.fill();
+    }
+    context.restore();
+    result
+}
@@ -0,0 +1,246 @@
+use std::rc::Rc;
+
+use anyhow::Result;
+
+use crate::render::color::Color;
+use crate::render::{
+    draw_sprite, draw_text, fill_rect, overlaps_canvas, Font, Position, Renderer, ScaledSprite,
+    Sprite,
+};
+
+/// SpriteBatch accumulates draws and issues them grouped by atlas.
+/// Draws with the same atlas keep the order in which they are pushed.
+#[derive(Debug, Default)]
+pub struct SpriteBatch<'a> {
+    commands: Vec<BatchCommand<'a>>,
+}
+
+#[derive(Debug)]
+struct BatchCommand<'a> {
+    sprite: &'a Sprite,
+    position: Position,
+    scale_x: f64,
+    scale_y: f64,
+}
+
+impl<'a> SpriteBatch<'a> {
+    /// new returns an empty SpriteBatch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// push adds a draw of sprite at position.
+    pub fn push(&mut self, sprite: &'a Sprite, position: Position) {
+        self.push_scaled(sprite, position, 1.0, 1.0);
+    }
+
+    /// push_scaled adds a draw of sprite at position scaled by scale_x and scale_y.
+    pub fn push_scaled(
+        &mut self,
+        sprite: &'a Sprite,
+        position: Position,
+        scale_x: f64,
+        scale_y: f64,
+    ) {
+        self.commands.push(BatchCommand {
+            sprite,
+            position,
+            scale_x,
+            scale_y,
+        });
+    }
+
+    /// len returns the number of the draws in the batch.
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// is_empty returns true when the batch has no draws.
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// flush sorts the draws by atlas and issues them on the canvas. The batch becomes empty.
+    /// Draws which are out of canvas are skipped.
+    pub fn flush(&mut self, renderer: &Renderer) -> Result<()> {
+        // sort_by_key is stable, so draws on the same atlas keep their order.
+        self.commands
+            .sort_by_key(|command| Rc::as_ptr(&command.sprite.atlas) as usize);
+        for command in self.commands.drain(..) {
+            let width = command.sprite.width() * command.scale_x;
+            let height = command.sprite.height() * command.scale_y;
+            let dx = command.position.dx();
+            let dy = command.position.dy();
+            if !overlaps_canvas(renderer, dx, dy, width, height) {
+                continue;
+            }
+            draw_sprite(renderer, command.sprite, dx, dy, width, height)?;
+        }
+        Ok(())
+    }
+}
+
+/// Batch accumulates draws and issues them sorted by depth, from the lowest to the highest,
+/// e.g. with the bottom y of each object as the depth for Y-sorting.
+/// Draws with the same depth keep the order in which they are pushed.
+#[derive(Debug, Default)]
+pub struct Batch<'a> {
+    commands: Vec<DepthCommand<'a>>,
+}
+
+#[derive(Debug)]
+struct DepthCommand<'a> {
+    depth: i32,
+    kind: DrawKind<'a>,
+}
+
+#[derive(Debug)]
+enum DrawKind<'a> {
+    Sprite {
+        sprite: &'a Sprite,
+        position: Position,
+        width: f64,
+        height: f64,
+    },
+    Rect {
+        position: Position,
+        width: f64,
+        height: f64,
+        color: Color,
+    },
+    Text {
+        text: String,
+        position: Position,
+        font: String,
+        color: Color,
+    },
+}
+
+impl<'a> Batch<'a> {
+    /// new returns an empty Batch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// push_sprite adds a draw of sprite at position.
+    pub fn push_sprite(&mut self, depth: i32, sprite: &'a Sprite, position: Position) {
+        self.push(
+            depth,
+            DrawKind::Sprite {
+                sprite,
+                position,
+                width: sprite.width(),
+                height: sprite.height(),
+            },
+        );
+    }
+
+    /// push_scaled_sprite adds a draw of scaled_sprite at position.
+    pub fn push_scaled_sprite(
+        &mut self,
+        depth: i32,
+        scaled_sprite: ScaledSprite<'a>,
+        position: Position,
+    ) {
+        self.push(
+            depth,
+            DrawKind::Sprite {
+                sprite: scaled_sprite.sprite(),
+                position,
+                width: scaled_sprite.width(),
+                height: scaled_sprite.height(),
+            },
+        );
+    }
+
+    /// push_rect adds a rectangle of width and height at position filled with color.
+    pub fn push_rect(
+        &mut self,
+        depth: i32,
+        position: Position,
+        width: f64,
+        height: f64,
+        color: Color,
+    ) {
+        self.push(
+            depth,
+            DrawKind::Rect {
+                position,
+                width,
+                height,
+                color,
+            },
+        );
+    }
+
+    /// push_text adds text filled with font and color. position is the left end of the baseline.
+    pub fn push_text(
+        &mut self,
+        depth: i32,
+        text: &str,
+        position: Position,
+        font: &Font,
+        color: Color,
+    ) {
+        self.push(
+            depth,
+            DrawKind::Text {
+                text: String::from(text),
+                position,
+                font: String::from(font.css_string()),
+                color,
+            },
+        );
+    }
+
+    /// len returns the number of the draws in the batch.
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// is_empty returns true when the batch has no draws.
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// flush sorts the draws by depth and issues them on the canvas. The batch becomes empty.
+    /// Sprites which are out of canvas are skipped.
+    pub fn flush(&mut self, renderer: &Renderer) -> Result<()> {
+        // sort_by_key is stable, so draws at the same depth keep their order.
+        self.commands.sort_by_key(|command| command.depth);
+        for command in self.commands.drain(..) {
+            match command.kind {
+                DrawKind::Sprite {
+                    sprite,
+                    position,
+                    width,
+                    height,
+                } => {
+                    let dx = position.dx();
+                    let dy = position.dy();
+                    if !overlaps_canvas(renderer, dx, dy, width, height) {
+                        continue;
+                    }
+                    draw_sprite(renderer, sprite, dx, dy, width, height)?;
+                }
+                DrawKind::Rect {
+                    position,
+                    width,
+                    height,
+                    color,
+                } => fill_rect(renderer, position.dx(), position.dy(), width, height, color),
+                DrawKind::Text {
+                    text,
+                    position,
+                    font,
+                    color,
+                } => draw_text(renderer, &text, position, &font, color)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, depth: i32, kind: DrawKind<'a>) {
+        self.commands.push(DepthCommand { depth, kind });
+    }
+}
@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+
+use anyhow::{ensure, Result};
+
+use crate::render::{draw_sprite, overlaps_canvas, Position, Renderer, SpriteStore};
+
+/// BitmapFont draws text with glyphs on a sprite-sheet, e.g. the font of a pixel-art game.
+/// Every glyph has the size of a tile of the SpriteStore.
+#[derive(Debug)]
+pub struct BitmapFont {
+    store: SpriteStore,
+    glyph_map: HashMap<char, usize>,
+}
+
+impl BitmapFont {
+    /// new returns a BitmapFont whose glyphs are the characters of mapping in row-major order,
+    /// e.g. "ABCDEFGH" for a sheet of 4x2 tiles whose first row is A to D.
+    /// It returns an error if mapping has more characters than the tiles of store.
+    pub fn new(store: SpriteStore, mapping: &str) -> Result<Self> {
+        let count = mapping.chars().count();
+        let (atlas_width, atlas_height) = store.atlas_dimensions();
+        let (tile_width, tile_height) = store.tile_dimensions();
+        let tiles = (atlas_width / tile_width * (atlas_height / tile_height)) as usize;
+        ensure!(
+            count <= tiles,
+            "the mapping has {} characters, but the store has only {} glyphs",
+            count,
+            tiles
+        );
+        let glyph_map = mapping
+            .chars()
+            .enumerate()
+            .map(|(index, c)| (c, index))
+            .collect();
+        Ok(Self { store, glyph_map })
+    }
+
+    /// draw_text draws text with the top-left corner at position, each glyph scaled by scale.
+    /// '\n' starts a new line. Characters without a glyph, e.g. spaces, leave a blank glyph.
+    /// Glyphs out of canvas are skipped.
+    pub fn draw_text(
+        &self,
+        renderer: &Renderer,
+        text: &str,
+        position: Position,
+        scale: f64,
+    ) -> Result<()> {
+        let (glyph_width, glyph_height) = self.glyph_size(scale);
+        let mut dx = position.dx();
+        let mut dy = position.dy();
+        for c in text.chars() {
+            if c == '\n' {
+                dx = position.dx();
+                dy += glyph_height;
+                continue;
+            }
+            if let Some(&index) = self.glyph_map.get(&c) {
+                if overlaps_canvas(renderer, dx, dy, glyph_width, glyph_height) {
+                    let sprite = self.store.sprite(index)?;
+                    draw_sprite(renderer, sprite, dx, dy, glyph_width, glyph_height)?;
+                }
+            }
+            dx += glyph_width;
+        }
+        Ok(())
+    }
+
+    /// measure returns the width and height of text drawn by draw_text with scale.
+    pub fn measure(&self, text: &str, scale: f64) -> (f64, f64) {
+        let (glyph_width, glyph_height) = self.glyph_size(scale);
+        let lines = text.split('\n');
+        let (columns, rows) = lines.fold((0, 0), |(columns, rows), line| {
+            (columns.max(line.chars().count()), rows + 1)
+        });
+        (columns as f64 * glyph_width, rows as f64 * glyph_height)
+    }
+
+    /// has_glyph returns true when c has a glyph.
+    pub fn has_glyph(&self, c: char) -> bool {
+        self.glyph_map.contains_key(&c)
+    }
+
+    /// store returns the SpriteStore of the glyphs.
+    pub fn store(&self) -> &SpriteStore {
+        &self.store
+    }
+
+    fn glyph_size(&self, scale: f64) -> (f64, f64) {
+        let (tile_width, tile_height) = self.store.tile_dimensions();
+        (tile_width as f64 * scale, tile_height as f64 * scale)
+    }
+}
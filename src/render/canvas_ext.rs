@@ -0,0 +1,132 @@
+use anyhow::{anyhow, Result};
+
+use crate::render::color::Color;
+use crate::render::Sprite;
+
+/// Canvas2dExt adds wrappers to the context returned by Renderer::context, which return
+/// anyhow::Result instead of Result<(), JsValue> and fill or stroke with a Color.
+/// The fallible ones are prefixed with try_ not to be shadowed by the methods of web_sys.
+pub trait Canvas2dExt {
+    /// fill_rect_with fills the rectangle with color, keeping the fill style.
+    fn fill_rect_with(&self, color: Color, x: f64, y: f64, width: f64, height: f64);
+    /// stroke_rect_with outlines the rectangle with color, keeping the stroke style.
+    fn stroke_rect_with(&self, color: Color, x: f64, y: f64, width: f64, height: f64);
+    /// fill_circle_with fills the circle of radius around (x, y) with color, keeping the fill
+    /// style. It begins a new path.
+    fn fill_circle_with(&self, color: Color, x: f64, y: f64, radius: f64) -> Result<()>;
+    /// try_fill_text fills text with its baseline starting at (x, y).
+    fn try_fill_text(&self, text: &str, x: f64, y: f64) -> Result<()>;
+    /// try_stroke_text outlines text with its baseline starting at (x, y).
+    fn try_stroke_text(&self, text: &str, x: f64, y: f64) -> Result<()>;
+    /// try_measure_text returns the width of text in the current font.
+    fn try_measure_text(&self, text: &str) -> Result<f64>;
+    /// try_draw_image draws the whole image with its top-left corner at (x, y).
+    fn try_draw_image(&self, image: &web_sys::HtmlImageElement, x: f64, y: f64) -> Result<()>;
+    /// try_draw_sprite draws sprite in the rectangle of width x height at (x, y).
+    fn try_draw_sprite(
+        &self,
+        sprite: &Sprite,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    ) -> Result<()>;
+    /// try_arc adds an arc of radius around (x, y) from start_rad to end_rad clockwise to the
+    /// path.
+    fn try_arc(&self, x: f64, y: f64, radius: f64, start_rad: f64, end_rad: f64) -> Result<()>;
+    /// try_translate moves the origin by (x, y).
+    fn try_translate(&self, x: f64, y: f64) -> Result<()>;
+    /// try_rotate rotates the coordinates clockwise by angle_rad around the origin.
+    fn try_rotate(&self, angle_rad: f64) -> Result<()>;
+    /// try_scale scales the coordinates by x and y.
+    fn try_scale(&self, x: f64, y: f64) -> Result<()>;
+}
+
+impl Canvas2dExt for web_sys::CanvasRenderingContext2d {
+    fn fill_rect_with(&self, color: Color, x: f64, y: f64, width: f64, height: f64) {
+        self.save();
+        self.set_fill_style(&(&color).into());
+        self.fill_rect(x, y, width, height);
+        self.restore();
+    }
+
+    fn stroke_rect_with(&self, color: Color, x: f64, y: f64, width: f64, height: f64) {
+        self.save();
+        self.set_stroke_style(&(&color).into());
+        self.stroke_rect(x, y, width, height);
+        self.restore();
+    }
+
+    fn fill_circle_with(&self, color: Color, x: f64, y: f64, radius: f64) -> Result<()> {
+        self.begin_path();
+        self.try_arc(x, y, radius, 0.0, std::f64::consts::TAU)?;
+        self.save();
+        self.set_fill_style(&(&color).into());
+        self.fill();
+        self.restore();
+        Ok(())
+    }
+
+    fn try_fill_text(&self, text: &str, x: f64, y: f64) -> Result<()> {
+        self.fill_text(text, x, y)
+            .map_err(|e| anyhow!("failed to fill text {:?}: {:?}", text, e))
+    }
+
+    fn try_stroke_text(&self, text: &str, x: f64, y: f64) -> Result<()> {
+        self.stroke_text(text, x, y)
+            .map_err(|e| anyhow!("failed to stroke text {:?}: {:?}", text, e))
+    }
+
+    fn try_measure_text(&self, text: &str) -> Result<f64> {
+        self.measure_text(text)
+            .map(|metrics| metrics.width())
+            .map_err(|e| anyhow!("failed to measure text {:?}: {:?}", text, e))
+    }
+
+    fn try_draw_image(&self, image: &web_sys::HtmlImageElement, x: f64, y: f64) -> Result<()> {
+        self.draw_image_with_html_image_element(image, x, y)
+            .map_err(|e| anyhow!("failed to draw image: {:?}", e))
+    }
+
+    fn try_draw_sprite(
+        &self,
+        sprite: &Sprite,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    ) -> Result<()> {
+        self.draw_image_with_html_image_element_and_sw_and_sh_and_dx_and_dy_and_dw_and_dh(
+            &sprite.atlas,
+            sprite.sx(),
+            sprite.sy(),
+            sprite.width(),
+            sprite.height(),
+            x,
+            y,
+            width,
+            height,
+        )
+        .map_err(|e| anyhow!("failed to draw image: {:?}", e))
+    }
+
+    fn try_arc(&self, x: f64, y: f64, radius: f64, start_rad: f64, end_rad: f64) -> Result<()> {
+        self.arc(x, y, radius, start_rad, end_rad)
+            .map_err(|e| anyhow!("failed to add an arc of radius {}: {:?}", radius, e))
+    }
+
+    fn try_translate(&self, x: f64, y: f64) -> Result<()> {
+        self.translate(x, y)
+            .map_err(|e| anyhow!("failed to translate: {:?}", e))
+    }
+
+    fn try_rotate(&self, angle_rad: f64) -> Result<()> {
+        self.rotate(angle_rad)
+            .map_err(|e| anyhow!("failed to rotate: {:?}", e))
+    }
+
+    fn try_scale(&self, x: f64, y: f64) -> Result<()> {
+        self.scale(x, y)
+            .map_err(|e| anyhow!("failed to scale: {:?}", e))
+    }
+}
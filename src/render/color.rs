@@ -0,0 +1,117 @@
+use std::fmt;
+
+use anyhow::{ensure, Context, Result};
+use wasm_bindgen::JsValue;
+
+/// Color is responsible for representing an RGBA color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Color {
+    r: u8,
+    g: u8,
+    b: u8,
+    a: u8,
+}
+
+impl Color {
+    /// BLACK is an opaque black.
+    pub const BLACK: Color = Color::rgb(0, 0, 0);
+    /// WHITE is an opaque white.
+    pub const WHITE: Color = Color::rgb(255, 255, 255);
+    /// TRANSPARENT is a fully transparent black.
+    pub const TRANSPARENT: Color = Color::rgba(0, 0, 0, 0);
+
+    /// rgb returns an opaque Color.
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self::rgba(r, g, b, 255)
+    }
+
+    /// rgba returns a Color with alpha. 0 is transparent and 255 is opaque.
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// hex parses a Color from "#rgb", "#rrggbb", or "#rrggbbaa". The leading '#' is optional.
+    pub fn hex(hex: &str) -> Result<Self> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        ensure!(
+            digits.is_ascii() && matches!(digits.len(), 3 | 6 | 8),
+            "hex: {} should be #rgb, #rrggbb, or #rrggbbaa",
+            hex
+        );
+        let parse = |digits: &str| {
+            u8::from_str_radix(digits, 16)
+                .with_context(|| format!("hex: {} should consist of hex digits", hex))
+        };
+        if digits.len() == 3 {
+            let mut channels = [0; 3];
+            for (channel, i) in channels.iter_mut().zip(0..3) {
+                // "f" means "ff".
+                *channel = parse(&digits[i..i + 1])? * 17;
+            }
+            return Ok(Self::rgb(channels[0], channels[1], channels[2]));
+        }
+        let a = if digits.len() == 8 {
+            parse(&digits[6..8])?
+        } else {
+            255
+        };
+        Ok(Self::rgba(
+            parse(&digits[0..2])?,
+            parse(&digits[2..4])?,
+            parse(&digits[4..6])?,
+            a,
+        ))
+    }
+
+    /// r is a red component.
+    pub fn r(&self) -> u8 {
+        self.r
+    }
+
+    /// g is a green component.
+    pub fn g(&self) -> u8 {
+        self.g
+    }
+
+    /// b is a blue component.
+    pub fn b(&self) -> u8 {
+        self.b
+    }
+
+    /// a is an alpha component.
+    pub fn a(&self) -> u8 {
+        self.a
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "rgba({}, {}, {}, {})",
+            self.r,
+            self.g,
+            self.b,
+            self.a as f64 / 255.0
+        )
+    }
+}
+
+impl From<&Color> for JsValue {
+    fn from(color: &Color) -> Self {
+        JsValue::from_str(&color.to_string())
+    }
+}
+
+impl From<(u8, u8, u8)> for Color {
+    fn from((r, g, b): (u8, u8, u8)) -> Self {
+        Self::rgb(r, g, b)
+    }
+}
+
+impl From<(u8, u8, u8, u8)> for Color {
+    fn from((r, g, b, a): (u8, u8, u8, u8)) -> Self {
+        Self::rgba(r, g, b, a)
+    }
+}
@@ -0,0 +1,91 @@
+use anyhow::Result;
+
+use crate::render::{sprite_data_url, Sprite};
+
+/// Cursor is the appearance of the mouse cursor over the canvas, set by Renderer::set_cursor.
+#[derive(Debug, Clone, Default)]
+pub enum Cursor {
+    /// Default is the cursor of the browser, usually an arrow. It is the default.
+    #[default]
+    Default,
+    /// Pointer is a hand, e.g. over buttons.
+    Pointer,
+    /// Crosshair is a cross, e.g. for aiming.
+    Crosshair,
+    /// Text is an I-beam over editable text.
+    Text,
+    /// Move shows that something can be moved.
+    Move,
+    /// Grab shows that something can be grabbed.
+    Grab,
+    /// Grabbing shows that something is being grabbed.
+    Grabbing,
+    /// NotAllowed shows that the action is not allowed.
+    NotAllowed,
+    /// Wait shows that the game is busy.
+    Wait,
+    /// Help shows that help is available.
+    Help,
+    /// EwResize is a horizontal resize arrow, e.g. over the left and right handles.
+    EwResize,
+    /// NsResize is a vertical resize arrow, e.g. over the top and bottom handles.
+    NsResize,
+    /// NeswResize is a diagonal resize arrow for the top-right and bottom-left corners.
+    NeswResize,
+    /// NwseResize is a diagonal resize arrow for the top-left and bottom-right corners.
+    NwseResize,
+    /// Hidden hides the cursor, e.g. to draw a crosshair sprite instead.
+    Hidden,
+    /// Custom shows image with its hotspot, the clicking point, at (x, y) pixels of the image.
+    /// Browsers ignore images larger than 128x128, and 32x32 is the safe size.
+    Custom {
+        /// image is the image of the cursor.
+        image: CursorImage,
+        /// hotspot is the (x, y) of the clicking point from the top-left corner of the image.
+        hotspot: (u32, u32),
+    },
+}
+
+/// CursorImage is the image of a Cursor::Custom.
+#[derive(Debug, Clone)]
+pub enum CursorImage {
+    /// Sprite is the region of an atlas. It must be loaded when the cursor is set.
+    Sprite(Sprite),
+    /// DataUrl is an image as a data URL, e.g. "data:image/png;base64,...".
+    DataUrl(String),
+}
+
+impl Cursor {
+    // css_value returns the value of the CSS cursor property. A custom cursor falls back to
+    // the default one when the browser cannot show its image.
+    pub(crate) fn css_value(&self) -> Result<String> {
+        let keyword = match self {
+            Self::Default => "default",
+            Self::Pointer => "pointer",
+            Self::Crosshair => "crosshair",
+            Self::Text => "text",
+            Self::Move => "move",
+            Self::Grab => "grab",
+            Self::Grabbing => "grabbing",
+            Self::NotAllowed => "not-allowed",
+            Self::Wait => "wait",
+            Self::Help => "help",
+            Self::EwResize => "ew-resize",
+            Self::NsResize => "ns-resize",
+            Self::NeswResize => "nesw-resize",
+            Self::NwseResize => "nwse-resize",
+            Self::Hidden => "none",
+            Self::Custom { image, hotspot } => {
+                let url = match image {
+                    CursorImage::Sprite(sprite) => sprite_data_url(sprite)?,
+                    CursorImage::DataUrl(url) => url.clone(),
+                };
+                return Ok(format!(
+                    "url(\"{}\") {} {}, auto",
+                    url, hotspot.0, hotspot.1
+                ));
+            }
+        };
+        Ok(String::from(keyword))
+    }
+}
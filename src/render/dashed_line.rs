@@ -0,0 +1,52 @@
+use anyhow::{anyhow, ensure, Result};
+
+use crate::render::color::Color;
+use crate::render::{Position, Renderer};
+use crate::test::DrawCommand;
+
+/// draw_dashed_line strokes a line of width pixels from from to to, with dashes of dash pixels
+/// separated by gaps of gap pixels, e.g. for range indicators and UI borders.
+/// The line dash of the context is restored afterwards, so it does not leak into other draws.
+/// It returns an error if dash is not positive or gap is negative.
+pub fn draw_dashed_line(
+    renderer: &Renderer,
+    from: Position,
+    to: Position,
+    dash: f64,
+    gap: f64,
+    color: Color,
+    width: f64,
+) -> Result<()> {
+    ensure!(dash > 0.0, "dash: {} should be positive", dash);
+    ensure!(gap >= 0.0, "gap: {} should not be negative", gap);
+    let recorded = renderer.record(|| DrawCommand::DashedLine {
+        x1: from.dx(),
+        y1: from.dy(),
+        x2: to.dx(),
+        y2: to.dy(),
+        dash,
+        gap,
+        color,
+        width,
+    });
+    if recorded {
+        return Ok(());
+    }
+
+    let context = renderer.context();
+    context.save();
+    let pattern = js_sys::Array::of2(&dash.into(), &gap.into());
+    let result = context
+        .set_line_dash(&pattern)
+        .map_err(|e| anyhow!("failed to set line dash: {:?}", e));
+    if result.is_ok() {
+        context.set_stroke_style(&(&color).into());
+        context.set_line_width(width);
+        context.begin_path();
+        context.move_to(from.dx(), from.dy());
+        context.line_to(to.dx(), to.dy());
+        context.stroke();
+    }
+    context.restore();
+    result
+}
@@ -0,0 +1,28 @@
+/// Font is a CSS font built once, so that its string is not formatted on every draw.
+/// Pass it to Renderer::set_font, which skips the context call if the font is unchanged.
+/// Batch::push_text and the text drawn by the engine share the same cache.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Font {
+    css: String,
+    size_px: u32,
+}
+
+impl Font {
+    /// new returns a Font of size_px pixels in family, e.g. "monospace".
+    pub fn new(size_px: u32, family: &str) -> Self {
+        Self {
+            css: format!("{}px {}", size_px, family),
+            size_px,
+        }
+    }
+
+    /// css_string returns the CSS font, e.g. "16px monospace".
+    pub fn css_string(&self) -> &str {
+        &self.css
+    }
+
+    /// size_px returns the size of the font in pixels.
+    pub fn size_px(&self) -> u32 {
+        self.size_px
+    }
+}
@@ -0,0 +1,94 @@
+use anyhow::{ensure, Result};
+
+/// Grid stores a value for each cell of cols x rows, e.g. sprite indices of a tile layer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "GridData<T>")
+)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    cols: usize,
+    rows: usize,
+}
+
+// GridData is a Grid before checking that it has a cell for each col and row.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct GridData<T> {
+    cells: Vec<T>,
+    cols: usize,
+    rows: usize,
+}
+
+#[cfg(feature = "serde")]
+impl<T> TryFrom<GridData<T>> for Grid<T> {
+    type Error = anyhow::Error;
+
+    fn try_from(data: GridData<T>) -> Result<Self> {
+        ensure!(
+            Some(data.cells.len()) == data.cols.checked_mul(data.rows),
+            "a grid of {}x{} should have {} cells: {}",
+            data.cols,
+            data.rows,
+            data.cols.saturating_mul(data.rows),
+            data.cells.len()
+        );
+        Ok(Self {
+            cells: data.cells,
+            cols: data.cols,
+            rows: data.rows,
+        })
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    /// new returns a Grid whose cells are all value.
+    pub fn new(cols: usize, rows: usize, value: T) -> Self {
+        Self {
+            cells: vec![value; cols * rows],
+            cols,
+            rows,
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    /// cols returns the number of columns.
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// rows returns the number of rows.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// get returns the value at col and row, or None if it is out of the grid.
+    pub fn get(&self, col: usize, row: usize) -> Option<&T> {
+        if col < self.cols && row < self.rows {
+            self.cells.get(col + row * self.cols)
+        } else {
+            None
+        }
+    }
+
+    /// set replaces the value at col and row. It returns an error if it is out of the grid.
+    pub fn set(&mut self, col: usize, row: usize, value: T) -> Result<()> {
+        ensure!(
+            col < self.cols,
+            "col: {} should be less than cols: {}",
+            col,
+            self.cols
+        );
+        ensure!(
+            row < self.rows,
+            "row: {} should be less than rows: {}",
+            row,
+            self.rows
+        );
+        self.cells[col + row * self.cols] = value;
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+use anyhow::Result;
+
+use crate::render::{draw_sprite, Position, Renderer, SpriteStore};
+
+/// NinePatch draws a resizable box, e.g. a dialogue window, from nine sprites on a SpriteStore.
+/// The corners keep their size, the edges stretch along the box, and the center fills the rest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NinePatch {
+    // The sprite indices from the top left to the bottom right, row by row.
+    indices: [usize; 9],
+}
+
+impl NinePatch {
+    /// new returns a NinePatch. It returns an error if any index is out of the store.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        top_left: usize,
+        top: usize,
+        top_right: usize,
+        left: usize,
+        center: usize,
+        right: usize,
+        bottom_left: usize,
+        bottom: usize,
+        bottom_right: usize,
+        store: &SpriteStore,
+    ) -> Result<Self> {
+        let indices = [
+            top_left,
+            top,
+            top_right,
+            left,
+            center,
+            right,
+            bottom_left,
+            bottom,
+            bottom_right,
+        ];
+        for index in indices {
+            store.sprite(index)?;
+        }
+        Ok(Self { indices })
+    }
+
+    /// draw fills the destination rectangle with the nine sprites of store.
+    /// If the rectangle is smaller than the corners, the edges and the center are not drawn.
+    pub fn draw(
+        &self,
+        renderer: &Renderer,
+        dest_position: Position,
+        dest_width: f64,
+        dest_height: f64,
+        store: &SpriteStore,
+    ) -> Result<()> {
+        let top_left = store.sprite(self.indices[0])?;
+        let bottom_right = store.sprite(self.indices[8])?;
+        let columns = [
+            (dest_position.dx(), top_left.width()),
+            (
+                dest_position.dx() + top_left.width(),
+                (dest_width - top_left.width() - bottom_right.width()).max(0.0),
+            ),
+            (
+                dest_position.dx() + dest_width - bottom_right.width(),
+                bottom_right.width(),
+            ),
+        ];
+        let rows = [
+            (dest_position.dy(), top_left.height()),
+            (
+                dest_position.dy() + top_left.height(),
+                (dest_height - top_left.height() - bottom_right.height()).max(0.0),
+            ),
+            (
+                dest_position.dy() + dest_height - bottom_right.height(),
+                bottom_right.height(),
+            ),
+        ];
+        for (i, index) in self.indices.iter().enumerate() {
+            let (dx, dw) = columns[i % 3];
+            let (dy, dh) = rows[i / 3];
+            if dw <= 0.0 || dh <= 0.0 {
+                continue;
+            }
+            draw_sprite(renderer, store.sprite(*index)?, dx, dy, dw, dh)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,328 @@
+use anyhow::{anyhow, ensure, Result};
+
+use crate::render::{draw_sprite, with_transform, Position, Renderer, Sprite, Transform};
+
+/// Node is an element of a SceneGraph: a local position, rotation and scale relative to the
+/// parent, and an optional sprite whose top-left corner is at the origin of the node.
+/// The node rotates and scales around its origin, so put the sprite of a turret in a child at
+/// (-w / 2, -h / 2) to rotate it around its center.
+#[derive(Debug)]
+pub struct Node {
+    position: Position,
+    rotation: f64,
+    scale: (f64, f64),
+    depth: i32,
+    sprite: Option<Sprite>,
+}
+
+impl Node {
+    /// new returns a Node at position relative to its parent, without a sprite.
+    pub fn new(position: Position) -> Self {
+        Self {
+            position,
+            rotation: 0.0,
+            scale: (1.0, 1.0),
+            depth: 0,
+            sprite: None,
+        }
+    }
+
+    /// with_sprite sets the sprite to draw at the node.
+    pub fn with_sprite(mut self, sprite: Sprite) -> Self {
+        self.sprite = Some(sprite);
+        self
+    }
+
+    /// with_rotation sets the clockwise rotation in radians.
+    pub fn with_rotation(mut self, angle_rad: f64) -> Self {
+        self.rotation = angle_rad;
+        self
+    }
+
+    /// with_scale sets the scale of x and y. It is (1.0, 1.0) by default.
+    pub fn with_scale(mut self, scale_x: f64, scale_y: f64) -> Self {
+        self.scale = (scale_x, scale_y);
+        self
+    }
+
+    /// with_depth sets the depth among the siblings. Deeper nodes are drawn later, on top, and
+    /// siblings of the same depth are drawn in the order they were attached. It is 0 by default.
+    pub fn with_depth(mut self, depth: i32) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// position returns the position relative to the parent.
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    /// set_position moves the node relative to the parent.
+    pub fn set_position(&mut self, position: Position) {
+        self.position = position;
+    }
+
+    /// rotation returns the clockwise rotation in radians.
+    pub fn rotation(&self) -> f64 {
+        self.rotation
+    }
+
+    /// set_rotation sets the clockwise rotation in radians, e.g. to aim a turret.
+    pub fn set_rotation(&mut self, angle_rad: f64) {
+        self.rotation = angle_rad;
+    }
+
+    /// scale returns the scale of x and y.
+    pub fn scale(&self) -> (f64, f64) {
+        self.scale
+    }
+
+    /// set_scale sets the scale of x and y.
+    pub fn set_scale(&mut self, scale_x: f64, scale_y: f64) {
+        self.scale = (scale_x, scale_y);
+    }
+
+    /// depth returns the depth among the siblings.
+    pub fn depth(&self) -> i32 {
+        self.depth
+    }
+
+    /// set_depth sets the depth among the siblings.
+    pub fn set_depth(&mut self, depth: i32) {
+        self.depth = depth;
+    }
+
+    /// sprite returns the sprite drawn at the node.
+    pub fn sprite(&self) -> Option<&Sprite> {
+        self.sprite.as_ref()
+    }
+
+    /// set_sprite replaces the sprite drawn at the node, e.g. None to hide it.
+    pub fn set_sprite(&mut self, sprite: Option<Sprite>) {
+        self.sprite = sprite;
+    }
+}
+
+/// NodeId identifies a Node in a SceneGraph. It is invalidated when the node is removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId {
+    index: usize,
+    generation: u32,
+}
+
+/// SceneGraph is a tree of Nodes, e.g. a tank hull with a rotating turret, or a character
+/// holding an item. Each node is drawn with the transforms of its ancestors.
+/// It is an optional layer on the draw calls, so mix it with other draws as you like.
+#[derive(Debug, Default)]
+pub struct SceneGraph {
+    slots: Vec<Slot>,
+    free: Vec<usize>,
+    roots: Vec<NodeId>,
+}
+
+#[derive(Debug)]
+struct Slot {
+    generation: u32,
+    entry: Option<Entry>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    node: Node,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+}
+
+impl SceneGraph {
+    /// new returns an empty SceneGraph.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// add adds node as the last child of parent, or as a root if parent is None.
+    /// It returns an error if parent is not in the graph.
+    pub fn add(&mut self, node: Node, parent: Option<NodeId>) -> Result<NodeId> {
+        if let Some(parent) = parent {
+            self.entry(parent)?;
+        }
+        let entry = Entry {
+            node,
+            parent,
+            children: vec![],
+        };
+        let id = match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                slot.entry = Some(entry);
+                NodeId {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    entry: Some(entry),
+                });
+                NodeId {
+                    index: self.slots.len() - 1,
+                    generation: 0,
+                }
+            }
+        };
+        self.siblings_mut(parent)?.push(id);
+        Ok(id)
+    }
+
+    /// remove removes the node with id and its descendants, and returns the node.
+    pub fn remove(&mut self, id: NodeId) -> Option<Node> {
+        let parent = self.entry(id).ok()?.parent;
+        self.siblings_mut(parent)
+            .ok()?
+            .retain(|&sibling| sibling != id);
+        let mut removed = vec![id];
+        let mut node = None;
+        while let Some(id) = removed.pop() {
+            let slot = &mut self.slots[id.index];
+            let entry = slot.entry.take()?;
+            slot.generation = slot.generation.wrapping_add(1);
+            self.free.push(id.index);
+            removed.extend(entry.children);
+            node.get_or_insert(entry.node);
+        }
+        node
+    }
+
+    /// set_parent moves the node with id and its descendants under parent, or to the roots if
+    /// parent is None, as the last child. The local transform is kept, so the node moves with the
+    /// new parent, e.g. an item picked up by a character. It returns an error if either is not
+    /// in the graph, or if parent is the node itself or one of its descendants.
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) -> Result<()> {
+        let old_parent = self.entry(id)?.parent;
+        let mut ancestor = parent;
+        while let Some(current) = ancestor {
+            ensure!(
+                current != id,
+                "a node cannot be attached to itself or its descendant"
+            );
+            ancestor = self.entry(current)?.parent;
+        }
+        self.siblings_mut(old_parent)?
+            .retain(|&sibling| sibling != id);
+        self.siblings_mut(parent)?.push(id);
+        self.entry_mut(id)?.parent = parent;
+        Ok(())
+    }
+
+    /// node returns the node with id, or None if it is not in the graph.
+    pub fn node(&self, id: NodeId) -> Option<&Node> {
+        self.entry(id).ok().map(|entry| &entry.node)
+    }
+
+    /// node_mut returns the node with id mutably, or None if it is not in the graph.
+    pub fn node_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+        self.entry_mut(id).ok().map(|entry| &mut entry.node)
+    }
+
+    /// parent returns the parent of the node with id, or None if it is a root or not in the graph.
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.entry(id).ok()?.parent
+    }
+
+    /// children returns the children of the node with id in the order they were attached.
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        self.entry(id)
+            .map(|entry| entry.children.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// roots returns the nodes without a parent in the order they were attached.
+    pub fn roots(&self) -> &[NodeId] {
+        &self.roots
+    }
+
+    /// len returns the number of the nodes.
+    pub fn len(&self) -> usize {
+        self.slots.len() - self.free.len()
+    }
+
+    /// is_empty returns true when the graph has no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// world_position returns the position of the origin of the node with id on the canvas,
+    /// with the transforms of its ancestors, e.g. for collision. None if it is not in the graph.
+    pub fn world_position(&self, id: NodeId) -> Option<Position> {
+        let mut path = vec![];
+        let mut current = Some(id);
+        while let Some(id) = current {
+            let entry = self.entry(id).ok()?;
+            path.push(&entry.node);
+            current = entry.parent;
+        }
+        // The origin is transformed by the node itself, then by the parent and so on.
+        let (mut x, mut y) = (0.0, 0.0);
+        for node in path {
+            let (sin, cos) = node.rotation.sin_cos();
+            let (sx, sy) = (x * node.scale.0, y * node.scale.1);
+            x = node.position.dx() + sx * cos - sy * sin;
+            y = node.position.dy() + sx * sin + sy * cos;
+        }
+        Some(Position::new(x, y))
+    }
+
+    /// draw draws the sprites of all the nodes, each parent before its children and siblings
+    /// by depth. A headless renderer records the sprites at the origin of their nodes, as
+    /// with_transform does not transform the recorded draws.
+    pub fn draw(&self, renderer: &Renderer) -> Result<()> {
+        self.draw_nodes(renderer, &self.roots)
+    }
+
+    fn draw_nodes(&self, renderer: &Renderer, ids: &[NodeId]) -> Result<()> {
+        let mut entries = ids
+            .iter()
+            .map(|&id| self.entry(id))
+            .collect::<Result<Vec<_>>>()?;
+        entries.sort_by_key(|entry| entry.node.depth);
+        for entry in entries {
+            let node = &entry.node;
+            let transform = Transform::new(
+                Position::new(node.position.dx(), node.position.dy()),
+                node.rotation,
+                node.scale.0,
+                node.scale.1,
+            );
+            with_transform(renderer, &transform, |renderer| {
+                if let Some(sprite) = &node.sprite {
+                    draw_sprite(renderer, sprite, 0.0, 0.0, sprite.width(), sprite.height())?;
+                }
+                self.draw_nodes(renderer, &entry.children)
+            })??;
+        }
+        Ok(())
+    }
+
+    fn siblings_mut(&mut self, parent: Option<NodeId>) -> Result<&mut Vec<NodeId>> {
+        match parent {
+            Some(parent) => Ok(&mut self.entry_mut(parent)?.children),
+            None => Ok(&mut self.roots),
+        }
+    }
+
+    fn entry(&self, id: NodeId) -> Result<&Entry> {
+        self.slots
+            .get(id.index)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.entry.as_ref())
+            .ok_or_else(|| anyhow!("the node is not in the scene graph: {:?}", id))
+    }
+
+    fn entry_mut(&mut self, id: NodeId) -> Result<&mut Entry> {
+        self.slots
+            .get_mut(id.index)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.entry.as_mut())
+            .ok_or_else(|| anyhow!("the node is not in the scene graph: {:?}", id))
+    }
+}
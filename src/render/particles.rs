@@ -0,0 +1,331 @@
+use std::ops::Range;
+
+use anyhow::{anyhow, Result};
+
+use crate::random::{self, Rng};
+use crate::render::color::Color;
+use crate::render::{draw_sprite, fill_rect, Position, Renderer, Sprite};
+
+/// ParticleLook is what every particle of a ParticleEmitter looks like.
+#[derive(Debug, Clone)]
+pub enum ParticleLook {
+    /// Sprite draws the sprite centered on the particle.
+    Sprite(Sprite),
+    /// Color fills a square of size pixels centered on the particle, e.g. for sparks and rain.
+    Color {
+        /// color is the color of the square. Its alpha is multiplied by the particle alpha.
+        color: Color,
+        /// size is the side of the square in pixels at scale 1.0.
+        size: f64,
+    },
+}
+
+/// Particle is a live particle of a ParticleEmitter.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Particle {
+    x: f64,
+    y: f64,
+    vx: f64,
+    vy: f64,
+    age_ms: f64,
+    lifetime_ms: f64,
+    alpha: f64,
+    scale: f64,
+}
+
+impl Particle {
+    /// position returns the center of the particle on the canvas.
+    pub fn position(&self) -> Position {
+        Position::new(self.x, self.y)
+    }
+
+    /// velocity returns the velocity in pixels per millisecond.
+    pub fn velocity(&self) -> (f64, f64) {
+        (self.vx, self.vy)
+    }
+
+    /// age_ms returns the milliseconds since the particle was spawned.
+    pub fn age_ms(&self) -> f64 {
+        self.age_ms
+    }
+
+    /// lifetime_ms returns the milliseconds the particle lives for.
+    pub fn lifetime_ms(&self) -> f64 {
+        self.lifetime_ms
+    }
+
+    /// alpha returns the opacity from 0.0 to 1.0 at the current age.
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    /// scale returns the scale at the current age.
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+}
+
+/// ParticleEmitter spawns, moves and draws particles, e.g. for explosions, sparks and rain.
+/// The particles live in a pool allocated up front, so a running emitter does not allocate,
+/// and no more than max_particles are alive at once.
+/// The particles are spawned at the position of the emitter and do not follow it when it moves.
+/// Speeds are in pixels per millisecond, and the same seed and the same updates spawn the same
+/// particles, e.g. in replays.
+#[derive(Debug, Clone)]
+pub struct ParticleEmitter {
+    look: ParticleLook,
+    x: f64,
+    y: f64,
+    rate: f64,
+    lifetime_ms: Range<f64>,
+    direction_rad: f64,
+    spread_rad: f64,
+    speed: Range<f64>,
+    gravity: (f64, f64),
+    alpha: (f64, f64),
+    scale: (f64, f64),
+    additive: bool,
+    rng: Rng,
+    // The live particles are the first live of the pool.
+    pool: Vec<Particle>,
+    live: usize,
+    // The fraction of a particle owed by rate, carried over to the next update.
+    pending: f64,
+}
+
+impl ParticleEmitter {
+    /// new returns an emitter at position which spawns nothing until rate or burst is set.
+    /// By default particles live for 1 second, fly upwards at 0.1 pixels per millisecond and
+    /// stay opaque at scale 1.0. The seed is taken from the global Rng, so seed_global makes
+    /// the emitter reproducible as well.
+    pub fn new(look: ParticleLook, position: Position, max_particles: usize) -> Self {
+        Self {
+            look,
+            x: position.dx(),
+            y: position.dy(),
+            rate: 0.0,
+            lifetime_ms: 1000.0..1000.0,
+            direction_rad: -std::f64::consts::FRAC_PI_2,
+            spread_rad: 0.0,
+            speed: 0.1..0.1,
+            gravity: (0.0, 0.0),
+            alpha: (1.0, 1.0),
+            scale: (1.0, 1.0),
+            additive: false,
+            rng: Rng::seeded(random::with_global(|rng| rng.next_u64())),
+            pool: vec![Particle::default(); max_particles],
+            live: 0,
+            pending: 0.0,
+        }
+    }
+
+    /// rate sets the particles spawned per second while updating, e.g. for a fountain.
+    pub fn rate(mut self, per_second: f64) -> Self {
+        self.rate = per_second.max(0.0);
+        self
+    }
+
+    /// lifetime sets the range of the milliseconds each particle lives for.
+    pub fn lifetime(mut self, lifetime_ms: Range<f64>) -> Self {
+        self.lifetime_ms = lifetime_ms;
+        self
+    }
+
+    /// velocity sets the initial velocity: a speed in the range, in pixels per millisecond, and
+    /// a direction in the cone of spread_rad around direction_rad. 0.0 is to the right and
+    /// angles go clockwise, so -PI / 2 is upwards. A spread of 2 * PI emits in every direction.
+    pub fn velocity(mut self, direction_rad: f64, spread_rad: f64, speed: Range<f64>) -> Self {
+        self.direction_rad = direction_rad;
+        self.spread_rad = spread_rad.max(0.0);
+        self.speed = speed;
+        self
+    }
+
+    /// gravity sets the acceleration in pixels per millisecond squared, e.g. (0.0, 0.0005).
+    pub fn gravity(mut self, x: f64, y: f64) -> Self {
+        self.gravity = (x, y);
+        self
+    }
+
+    /// alpha sets the opacity at the spawn and at the end of the lifetime, from 0.0 to 1.0.
+    /// It is interpolated linearly in between.
+    pub fn alpha(mut self, start: f64, end: f64) -> Self {
+        self.alpha = (start.clamp(0.0, 1.0), end.clamp(0.0, 1.0));
+        self
+    }
+
+    /// scale sets the scale at the spawn and at the end of the lifetime.
+    /// It is interpolated linearly in between.
+    pub fn scale(mut self, start: f64, end: f64) -> Self {
+        self.scale = (start.max(0.0), end.max(0.0));
+        self
+    }
+
+    /// additive sets whether the particles are drawn with additive blending, so overlapping
+    /// particles brighten each other, e.g. for fire. Headless renderers ignore it.
+    pub fn additive(mut self, additive: bool) -> Self {
+        self.additive = additive;
+        self
+    }
+
+    /// seed reseeds the emitter, e.g. with a seed saved in a replay.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::seeded(seed);
+        self
+    }
+
+    /// position returns the position where particles are spawned.
+    pub fn position(&self) -> Position {
+        Position::new(self.x, self.y)
+    }
+
+    /// set_position moves the emitter, e.g. to follow a rocket.
+    pub fn set_position(&mut self, position: Position) {
+        self.x = position.dx();
+        self.y = position.dy();
+    }
+
+    /// set_rate changes the particles spawned per second. 0.0 stops spawning, and the live
+    /// particles still fade out.
+    pub fn set_rate(&mut self, per_second: f64) {
+        self.rate = per_second.max(0.0);
+    }
+
+    /// burst spawns count particles at once, e.g. for an explosion. Particles beyond
+    /// max_particles are not spawned. It returns the number of the spawned particles.
+    pub fn burst(&mut self, count: usize) -> usize {
+        let count = count.min(self.pool.len() - self.live);
+        for _ in 0..count {
+            self.spawn();
+        }
+        count
+    }
+
+    /// update advances the particles by dt milliseconds, recycles the expired ones and spawns
+    /// new ones by the rate.
+    pub fn update(&mut self, dt: f64) {
+        let dt = dt.max(0.0);
+        let (gx, gy) = self.gravity;
+        let mut i = 0;
+        while i < self.live {
+            let particle = &mut self.pool[i];
+            particle.age_ms += dt;
+            if particle.age_ms >= particle.lifetime_ms {
+                // The last live particle takes the place of the expired one.
+                self.live -= 1;
+                self.pool.swap(i, self.live);
+                continue;
+            }
+            // Gravity is constant, so this is exact however long dt is.
+            particle.x += particle.vx * dt + 0.5 * gx * dt * dt;
+            particle.y += particle.vy * dt + 0.5 * gy * dt * dt;
+            particle.vx += gx * dt;
+            particle.vy += gy * dt;
+            let t = particle.age_ms / particle.lifetime_ms;
+            particle.alpha = lerp(self.alpha, t);
+            particle.scale = lerp(self.scale, t);
+            i += 1;
+        }
+
+        self.pending += self.rate * dt / 1000.0;
+        let count = self.pending as usize;
+        self.pending -= count as f64;
+        self.burst(count);
+    }
+
+    /// draw draws the live particles. Sprites are drawn with the particle alpha unless the
+    /// renderer is headless, and squares are filled with it.
+    pub fn draw(&self, renderer: &Renderer) -> Result<()> {
+        if self.live == 0 {
+            return Ok(());
+        }
+        if renderer.is_headless() {
+            return self.draw_particles(renderer, true);
+        }
+
+        let context = renderer.context();
+        context.save();
+        let result = if self.additive {
+            context
+                .set_global_composite_operation("lighter")
+                .map_err(|e| anyhow!("failed to set additive blending: {:?}", e))
+        } else {
+            Ok(())
+        }
+        .and_then(|_| self.draw_particles(renderer, false));
+        context.restore();
+        result
+    }
+
+    fn draw_particles(&self, renderer: &Renderer, headless: bool) -> Result<()> {
+        for particle in self.particles() {
+            match &self.look {
+                ParticleLook::Sprite(sprite) => {
+                    if !headless {
+                        renderer.context().set_global_alpha(particle.alpha);
+                    }
+                    let dw = sprite.width() * particle.scale;
+                    let dh = sprite.height() * particle.scale;
+                    draw_sprite(
+                        renderer,
+                        sprite,
+                        particle.x - dw / 2.0,
+                        particle.y - dh / 2.0,
+                        dw,
+                        dh,
+                    )?;
+                }
+                ParticleLook::Color { color, size } => {
+                    let side = size * particle.scale;
+                    let alpha = (color.a() as f64 * particle.alpha).round() as u8;
+                    fill_rect(
+                        renderer,
+                        particle.x - side / 2.0,
+                        particle.y - side / 2.0,
+                        side,
+                        side,
+                        Color::rgba(color.r(), color.g(), color.b(), alpha),
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// particles returns the live particles.
+    pub fn particles(&self) -> &[Particle] {
+        &self.pool[..self.live]
+    }
+
+    /// live_count returns the number of the live particles.
+    pub fn live_count(&self) -> usize {
+        self.live
+    }
+
+    /// max_particles returns the size of the pool.
+    pub fn max_particles(&self) -> usize {
+        self.pool.len()
+    }
+
+    // spawn initializes the first free particle of the pool. The pool must not be full.
+    fn spawn(&mut self) {
+        let lifetime_ms = self.rng.range_f64(self.lifetime_ms.clone()).max(0.0);
+        let angle = self.direction_rad + self.spread_rad * (self.rng.next_f64() - 0.5);
+        let speed = self.rng.range_f64(self.speed.clone());
+        self.pool[self.live] = Particle {
+            x: self.x,
+            y: self.y,
+            vx: speed * angle.cos(),
+            vy: speed * angle.sin(),
+            age_ms: 0.0,
+            lifetime_ms,
+            alpha: self.alpha.0,
+            scale: self.scale.0,
+        };
+        self.live += 1;
+    }
+}
+
+fn lerp((start, end): (f64, f64), t: f64) -> f64 {
+    start + (end - start) * t
+}
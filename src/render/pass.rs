@@ -0,0 +1,13 @@
+/// RenderPassId names a pass of App::render_pass. App::render_passes decides which passes are
+/// rendered in which order, e.g. the background, the world with a camera, and the UI on top.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RenderPassId {
+    /// Background is for what is behind the world, e.g. the sky and parallax layers.
+    Background,
+    /// World is for the game objects. It is the only pass by default.
+    World,
+    /// Ui is for what is in front of the world, e.g. the score and menus.
+    Ui,
+    /// Custom is for any other pass, e.g. `Custom(0)` for a lighting overlay.
+    Custom(u32),
+}
@@ -0,0 +1,36 @@
+use anyhow::Result;
+
+use crate::render::{draw_sprite, Position, Renderer, Sprite};
+
+/// draw_image_tiled repeats sprite from dest_position to fill dest_width x dest_height, e.g. for
+/// grass or water. The tiles on the right and bottom edges are cut to fit the rectangle, and the
+/// tiles out of the canvas are skipped.
+pub fn draw_image_tiled(
+    renderer: &Renderer,
+    sprite: &Sprite,
+    dest_position: Position,
+    dest_width: f64,
+    dest_height: f64,
+) -> Result<()> {
+    let (tile_width, tile_height) = (sprite.width(), sprite.height());
+    if tile_width <= 0.0 || tile_height <= 0.0 {
+        return Ok(());
+    }
+    let right = dest_position.dx() + dest_width;
+    let bottom = dest_position.dy() + dest_height;
+    let mut dy = dest_position.dy();
+    while dy < bottom && dy <= renderer.canvas_height() {
+        let height = tile_height.min(bottom - dy);
+        let mut dx = dest_position.dx();
+        while dx < right && dx <= renderer.canvas_width() {
+            let width = tile_width.min(right - dx);
+            if 0.0 <= dx + width && 0.0 <= dy + height {
+                let tile = Sprite::new(sprite.atlas(), sprite.sx(), sprite.sy(), width, height);
+                draw_sprite(renderer, &tile, dx, dy, width, height)?;
+            }
+            dx += tile_width;
+        }
+        dy += tile_height;
+    }
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use anyhow::{ensure, Result};
+
+use crate::render::color::Color;
+use crate::render::{Position, Renderer};
+use crate::test::DrawCommand;
+
+/// draw_polygon fills the polygon through points with fill_color and outlines it with
+/// stroke_color stroke_width pixels wide, e.g. for irregular hit boxes and HUD elements.
+/// Either color may be None to skip the fill or the outline. The last point is joined to the
+/// first one. It returns an error if there are fewer than three points.
+pub fn draw_polygon(
+    renderer: &Renderer,
+    points: &[Position],
+    fill_color: Option<Color>,
+    stroke_color: Option<Color>,
+    stroke_width: f64,
+) -> Result<()> {
+    ensure!(
+        points.len() >= 3,
+        "a polygon needs at least three points: {}",
+        points.len()
+    );
+    let recorded = renderer.record(|| DrawCommand::Polygon {
+        points: points
+            .iter()
+            .map(|point| (point.dx(), point.dy()))
+            .collect(),
+        fill_color,
+        stroke_color,
+    });
+    if recorded {
+        return Ok(());
+    }
+
+    let context = renderer.context();
+    context.save();
+    context.begin_path();
+    context.move_to(points[0].dx(), points[0].dy());
+    for point in &points[1..] {
+        context.line_to(point.dx(), point.dy());
+    }
+    context.close_path();
+    if let Some(color) = fill_color {
+        context.set_fill_style(&(&color).into());
+        context.fill();
+    }
+    if let Some(color) = stroke_color {
+        context.set_stroke_style(&(&color).into());
+        context.set_line_width(stroke_width);
+        context.stroke();
+    }
+    context.restore();
+    Ok(())
+}
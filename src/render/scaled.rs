@@ -0,0 +1,64 @@
+use anyhow::{ensure, Result};
+
+use crate::render::{draw_sprite, overlaps_canvas, Position, Renderer, Sprite};
+
+/// ScaledSprite is a sprite with the size to draw it in, returned by Sprite::scale_to.
+#[derive(Debug, Clone, Copy)]
+pub struct ScaledSprite<'a> {
+    sprite: &'a Sprite,
+    width: f64,
+    height: f64,
+}
+
+impl<'a> ScaledSprite<'a> {
+    pub(crate) fn new(sprite: &'a Sprite, width: f64, height: f64) -> Self {
+        Self {
+            sprite,
+            width,
+            height,
+        }
+    }
+
+    /// sprite returns the original sprite.
+    pub fn sprite(&self) -> &'a Sprite {
+        self.sprite
+    }
+
+    /// width returns the width to draw the sprite in.
+    pub fn width(&self) -> f64 {
+        self.width
+    }
+
+    /// height returns the height to draw the sprite in.
+    pub fn height(&self) -> f64 {
+        self.height
+    }
+}
+
+/// draw_scaled_sprite depicts a scaled sprite at a specified position on the canvas.
+/// It returns an error if the sprite does not overlap or touch the canvas.
+pub fn draw_scaled_sprite(
+    renderer: &Renderer,
+    scaled_sprite: ScaledSprite,
+    position: Position,
+) -> Result<()> {
+    ensure!(
+        overlaps_canvas(
+            renderer,
+            position.dx(),
+            position.dy(),
+            scaled_sprite.width(),
+            scaled_sprite.height()
+        ),
+        "the sprite to draw is out of canvas"
+    );
+
+    draw_sprite(
+        renderer,
+        scaled_sprite.sprite(),
+        position.dx(),
+        position.dy(),
+        scaled_sprite.width(),
+        scaled_sprite.height(),
+    )
+}
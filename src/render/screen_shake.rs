@@ -0,0 +1,69 @@
+use std::cell::RefCell;
+
+use crate::random::{self, Rng};
+use crate::render::Position;
+
+/// ScreenShake offsets draw positions by a random amount which decays over time,
+/// e.g. on explosions. Apply the same ScreenShake to every position drawn in a frame.
+/// The offsets come from an Rng seeded by the global Rng, so seed_global reproduces them.
+#[derive(Debug, Clone)]
+pub struct ScreenShake {
+    magnitude: f64,
+    duration_ms: f64,
+    elapsed_ms: f64,
+    // apply_offset takes &self, so that the positions are drawn from the render.
+    rng: RefCell<Rng>,
+}
+
+impl ScreenShake {
+    /// new returns an inactive ScreenShake.
+    pub fn new() -> Self {
+        Self {
+            magnitude: 0.0,
+            duration_ms: 0.0,
+            elapsed_ms: 0.0,
+            rng: RefCell::new(Rng::seeded(random::with_global(|rng| rng.next_u64()))),
+        }
+    }
+
+    /// start (re)starts shaking by up to magnitude pixels for duration_ms milliseconds.
+    pub fn start(&mut self, magnitude: f64, duration_ms: f64) {
+        self.magnitude = magnitude.max(0.0);
+        self.duration_ms = duration_ms.max(0.0);
+        self.elapsed_ms = 0.0;
+    }
+
+    /// update advances the shake by dt milliseconds.
+    pub fn update(&mut self, dt: f64) {
+        self.elapsed_ms = (self.elapsed_ms + dt.max(0.0)).min(self.duration_ms);
+    }
+
+    /// active returns true while shaking.
+    pub fn active(&self) -> bool {
+        self.elapsed_ms < self.duration_ms && self.magnitude > 0.0
+    }
+
+    /// apply_offset returns position moved by a random offset, which decreases linearly from
+    /// magnitude to zero over the duration. It returns the same position when inactive.
+    pub fn apply_offset(&self, position: Position) -> Position {
+        if !self.active() {
+            return position;
+        }
+        let intensity = self.magnitude * (1.0 - self.elapsed_ms / self.duration_ms);
+        Position::new(
+            position.dx() + self.next_signed() * intensity,
+            position.dy() + self.next_signed() * intensity,
+        )
+    }
+
+    // next_signed returns a pseudo-random number in [-1.0, 1.0).
+    fn next_signed(&self) -> f64 {
+        self.rng.borrow_mut().range_f64(-1.0..1.0)
+    }
+}
+
+impl Default for ScreenShake {
+    fn default() -> Self {
+        Self::new()
+    }
+}
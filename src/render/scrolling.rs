@@ -0,0 +1,61 @@
+use anyhow::Result;
+
+use crate::render::{draw_sprite, Renderer, Sprite};
+
+/// ScrollingBackground tiles a sprite horizontally and scrolls it infinitely.
+/// Multiple instances with different speeds produce parallax depth.
+#[derive(Debug)]
+pub struct ScrollingBackground {
+    sprite: Sprite,
+    scroll_speed: f64,
+    offset: f64,
+}
+
+impl ScrollingBackground {
+    /// new returns a ScrollingBackground. scroll_speed is in pixels per millisecond,
+    /// and a positive speed scrolls the sprite to the left.
+    pub fn new(sprite: Sprite, scroll_speed: f64) -> Self {
+        Self {
+            sprite,
+            scroll_speed,
+            offset: 0.0,
+        }
+    }
+
+    /// update scrolls the background by dt milliseconds.
+    pub fn update(&mut self, dt: f64) {
+        let width = self.sprite.width();
+        if width > 0.0 {
+            self.offset = (self.offset + self.scroll_speed * dt).rem_euclid(width);
+        }
+    }
+
+    /// draw tiles the sprite to fill the canvas width regardless of the scroll position.
+    pub fn draw(&self, renderer: &Renderer) -> Result<()> {
+        let width = self.sprite.width();
+        if width <= 0.0 {
+            return Ok(());
+        }
+        let mut dx = -self.offset;
+        while dx < renderer.canvas_width() {
+            draw_sprite(renderer, &self.sprite, dx, 0.0, width, self.sprite.height())?;
+            dx += width;
+        }
+        Ok(())
+    }
+
+    /// offset returns the current scroll position within the sprite width.
+    pub fn offset(&self) -> f64 {
+        self.offset
+    }
+
+    /// scroll_speed returns the speed in pixels per millisecond.
+    pub fn scroll_speed(&self) -> f64 {
+        self.scroll_speed
+    }
+
+    /// set_scroll_speed changes the speed in pixels per millisecond.
+    pub fn set_scroll_speed(&mut self, scroll_speed: f64) {
+        self.scroll_speed = scroll_speed;
+    }
+}
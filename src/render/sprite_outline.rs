@@ -0,0 +1,48 @@
+use anyhow::{ensure, Result};
+
+use crate::render::{draw_sprite, overlaps_canvas, Position, Renderer, Sprite};
+
+/// draw_sprite_outline depicts sprite at position with an outline of color, a CSS color, which is
+/// thickness pixels wide around its opaque pixels, e.g. to highlight the selected character.
+/// The outline is the shadow of the sprite drawn at four diagonal offsets without blur, so it
+/// follows the shape of the sprite without an outline texture.
+/// It returns an error if the outlined sprite does not overlap or touch the canvas.
+pub fn draw_sprite_outline(
+    renderer: &Renderer,
+    sprite: &Sprite,
+    position: Position,
+    color: &str,
+    thickness: u32,
+) -> Result<()> {
+    let (dx, dy) = (position.dx(), position.dy());
+    let (dw, dh) = (sprite.width(), sprite.height());
+    let thickness = thickness as f64;
+    ensure!(
+        overlaps_canvas(
+            renderer,
+            dx - thickness,
+            dy - thickness,
+            dw + thickness * 2.0,
+            dh + thickness * 2.0
+        ),
+        "the sprite to outline is out of canvas"
+    );
+    if renderer.is_headless() || thickness == 0.0 {
+        return draw_sprite(renderer, sprite, dx, dy, dw, dh);
+    }
+
+    let context = renderer.context();
+    context.save();
+    context.set_shadow_blur(0.0);
+    context.set_shadow_color(color);
+    let mut result = Ok(());
+    for (x, y) in [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)] {
+        context.set_shadow_offset_x(x * thickness);
+        context.set_shadow_offset_y(y * thickness);
+        result = result.and(draw_sprite(renderer, sprite, dx, dy, dw, dh));
+    }
+    context.restore();
+    // A later shadow may cover the sprite drawn with an earlier one, so the sprite is drawn again
+    // on top of the outline.
+    result.and(draw_sprite(renderer, sprite, dx, dy, dw, dh))
+}
@@ -0,0 +1,79 @@
+use anyhow::Result;
+
+use crate::render::color::Color;
+use crate::render::{draw_text, Position, Renderer};
+
+/// TextBox reveals its text character by character, e.g. for dialogues in RPGs.
+#[derive(Debug, Clone)]
+pub struct TextBox {
+    full_text: String,
+    chars_revealed: usize,
+    reveal_rate_per_ms: f64,
+    elapsed_ms: f64,
+}
+
+impl TextBox {
+    /// new returns a TextBox which reveals reveal_rate_per_ms characters per millisecond,
+    /// e.g. 0.03 for 30 characters per second. No character is revealed at first.
+    pub fn new(full_text: &str, reveal_rate_per_ms: f64) -> Self {
+        Self {
+            full_text: String::from(full_text),
+            chars_revealed: 0,
+            reveal_rate_per_ms,
+            elapsed_ms: 0.0,
+        }
+    }
+
+    /// update reveals characters for dt milliseconds.
+    pub fn update(&mut self, dt: f64) {
+        if self.is_complete() {
+            return;
+        }
+        self.elapsed_ms += dt.max(0.0);
+        let chars_revealed = (self.elapsed_ms * self.reveal_rate_per_ms).max(0.0) as usize;
+        self.chars_revealed = chars_revealed.min(self.char_count());
+    }
+
+    /// draw fills the revealed characters at position with a CSS font, e.g. "16px monospace".
+    pub fn draw(
+        &self,
+        renderer: &Renderer,
+        position: Position,
+        font: &str,
+        color: Color,
+    ) -> Result<()> {
+        draw_text(renderer, self.revealed_text(), position, font, color)
+    }
+
+    /// is_complete returns true when all the characters are revealed.
+    pub fn is_complete(&self) -> bool {
+        self.chars_revealed >= self.char_count()
+    }
+
+    /// skip_to_end reveals all the characters at once, e.g. when a player presses a key.
+    pub fn skip_to_end(&mut self) {
+        self.chars_revealed = self.char_count();
+    }
+
+    /// full_text returns the whole text including unrevealed characters.
+    pub fn full_text(&self) -> &str {
+        &self.full_text
+    }
+
+    /// revealed_text returns the first chars_revealed characters of the text.
+    pub fn revealed_text(&self) -> &str {
+        match self.full_text.char_indices().nth(self.chars_revealed) {
+            Some((end, _)) => &self.full_text[..end],
+            None => &self.full_text,
+        }
+    }
+
+    /// chars_revealed returns the number of revealed characters.
+    pub fn chars_revealed(&self) -> usize {
+        self.chars_revealed
+    }
+
+    fn char_count(&self) -> usize {
+        self.full_text.chars().count()
+    }
+}
@@ -0,0 +1,89 @@
+use anyhow::Result;
+
+use crate::render::{draw_image, Grid, Position, Renderer, SpriteStore};
+
+/// Tileset is a tile layer which pairs a SpriteStore with a grid of sprite indices.
+/// None in the grid is an empty tile.
+#[derive(Debug)]
+pub struct Tileset {
+    store: SpriteStore,
+    grid: Grid<Option<usize>>,
+    tile_w: f64,
+    tile_h: f64,
+}
+
+impl Tileset {
+    /// new returns an empty Tileset of cols x rows tiles. The tile size is that of store.
+    pub fn new(store: SpriteStore, cols: usize, rows: usize) -> Self {
+        let (tile_w, tile_h) = store.tile_dimensions();
+        Self {
+            store,
+            grid: Grid::new(cols, rows, None),
+            tile_w: tile_w as f64,
+            tile_h: tile_h as f64,
+        }
+    }
+
+    /// set_tile sets the sprite index at col and row. None clears the tile.
+    /// It returns an error if col and row are out of the grid or index is out of the store.
+    pub fn set_tile(&mut self, col: usize, row: usize, index: Option<usize>) -> Result<()> {
+        if let Some(index) = index {
+            self.store.sprite(index)?;
+        }
+        self.grid.set(col, row, index)
+    }
+
+    /// tile returns the sprite index at col and row.
+    pub fn tile(&self, col: usize, row: usize) -> Option<usize> {
+        self.grid.get(col, row).copied().flatten()
+    }
+
+    /// draw depicts the tiles visible on the canvas. camera_offset is the position of the layer
+    /// at the top-left corner of the canvas.
+    pub fn draw(&self, renderer: &Renderer, camera_offset: Position) -> Result<()> {
+        let (first_col, last_col) = visible_range(
+            camera_offset.dx(),
+            renderer.canvas_width(),
+            self.tile_w,
+            self.grid.cols(),
+        );
+        let (first_row, last_row) = visible_range(
+            camera_offset.dy(),
+            renderer.canvas_height(),
+            self.tile_h,
+            self.grid.rows(),
+        );
+        for row in first_row..last_row {
+            for col in first_col..last_col {
+                if let Some(index) = self.tile(col, row) {
+                    let position = Position::new(
+                        col as f64 * self.tile_w - camera_offset.dx(),
+                        row as f64 * self.tile_h - camera_offset.dy(),
+                    );
+                    draw_image(renderer, self.store.sprite(index)?, position)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// store returns the SpriteStore of the tiles.
+    pub fn store(&self) -> &SpriteStore {
+        &self.store
+    }
+
+    /// grid returns the sprite indices of the tiles.
+    pub fn grid(&self) -> &Grid<Option<usize>> {
+        &self.grid
+    }
+}
+
+// visible_range returns the range of the tiles which overlap the canvas along an axis.
+fn visible_range(offset: f64, canvas_size: f64, tile_size: f64, count: usize) -> (usize, usize) {
+    if tile_size <= 0.0 {
+        return (0, 0);
+    }
+    let first = (offset / tile_size).floor().max(0.0) as usize;
+    let last = ((offset + canvas_size) / tile_size).ceil().max(0.0) as usize;
+    (first.min(count), last.min(count))
+}
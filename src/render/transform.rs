@@ -0,0 +1,82 @@
+use anyhow::{anyhow, Result};
+
+use crate::render::{Position, Renderer};
+
+/// Transform moves, rotates and scales the draws between apply and unapply, in this order:
+/// the origin is moved to translate, then rotated by angle_rad clockwise and scaled around it.
+/// Draw a sprite at (-w / 2, -h / 2) to rotate it around its center at translate.
+/// A headless renderer records the draws without the transform.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transform {
+    translate: Position,
+    angle_rad: f64,
+    scale: (f64, f64),
+}
+
+impl Transform {
+    /// new returns a Transform which moves the origin to translate, rotates by angle_rad and
+    /// scales by scale_x and scale_y.
+    pub fn new(translate: Position, angle_rad: f64, scale_x: f64, scale_y: f64) -> Self {
+        Self {
+            translate,
+            angle_rad,
+            scale: (scale_x, scale_y),
+        }
+    }
+
+    /// apply saves the state of the context and sets the transform on top of the current one.
+    /// Call unapply after the draws.
+    pub fn apply(&self, renderer: &Renderer) -> Result<()> {
+        if renderer.is_headless() {
+            return Ok(());
+        }
+        let context = renderer.context();
+        context.save();
+        let (scale_x, scale_y) = self.scale;
+        let result = context
+            .translate(self.translate.dx(), self.translate.dy())
+            .and_then(|_| context.rotate(self.angle_rad))
+            .and_then(|_| context.scale(scale_x, scale_y))
+            .map_err(|e| anyhow!("failed to set the transform: {:?}", e));
+        if result.is_err() {
+            context.restore();
+        }
+        result
+    }
+
+    /// unapply restores the state of the context saved by apply.
+    pub fn unapply(&self, renderer: &Renderer) {
+        if !renderer.is_headless() {
+            renderer.context().restore();
+        }
+    }
+
+    /// translate returns the position which the origin is moved to.
+    pub fn translate(&self) -> &Position {
+        &self.translate
+    }
+
+    /// angle_rad returns the clockwise rotation in radians.
+    pub fn angle_rad(&self) -> f64 {
+        self.angle_rad
+    }
+
+    /// scale returns the scale of x and y.
+    pub fn scale(&self) -> (f64, f64) {
+        self.scale
+    }
+}
+
+/// with_transform calls f with the draws transformed by transform, and restores the context
+/// afterwards even if f returns an error.
+pub fn with_transform<R>(
+    renderer: &Renderer,
+    transform: &Transform,
+    f: impl FnOnce(&Renderer) -> R,
+) -> Result<R> {
+    transform.apply(renderer)?;
+    let result = f(renderer);
+    transform.unapply(renderer);
+    Ok(result)
+}
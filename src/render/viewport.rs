@@ -0,0 +1,80 @@
+use anyhow::Result;
+
+use crate::render::{draw_image, overlaps_area, Position, Renderer, Sprite};
+
+/// Viewport is a view into a larger world, whose top-left corner is at world_position.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Viewport {
+    world_position: Position,
+    canvas_width: f64,
+    canvas_height: f64,
+}
+
+impl Viewport {
+    /// new returns a Viewport of canvas_width x canvas_height at world_position.
+    pub fn new(world_position: Position, canvas_width: f64, canvas_height: f64) -> Self {
+        Self {
+            world_position,
+            canvas_width,
+            canvas_height,
+        }
+    }
+
+    /// world_position returns the position in the world at the top-left corner of the canvas.
+    pub fn world_position(&self) -> &Position {
+        &self.world_position
+    }
+
+    /// set_world_position scrolls the viewport to world_position.
+    pub fn set_world_position(&mut self, world_position: Position) {
+        self.world_position = world_position;
+    }
+
+    /// world_to_screen converts a position in the world to the position on the canvas.
+    pub fn world_to_screen(&self, world: Position) -> Position {
+        Position::new(
+            world.dx() - self.world_position.dx(),
+            world.dy() - self.world_position.dy(),
+        )
+    }
+
+    /// screen_to_world converts a position on the canvas to the position in the world.
+    pub fn screen_to_world(&self, screen: Position) -> Position {
+        Position::new(
+            screen.dx() + self.world_position.dx(),
+            screen.dy() + self.world_position.dy(),
+        )
+    }
+
+    /// is_visible returns true when the rectangle of w x h at world overlaps the viewport.
+    pub fn is_visible(&self, world: Position, w: f64, h: f64) -> bool {
+        self.is_visible_on_screen(&self.world_to_screen(world), w, h)
+    }
+
+    fn is_visible_on_screen(&self, screen: &Position, w: f64, h: f64) -> bool {
+        overlaps_area(
+            screen.dx(),
+            screen.dy(),
+            w,
+            h,
+            self.canvas_width,
+            self.canvas_height,
+        )
+    }
+}
+
+/// draw_image_in_viewport depicts sprite at world_pos in the world seen through viewport.
+/// Sprites out of the viewport are skipped.
+pub fn draw_image_in_viewport(
+    renderer: &Renderer,
+    viewport: &Viewport,
+    sprite: &Sprite,
+    world_pos: Position,
+) -> Result<()> {
+    let screen = viewport.world_to_screen(world_pos);
+    if !viewport.is_visible_on_screen(&screen, sprite.width(), sprite.height()) {
+        return Ok(());
+    }
+    draw_image(renderer, sprite, screen)
+}
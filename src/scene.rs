@@ -0,0 +1,151 @@
+use std::fmt;
+
+use anyhow::Result;
+
+use crate::app::App;
+use crate::render::Renderer;
+use crate::update::{KeyEvent, MouseEvent};
+
+/// Scene trait should be implemented by each screen of the game, e.g. title, gameplay and pause.
+pub trait Scene {
+    /// update is responsible for updating the scene. It returns a Transition to another scene.
+    fn update(&mut self, elapsed_time: f64, key_event: &KeyEvent) -> Result<Transition>;
+    /// on_mouse is called before update with the mouse, while the scene is the top.
+    fn on_mouse(&mut self, _mouse_event: &MouseEvent) {}
+    /// render is responsible for rendering the scene on the canvas.
+    fn render(&self, renderer: &Renderer) -> Result<()>;
+    /// enter is called when the scene is put on the SceneStack by SceneStack::new, Push or
+    /// Switch. It is not called again when the scene becomes the top by a Pop of the scene above.
+    fn enter(&mut self) {}
+    /// exit is called when the scene is removed from the SceneStack.
+    fn exit(&mut self) {}
+}
+
+/// Transition tells the SceneStack what to do after Scene::update.
+pub enum Transition {
+    /// None keeps the current scene.
+    None,
+    /// Push puts a scene on the current scene.
+    /// When draw_previous is true, the scene underneath is rendered before the pushed scene.
+    Push {
+        /// scene is the scene to push.
+        scene: Box<dyn Scene>,
+        /// draw_previous enables a translucent scene like a pause screen.
+        draw_previous: bool,
+    },
+    /// Pop removes the current scene and returns to the scene underneath.
+    Pop,
+    /// Switch replaces the current scene with another scene.
+    Switch(Box<dyn Scene>),
+    /// Quit removes all the scenes and stops the game loop.
+    Quit,
+}
+
+impl fmt::Debug for Transition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "None"),
+            Self::Push { draw_previous, .. } => f
+                .debug_struct("Push")
+                .field("draw_previous", draw_previous)
+                .finish_non_exhaustive(),
+            Self::Pop => write!(f, "Pop"),
+            Self::Switch(_) => write!(f, "Switch(..)"),
+            Self::Quit => write!(f, "Quit"),
+        }
+    }
+}
+
+/// SceneStack runs the scene on the top of the stack. It implements App, so you can pass it to run.
+pub struct SceneStack {
+    scenes: Vec<(Box<dyn Scene>, bool)>,
+}
+
+impl SceneStack {
+    /// new returns a SceneStack starting with the initial scene.
+    pub fn new(mut initial: Box<dyn Scene>) -> Self {
+        initial.enter();
+        Self {
+            scenes: vec![(initial, false)],
+        }
+    }
+
+    /// len returns the number of the scenes on the stack.
+    pub fn len(&self) -> usize {
+        self.scenes.len()
+    }
+
+    /// is_empty returns true when all the scenes have been removed.
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    fn apply(&mut self, transition: Transition) {
+        match transition {
+            Transition::None => {}
+            Transition::Push {
+                mut scene,
+                draw_previous,
+            } => {
+                scene.enter();
+                self.scenes.push((scene, draw_previous));
+            }
+            Transition::Pop => {
+                if let Some((mut scene, _)) = self.scenes.pop() {
+                    scene.exit();
+                }
+            }
+            Transition::Switch(mut scene) => {
+                if let Some((mut previous, _)) = self.scenes.pop() {
+                    previous.exit();
+                }
+                scene.enter();
+                self.scenes.push((scene, false));
+            }
+            Transition::Quit => {
+                while let Some((mut scene, _)) = self.scenes.pop() {
+                    scene.exit();
+                }
+            }
+        }
+    }
+}
+
+impl App for SceneStack {
+    fn on_mouse(&mut self, mouse_event: &MouseEvent) {
+        if let Some((scene, _)) = self.scenes.last_mut() {
+            scene.on_mouse(mouse_event);
+        }
+    }
+
+    fn update(&mut self, elapsed_time: f64, key_event: &KeyEvent) -> Result<()> {
+        if let Some((scene, _)) = self.scenes.last_mut() {
+            let transition = scene.update(elapsed_time, key_event)?;
+            self.apply(transition);
+        }
+        Ok(())
+    }
+
+    fn render(&self, renderer: &Renderer) -> Result<()> {
+        let mut bottom = self.scenes.len().saturating_sub(1);
+        while bottom > 0 && self.scenes[bottom].1 {
+            bottom -= 1;
+        }
+        for (scene, _) in self.scenes.iter().skip(bottom) {
+            scene.render(renderer)?;
+        }
+        Ok(())
+    }
+
+    fn should_stop(&self) -> bool {
+        self.is_empty()
+    }
+}
+
+impl fmt::Debug for SceneStack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SceneStack")
+            .field("len", &self.scenes.len())
+            .finish()
+    }
+}
@@ -0,0 +1,173 @@
+use std::cell::Cell;
+use std::fmt;
+use std::rc::Rc;
+
+use anyhow::{ensure, Result};
+
+/// Scheduler runs callbacks after a delay or at an interval, advanced by the frame delta.
+/// Callbacks get mutable access to the context passed to update, e.g. your App.
+pub struct Scheduler<C> {
+    tasks: Vec<Task<C>>,
+    now: f64,
+    next_order: u64,
+}
+
+/// Step is a delay in milliseconds and an action, which Scheduler::sequence runs.
+pub type Step<C> = (f64, fn(&mut C));
+
+struct Task<C> {
+    due: f64,
+    interval: Option<f64>,
+    // Tasks due at the same time run in the order they were scheduled.
+    order: u64,
+    handle: TaskHandle,
+    action: Box<dyn FnMut(&mut C)>,
+}
+
+impl<C: 'static> Scheduler<C> {
+    /// new returns a Scheduler without tasks.
+    pub fn new() -> Self {
+        Self {
+            tasks: vec![],
+            now: 0.0,
+            next_order: 0,
+        }
+    }
+
+    /// after runs action once after ms milliseconds.
+    pub fn after(&mut self, ms: f64, action: impl FnOnce(&mut C) + 'static) -> TaskHandle {
+        let handle = TaskHandle::new();
+        let mut action = Some(action);
+        self.push(ms, None, handle.clone(), move |ctx| {
+            if let Some(action) = action.take() {
+                action(ctx);
+            }
+        });
+        handle
+    }
+
+    /// every runs action every ms milliseconds, starting ms milliseconds later.
+    /// When a delta spans multiple intervals, action runs once for each of them.
+    /// It returns an error if ms is not positive and finite.
+    pub fn every(&mut self, ms: f64, action: impl FnMut(&mut C) + 'static) -> Result<TaskHandle> {
+        ensure!(
+            ms.is_finite() && ms > 0.0,
+            "ms: {} should be positive and finite",
+            ms
+        );
+        let handle = TaskHandle::new();
+        self.push(ms, Some(ms), handle.clone(), action);
+        Ok(handle)
+    }
+
+    /// sequence runs the actions one by one, each ms milliseconds after the previous one,
+    /// e.g. for a scripted cutscene. The handle cancels all the remaining actions.
+    pub fn sequence(&mut self, steps: &[Step<C>]) -> TaskHandle {
+        let handle = TaskHandle::new();
+        let mut delay = 0.0;
+        for (ms, action) in steps {
+            delay += ms.max(0.0);
+            self.push(delay, None, handle.clone(), *action);
+        }
+        handle
+    }
+
+    /// update advances the scheduler by dt milliseconds and runs the due actions in time order.
+    pub fn update(&mut self, dt: f64, ctx: &mut C) {
+        let target = self.now + dt.max(0.0);
+        loop {
+            self.tasks.retain(|task| !task.handle.is_cancelled());
+            let next = self
+                .tasks
+                .iter()
+                .enumerate()
+                .filter(|(_, task)| task.due <= target)
+                .min_by(|(_, a), (_, b)| a.due.total_cmp(&b.due).then(a.order.cmp(&b.order)))
+                .map(|(i, _)| i);
+            let i = match next {
+                Some(i) => i,
+                None => break,
+            };
+            let task = &mut self.tasks[i];
+            self.now = task.due;
+            (task.action)(ctx);
+            match task.interval {
+                Some(interval) => task.due += interval,
+                None => {
+                    self.tasks.remove(i);
+                }
+            }
+        }
+        self.now = target;
+    }
+
+    /// len returns the number of pending actions.
+    pub fn len(&self) -> usize {
+        self.tasks
+            .iter()
+            .filter(|task| !task.handle.is_cancelled())
+            .count()
+    }
+
+    /// is_empty returns true when no action is pending.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn push(
+        &mut self,
+        delay: f64,
+        interval: Option<f64>,
+        handle: TaskHandle,
+        action: impl FnMut(&mut C) + 'static,
+    ) {
+        self.tasks.push(Task {
+            due: self.now + delay.max(0.0),
+            interval,
+            order: self.next_order,
+            handle,
+            action: Box::new(action),
+        });
+        self.next_order += 1;
+    }
+}
+
+impl<C: 'static> Default for Scheduler<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: 'static> fmt::Debug for Scheduler<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scheduler")
+            .field("len", &self.len())
+            .field("now", &self.now)
+            .finish()
+    }
+}
+
+/// TaskHandle cancels the actions scheduled by a Scheduler, or a Tween run by Tweens.
+/// It is cheap to clone, so you can keep it in the context and cancel actions inside callbacks.
+#[derive(Debug, Clone)]
+pub struct TaskHandle {
+    cancelled: Rc<Cell<bool>>,
+}
+
+impl TaskHandle {
+    pub(crate) fn new() -> Self {
+        Self {
+            cancelled: Rc::new(Cell::new(false)),
+        }
+    }
+
+    /// cancel stops the pending actions. Actions cancelled during update do not run.
+    pub fn cancel(&self) {
+        self.cancelled.set(true);
+    }
+
+    /// is_cancelled returns true when cancel was called.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.get()
+    }
+}
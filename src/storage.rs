@@ -0,0 +1,90 @@
+use anyhow::{anyhow, Context, Result};
+
+mod high_scores;
+pub use high_scores::{HighScore, HighScores};
+
+mod indexed_db;
+pub use indexed_db::Store;
+
+/// Storage saves data in localStorage under keys prefixed by the name of the game, so that
+/// games on the same origin do not overwrite each other.
+/// Errors, e.g. in private browsing or when the quota is exceeded, are returned as Results.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Storage {
+    prefix: String,
+}
+
+impl Storage {
+    /// new returns a Storage for the game with game_name, e.g. "my-game".
+    pub fn new(game_name: &str) -> Self {
+        Self {
+            prefix: format!("{}:", game_name),
+        }
+    }
+
+    /// save stores value as JSON at key, replacing the previous value.
+    #[cfg(feature = "serde")]
+    pub fn save<T: serde::Serialize>(&self, key: &str, value: &T) -> Result<()> {
+        let json = serde_json::to_string(value)
+            .with_context(|| format!("failed to encode the value of {}", key))?;
+        self.set_item(key, &json)
+    }
+
+    /// load returns the value stored at key, or None if nothing is stored.
+    /// It returns an error if the stored JSON does not match T.
+    #[cfg(feature = "serde")]
+    pub fn load<T: serde::de::DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        match self.get_item(key)? {
+            Some(json) => serde_json::from_str(&json)
+                .map(Some)
+                .with_context(|| format!("failed to decode the value of {}", key)),
+            None => Ok(None),
+        }
+    }
+
+    /// set_item stores text at key as it is.
+    pub fn set_item(&self, key: &str, text: &str) -> Result<()> {
+        local_storage()?
+            .set_item(&self.full_key(key), text)
+            .map_err(|e| {
+                anyhow!(
+                    "failed to save {}; the storage quota may be exceeded: {:?}",
+                    key,
+                    e
+                )
+            })
+    }
+
+    /// get_item returns the text stored at key, or None if nothing is stored.
+    pub fn get_item(&self, key: &str) -> Result<Option<String>> {
+        local_storage()?
+            .get_item(&self.full_key(key))
+            .map_err(|e| anyhow!("failed to load {}: {:?}", key, e))
+    }
+
+    /// remove deletes the value at key. It does nothing if nothing is stored.
+    pub fn remove(&self, key: &str) -> Result<()> {
+        local_storage()?
+            .remove_item(&self.full_key(key))
+            .map_err(|e| anyhow!("failed to remove {}: {:?}", key, e))
+    }
+
+    /// exists returns true when a value is stored at key.
+    pub fn exists(&self, key: &str) -> Result<bool> {
+        Ok(self.get_item(key)?.is_some())
+    }
+
+    fn full_key(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+}
+
+// local_storage returns localStorage. It is unavailable e.g. in private browsing of some browsers
+// or when cookies are blocked.
+fn local_storage() -> Result<web_sys::Storage> {
+    web_sys::window()
+        .context("localStorage is unavailable: no global window exists")?
+        .local_storage()
+        .map_err(|e| anyhow!("localStorage is unavailable: {:?}", e))?
+        .context("localStorage is unavailable in this browser")
+}
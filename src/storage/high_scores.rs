@@ -0,0 +1,131 @@
+#[cfg(feature = "serde")]
+use anyhow::Result;
+
+#[cfg(feature = "serde")]
+use crate::storage::Storage;
+
+/// HighScore is an entry of HighScores.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HighScore<S> {
+    name: String,
+    score: S,
+}
+
+impl<S> HighScore<S> {
+    /// name returns the name submitted with the score.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// score returns the score.
+    pub fn score(&self) -> &S {
+        &self.score
+    }
+}
+
+/// HighScores keeps the top scores sorted from the highest, e.g. the ten entries of an arcade
+/// game. Of equal scores, the earlier submitted one ranks higher.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HighScores<S> {
+    capacity: usize,
+    entries: Vec<HighScore<S>>,
+}
+
+// SavedHighScores is the format of HighScores in Storage. Bump VERSION when it changes, so that
+// older saves are discarded instead of misread.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SavedHighScores<E> {
+    version: u32,
+    entries: E,
+}
+
+impl<S: PartialOrd> HighScores<S> {
+    #[cfg(feature = "serde")]
+    const VERSION: u32 = 1;
+
+    /// new returns empty HighScores which keep up to capacity entries.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: vec![],
+        }
+    }
+
+    /// submit adds score by name and returns its rank from 1, or None if the score does not make
+    /// it into the table. The lowest entry is dropped when the table is full.
+    pub fn submit(&mut self, name: &str, score: S) -> Option<usize> {
+        // Inserting after the equal scores ranks the earlier submissions higher.
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| entry.score < score)
+            .unwrap_or(self.entries.len());
+        if index >= self.capacity {
+            return None;
+        }
+        self.entries.insert(
+            index,
+            HighScore {
+                name: String::from(name),
+                score,
+            },
+        );
+        self.entries.truncate(self.capacity);
+        Some(index + 1)
+    }
+
+    /// qualifies returns true when score would make it into the table, e.g. to ask for a name.
+    pub fn qualifies(&self, score: &S) -> bool {
+        self.entries.len() < self.capacity || self.entries.iter().any(|entry| entry.score < *score)
+    }
+
+    /// entries returns the entries from the highest score.
+    pub fn entries(&self) -> &[HighScore<S>] {
+        &self.entries
+    }
+
+    /// capacity returns the maximum number of the entries.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// clear removes all the entries.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<S> HighScores<S>
+where
+    S: PartialOrd + serde::Serialize + serde::de::DeserializeOwned,
+{
+    /// load returns the HighScores saved at key in storage.
+    /// Nothing saved, a corrupted save, an older format or an unavailable storage results in
+    /// empty HighScores instead of an error.
+    pub fn load(storage: &Storage, key: &str, capacity: usize) -> Self {
+        let mut high_scores = Self::new(capacity);
+        if let Ok(Some(saved)) = storage.load::<SavedHighScores<Vec<HighScore<S>>>>(key) {
+            if saved.version == Self::VERSION {
+                // Submitting again keeps the table sorted even if the save was edited by hand.
+                for entry in saved.entries {
+                    high_scores.submit(&entry.name, entry.score);
+                }
+            }
+        }
+        high_scores
+    }
+
+    /// save stores the HighScores at key in storage.
+    pub fn save(&self, storage: &Storage, key: &str) -> Result<()> {
+        storage.save(
+            key,
+            &SavedHighScores {
+                version: Self::VERSION,
+                entries: self.entries.as_slice(),
+            },
+        )
+    }
+}
@@ -0,0 +1,154 @@
+use anyhow::{anyhow, Context, Result};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+
+/// Store saves bytes in IndexedDB, e.g. maps of a level editor and recorded replays, which are
+/// too large for localStorage. The database and its object store are created on the first open.
+/// Writes to the same key are last-writer-wins: the put called later is kept, even if the puts
+/// are not awaited in order.
+#[derive(Debug, Clone)]
+pub struct Store {
+    db: web_sys::IdbDatabase,
+}
+
+impl Store {
+    // The object store which keeps all the bytes. Bump VERSION when the layout changes,
+    // and migrate in upgrade.
+    const STORE_NAME: &'static str = "bytes";
+    const VERSION: u32 = 1;
+
+    /// open opens the database with db_name, e.g. the name of the game, creating it if needed.
+    pub async fn open(db_name: &str) -> Result<Self> {
+        let factory = web_sys::window()
+            .context("IndexedDB is unavailable: no global window exists")?
+            .indexed_db()
+            .map_err(|e| anyhow!("IndexedDB is unavailable: {:?}", e))?
+            .context("IndexedDB is unavailable in this browser")?;
+        let request = factory
+            .open_with_u32(db_name, Self::VERSION)
+            .map_err(|e| anyhow!("failed to open {}: {:?}", db_name, e))?;
+        let upgrade_request = request.clone();
+        let on_upgrade = Closure::wrap(Box::new(move || {
+            if let Err(e) = upgrade(&upgrade_request) {
+                web_sys::console::error_1(&JsValue::from_str(&format!("{:#}", e)));
+            }
+        }) as Box<dyn FnMut()>);
+        request.set_onupgradeneeded(Some(on_upgrade.as_ref().unchecked_ref()));
+        let result = await_request(&request).await;
+        request.set_onupgradeneeded(None);
+        let db = result
+            .map_err(|e| anyhow!("failed to open {}: {:?}", db_name, e))?
+            .dyn_into::<web_sys::IdbDatabase>()
+            .map_err(|_| anyhow!("failed to open {}: the result is not a database", db_name))?;
+        Ok(Self { db })
+    }
+
+    /// put_bytes stores bytes at key, replacing the previous bytes.
+    pub async fn put_bytes(&self, key: &str, bytes: &[u8]) -> Result<()> {
+        let value = js_sys::Uint8Array::from(bytes);
+        let request = self
+            .object_store(web_sys::IdbTransactionMode::Readwrite)?
+            .put_with_key(&value, &JsValue::from_str(key))
+            .map_err(|e| anyhow!("failed to put {}: {:?}", key, e))?;
+        await_request(&request)
+            .await
+            .map_err(|e| anyhow!("failed to put {}: {:?}", key, e))?;
+        Ok(())
+    }
+
+    /// get_bytes returns the bytes stored at key, or None if nothing is stored.
+    pub async fn get_bytes(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let request = self
+            .object_store(web_sys::IdbTransactionMode::Readonly)?
+            .get(&JsValue::from_str(key))
+            .map_err(|e| anyhow!("failed to get {}: {:?}", key, e))?;
+        let value = await_request(&request)
+            .await
+            .map_err(|e| anyhow!("failed to get {}: {:?}", key, e))?;
+        if value.is_undefined() {
+            return Ok(None);
+        }
+        let bytes = value
+            .dyn_into::<js_sys::Uint8Array>()
+            .map_err(|_| anyhow!("failed to get {}: the value is not bytes", key))?;
+        Ok(Some(bytes.to_vec()))
+    }
+
+    /// delete removes the bytes at key. It does nothing if nothing is stored.
+    pub async fn delete(&self, key: &str) -> Result<()> {
+        let request = self
+            .object_store(web_sys::IdbTransactionMode::Readwrite)?
+            .delete(&JsValue::from_str(key))
+            .map_err(|e| anyhow!("failed to delete {}: {:?}", key, e))?;
+        await_request(&request)
+            .await
+            .map_err(|e| anyhow!("failed to delete {}: {:?}", key, e))?;
+        Ok(())
+    }
+
+    /// list_keys returns all the keys in the store in ascending order.
+    pub async fn list_keys(&self) -> Result<Vec<String>> {
+        let request = self
+            .object_store(web_sys::IdbTransactionMode::Readonly)?
+            .get_all_keys()
+            .map_err(|e| anyhow!("failed to list the keys: {:?}", e))?;
+        let keys = await_request(&request)
+            .await
+            .map_err(|e| anyhow!("failed to list the keys: {:?}", e))?;
+        Ok(js_sys::Array::from(&keys)
+            .iter()
+            .filter_map(|key| key.as_string())
+            .collect())
+    }
+
+    fn object_store(&self, mode: web_sys::IdbTransactionMode) -> Result<web_sys::IdbObjectStore> {
+        self.db
+            .transaction_with_str_and_mode(Self::STORE_NAME, mode)
+            .and_then(|transaction| transaction.object_store(Self::STORE_NAME))
+            .map_err(|e| anyhow!("failed to start a transaction: {:?}", e))
+    }
+}
+
+// upgrade creates the object store when the database is created or its version is bumped.
+fn upgrade(request: &web_sys::IdbOpenDbRequest) -> Result<()> {
+    let db = request
+        .result()
+        .map_err(|e| anyhow!("failed to upgrade the database: {:?}", e))?
+        .dyn_into::<web_sys::IdbDatabase>()
+        .map_err(|_| anyhow!("failed to upgrade the database: the result is not a database"))?;
+    if !db.object_store_names().contains(Store::STORE_NAME) {
+        db.create_object_store(Store::STORE_NAME)
+            .map_err(|e| anyhow!("failed to create the object store: {:?}", e))?;
+    }
+    Ok(())
+}
+
+// await_request returns the result of request, or its error.
+async fn await_request(request: &web_sys::IdbRequest) -> Result<JsValue, JsValue> {
+    let mut handlers = None;
+    let promise = js_sys::Promise::new(&mut |resolve, reject| {
+        let success_request = request.clone();
+        let on_success = Closure::once(move || {
+            let result = success_request.result().unwrap_or(JsValue::UNDEFINED);
+            let _ = resolve.call1(&JsValue::UNDEFINED, &result);
+        });
+        let error_request = request.clone();
+        let on_error = Closure::once(move || {
+            let error = match error_request.error() {
+                Ok(Some(error)) => error.into(),
+                _ => JsValue::from_str("unknown error"),
+            };
+            let _ = reject.call1(&JsValue::UNDEFINED, &error);
+        });
+        request.set_onsuccess(Some(on_success.as_ref().unchecked_ref()));
+        request.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+        handlers = Some((on_success, on_error));
+    });
+    let result = JsFuture::from(promise).await;
+    // The handlers are kept alive until the request is settled.
+    request.set_onsuccess(None);
+    request.set_onerror(None);
+    drop(handlers);
+    result
+}
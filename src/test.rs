@@ -0,0 +1,304 @@
+use std::rc::Rc;
+
+use anyhow::Result;
+use wasm_bindgen::{JsCast, JsValue};
+
+use crate::app::{render_passes, App};
+use crate::overlay::Overlay;
+use crate::render::color::Color;
+use crate::render::{Renderer, Sprite};
+use crate::update::KeyEvent;
+
+/// DrawCommand is a draw recorded by a headless renderer.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum DrawCommand {
+    /// Clear clears the whole canvas.
+    Clear,
+    /// Image draws the source rectangle of an atlas in the destination rectangle.
+    Image {
+        /// sx is a source x on the atlas.
+        sx: f64,
+        /// sy is a source y on the atlas.
+        sy: f64,
+        /// sw is a source width on the atlas.
+        sw: f64,
+        /// sh is a source height on the atlas.
+        sh: f64,
+        /// dx is a destination x on the canvas.
+        dx: f64,
+        /// dy is a destination y on the canvas.
+        dy: f64,
+        /// dw is a destination width on the canvas.
+        dw: f64,
+        /// dh is a destination height on the canvas.
+        dh: f64,
+    },
+    /// Text fills the text at the destination position.
+    Text {
+        /// text is the text to draw.
+        text: String,
+        /// dx is a destination x on the canvas.
+        dx: f64,
+        /// dy is a destination y of the baseline on the canvas.
+        dy: f64,
+        /// font is a CSS font, e.g. "16px monospace".
+        font: String,
+        /// color is the fill color.
+        color: Color,
+    },
+    /// FillRect fills the destination rectangle.
+    FillRect {
+        /// dx is a destination x on the canvas.
+        dx: f64,
+        /// dy is a destination y on the canvas.
+        dy: f64,
+        /// dw is a destination width on the canvas.
+        dw: f64,
+        /// dh is a destination height on the canvas.
+        dh: f64,
+        /// color is the fill color.
+        color: Color,
+    },
+    /// StrokeRect outlines the destination rectangle.
+    StrokeRect {
+        /// dx is a destination x on the canvas.
+        dx: f64,
+        /// dy is a destination y on the canvas.
+        dy: f64,
+        /// dw is a destination width on the canvas.
+        dw: f64,
+        /// dh is a destination height on the canvas.
+        dh: f64,
+        /// color is a CSS color of the outline.
+        color: String,
+    },
+    /// Line strokes a line from (x1, y1) to (x2, y2).
+    Line {
+        /// x1 is the x of the start on the canvas.
+        x1: f64,
+        /// y1 is the y of the start on the canvas.
+        y1: f64,
+        /// x2 is the x of the end on the canvas.
+        x2: f64,
+        /// y2 is the y of the end on the canvas.
+        y2: f64,
+        /// color is a CSS color of the line.
+        color: String,
+    },
+    /// DashedLine strokes a dashed line from (x1, y1) to (x2, y2).
+    DashedLine {
+        /// x1 is the x of the start on the canvas.
+        x1: f64,
+        /// y1 is the y of the start on the canvas.
+        y1: f64,
+        /// x2 is the x of the end on the canvas.
+        x2: f64,
+        /// y2 is the y of the end on the canvas.
+        y2: f64,
+        /// dash is the length of each dash.
+        dash: f64,
+        /// gap is the length of each gap between the dashes.
+        gap: f64,
+        /// color is the color of the line.
+        color: Color,
+        /// width is the width of the line.
+        width: f64,
+    },
+    /// Arc strokes an arc, or fills a pie slice, of radius around (dx, dy).
+    Arc {
+        /// dx is the x of the center on the canvas.
+        dx: f64,
+        /// dy is the y of the center on the canvas.
+        dy: f64,
+        /// radius is the radius of the arc.
+        radius: f64,
+        /// start_rad is the start angle clockwise from the right of the center.
+        start_rad: f64,
+        /// end_rad is the end angle clockwise from the right of the center.
+        end_rad: f64,
+        /// color is the color of the arc or the slice.
+        color: Color,
+        /// filled is true for a pie slice and false for an arc.
+        filled: bool,
+    },
+    /// Polygon fills and outlines the polygon through the points.
+    Polygon {
+        /// points are the (x, y) of the vertices on the canvas.
+        points: Vec<(f64, f64)>,
+        /// fill_color is the fill color, or None if it is not filled.
+        fill_color: Option<Color>,
+        /// stroke_color is the color of the outline, or None if it is not outlined.
+        stroke_color: Option<Color>,
+    },
+}
+
+/// MockRenderer owns a headless Renderer which records draw commands instead of drawing them.
+/// It works without a browser, so you can use it in plain `cargo test`.
+/// The draw functions of retrospector are recorded, but Renderer::context must not be used, and
+/// Renderer::try_context returns None.
+#[derive(Debug)]
+pub struct MockRenderer {
+    renderer: Renderer,
+}
+
+impl MockRenderer {
+    /// new returns a MockRenderer with the size of the canvas.
+    pub fn new(canvas_width: f64, canvas_height: f64) -> Self {
+        Self {
+            renderer: Renderer::headless(canvas_width, canvas_height),
+        }
+    }
+
+    /// renderer returns the headless Renderer to pass to App::render.
+    pub fn renderer(&self) -> &Renderer {
+        &self.renderer
+    }
+
+    /// take_commands returns the recorded draw commands and clears them.
+    pub fn take_commands(&self) -> Vec<DrawCommand> {
+        self.renderer.take_commands()
+    }
+}
+
+/// mock_sprite returns a Sprite without an image, which can be drawn on a MockRenderer.
+pub fn mock_sprite(sx: f64, sy: f64, width: f64, height: f64) -> Sprite {
+    Sprite::new(
+        Rc::new(JsValue::NULL.unchecked_into()),
+        sx,
+        sy,
+        width,
+        height,
+    )
+}
+
+/// TestHarness drives App::update and App::render for synthetic frames without a browser.
+/// ```
+/// use anyhow::Result;
+/// use retrospector::app::App;
+/// use retrospector::render::{clear, Renderer};
+/// use retrospector::test::{DrawCommand, TestHarness};
+/// use retrospector::update::KeyEvent;
+///
+/// struct Counter {
+///     count: u32,
+/// }
+///
+/// impl App for Counter {
+///     fn update(&mut self, _elapsed_time: f64, key_event: &KeyEvent) -> Result<()> {
+///         if key_event.is_enter_down() {
+///             self.count += 1;
+///         }
+///         Ok(())
+///     }
+///
+///     fn render(&self, renderer: &Renderer) -> Result<()> {
+///         clear(renderer);
+///         Ok(())
+///     }
+/// }
+///
+/// let mut harness = TestHarness::new(Counter { count: 0 }, 352.0, 352.0);
+/// harness.key_down(web_sys::KeyEvent::DOM_VK_RETURN);
+/// harness.run_frames(3)?;
+/// harness.key_up(web_sys::KeyEvent::DOM_VK_RETURN);
+/// harness.run_frames(2)?;
+/// assert_eq!(harness.app().count, 3);
+/// assert_eq!(harness.commands(), &[DrawCommand::Clear]);
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct TestHarness<T: App> {
+    app: T,
+    renderer: MockRenderer,
+    key_event: KeyEvent,
+    elapsed_time: f64,
+    frame_ms: f64,
+    frame: u64,
+    commands: Vec<DrawCommand>,
+    overlay: Option<Overlay>,
+}
+
+impl<T: App> TestHarness<T> {
+    /// new returns a TestHarness with the size of the canvas. Each frame takes 1/60 seconds.
+    pub fn new(app: T, canvas_width: f64, canvas_height: f64) -> Self {
+        Self {
+            app,
+            renderer: MockRenderer::new(canvas_width, canvas_height),
+            key_event: KeyEvent::new(),
+            elapsed_time: 0.0,
+            frame_ms: 1000.0 / 60.0,
+            frame: 0,
+            commands: vec![],
+            overlay: None,
+        }
+    }
+
+    /// frame_ms sets the duration of each synthetic frame in milliseconds.
+    pub fn frame_ms(mut self, frame_ms: f64) -> Self {
+        self.frame_ms = frame_ms;
+        self
+    }
+
+    /// overlay makes every frame call the click callbacks of overlay before App::update as run
+    /// does. Click its elements with Overlay::click.
+    pub fn overlay(mut self, overlay: &Overlay) -> Self {
+        self.overlay = Some(overlay.clone());
+        self
+    }
+
+    /// key_down makes the key with key_code down, e.g. web_sys::KeyEvent::DOM_VK_RETURN.
+    pub fn key_down(&mut self, key_code: u32) {
+        self.key_event.set_key(key_code, true);
+    }
+
+    /// key_up makes the key with key_code up.
+    pub fn key_up(&mut self, key_code: u32) {
+        self.key_event.set_key(key_code, false);
+    }
+
+    /// run_frames calls App::update and renders App::render_passes n times.
+    /// It stops early when App::should_stop returns true, or returns the first error.
+    pub fn run_frames(&mut self, n: u64) -> Result<()> {
+        for _ in 0..n {
+            self.elapsed_time += self.frame_ms;
+            if let Some(overlay) = &self.overlay {
+                overlay.dispatch_clicks();
+            }
+            self.app.update(self.elapsed_time, &self.key_event)?;
+            self.frame += 1;
+            if self.app.should_stop() {
+                return Ok(());
+            }
+            let result = render_passes(&self.app, self.renderer.renderer());
+            self.commands = self.renderer.take_commands();
+            result?;
+        }
+        Ok(())
+    }
+
+    /// app returns the app under test.
+    pub fn app(&self) -> &T {
+        &self.app
+    }
+
+    /// app_mut returns the app under test mutably.
+    pub fn app_mut(&mut self) -> &mut T {
+        &mut self.app
+    }
+
+    /// commands returns the draw commands recorded in the last rendered frame.
+    pub fn commands(&self) -> &[DrawCommand] {
+        &self.commands
+    }
+
+    /// elapsed_time returns the time passed to the last App::update.
+    pub fn elapsed_time(&self) -> f64 {
+        self.elapsed_time
+    }
+
+    /// frame returns the number of updates so far.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+}
@@ -0,0 +1,474 @@
+use std::collections::VecDeque;
+
+use anyhow::{ensure, Result};
+
+/// FrameTiming measures how long each frame takes.
+#[derive(Debug, Clone)]
+pub struct FrameTiming {
+    frame_times: VecDeque<f64>,
+    last_time: Option<f64>,
+    frame: u64,
+    update: PhaseStats,
+    render: PhaseStats,
+}
+
+impl FrameTiming {
+    // The number of frames kept for the percentile.
+    const WINDOW: usize = 120;
+    // The duration over which fps is smoothed.
+    const FPS_SPAN_MS: f64 = 1000.0;
+
+    pub(crate) fn new() -> Self {
+        Self {
+            frame_times: VecDeque::with_capacity(Self::WINDOW),
+            last_time: None,
+            frame: 0,
+            update: PhaseStats::new(Self::WINDOW),
+            render: PhaseStats::new(Self::WINDOW),
+        }
+    }
+
+    /// reset forgets the last frame, so that the next frame does not count the stalled duration.
+    pub(crate) fn reset(&mut self) {
+        self.last_time = None;
+    }
+
+    /// record adds a frame which started at time in milliseconds.
+    pub(crate) fn record(&mut self, time: f64) {
+        if let Some(last_time) = self.last_time {
+            if self.frame_times.len() == Self::WINDOW {
+                self.frame_times.pop_front();
+            }
+            self.frame_times.push_back((time - last_time).max(0.0));
+        }
+        self.last_time = Some(time);
+    }
+
+    pub(crate) fn set_frame(&mut self, frame: u64) {
+        self.frame = frame;
+    }
+
+    /// record_update adds the duration of App::update in milliseconds.
+    pub(crate) fn record_update(&mut self, duration_ms: f64) {
+        self.update.record(duration_ms);
+    }
+
+    /// record_render adds the duration of App::render in milliseconds.
+    pub(crate) fn record_render(&mut self, duration_ms: f64) {
+        self.render.record(duration_ms);
+    }
+
+    /// frame returns the number of updates so far. It does not advance on skipped or paused frames.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// fps returns the frames per second smoothed over about a second.
+    pub fn fps(&self) -> f64 {
+        let mut count = 0;
+        let mut total_ms = 0.0;
+        for frame_ms in self.frame_times.iter().rev() {
+            if total_ms >= Self::FPS_SPAN_MS {
+                break;
+            }
+            count += 1;
+            total_ms += frame_ms;
+        }
+        if total_ms <= 0.0 {
+            return 0.0;
+        }
+        count as f64 * 1000.0 / total_ms
+    }
+
+    /// last_frame_ms returns the duration of the last frame in milliseconds.
+    pub fn last_frame_ms(&self) -> f64 {
+        self.frame_times.back().copied().unwrap_or(0.0)
+    }
+
+    /// p95_frame_ms returns the 95th-percentile duration of the recent frames in milliseconds.
+    pub fn p95_frame_ms(&self) -> f64 {
+        if self.frame_times.is_empty() {
+            return 0.0;
+        }
+        let mut sorted: Vec<f64> = self.frame_times.iter().copied().collect();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let index = ((sorted.len() as f64 * 0.95).ceil() as usize).saturating_sub(1);
+        sorted[index]
+    }
+
+    /// update_stats returns the durations of App::update over the recent frames.
+    pub fn update_stats(&self) -> &PhaseStats {
+        &self.update
+    }
+
+    /// render_stats returns the durations of App::render over the recent frames.
+    pub fn render_stats(&self) -> &PhaseStats {
+        &self.render
+    }
+}
+
+/// PhaseStats aggregates the durations of a phase of the loop, e.g. update or render.
+#[derive(Debug, Clone)]
+pub struct PhaseStats {
+    durations: VecDeque<f64>,
+    window: usize,
+    total_ms: f64,
+}
+
+impl PhaseStats {
+    pub(crate) fn new(window: usize) -> Self {
+        Self {
+            durations: VecDeque::with_capacity(window),
+            window,
+            total_ms: 0.0,
+        }
+    }
+
+    /// record adds a duration in milliseconds and drops the oldest one out of the window.
+    pub(crate) fn record(&mut self, duration_ms: f64) {
+        let duration_ms = duration_ms.max(0.0);
+        if self.durations.len() == self.window {
+            if let Some(oldest) = self.durations.pop_front() {
+                self.total_ms -= oldest;
+            }
+        }
+        self.durations.push_back(duration_ms);
+        self.total_ms += duration_ms;
+    }
+
+    /// last_ms returns the last duration in milliseconds.
+    pub fn last_ms(&self) -> f64 {
+        self.durations.back().copied().unwrap_or(0.0)
+    }
+
+    /// average_ms returns the average duration of the recent frames in milliseconds.
+    pub fn average_ms(&self) -> f64 {
+        if self.durations.is_empty() {
+            return 0.0;
+        }
+        (self.total_ms / self.durations.len() as f64).max(0.0)
+    }
+
+    /// min_ms returns the shortest duration of the recent frames in milliseconds.
+    pub fn min_ms(&self) -> f64 {
+        self.durations
+            .iter()
+            .copied()
+            .reduce(f64::min)
+            .unwrap_or(0.0)
+    }
+
+    /// max_ms returns the longest duration of the recent frames in milliseconds.
+    pub fn max_ms(&self) -> f64 {
+        self.durations.iter().copied().fold(0.0, f64::max)
+    }
+}
+
+/// FrameReport is passed to the callback set by AppConfigBuilder::on_slow_frame.
+/// It is also the FrameStats of the profiler.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameReport {
+    frame: u64,
+    update_ms: f64,
+    render_ms: f64,
+}
+
+impl FrameReport {
+    pub(crate) fn new(frame: u64, update_ms: f64, render_ms: f64) -> Self {
+        Self {
+            frame,
+            update_ms,
+            render_ms,
+        }
+    }
+
+    /// frame returns the number of updates so far at the slow frame.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// update_ms returns the duration of App::update in milliseconds, or 0.0 if it was skipped.
+    pub fn update_ms(&self) -> f64 {
+        self.update_ms
+    }
+
+    /// render_ms returns the duration of App::render in milliseconds, or 0.0 if it was skipped.
+    pub fn render_ms(&self) -> f64 {
+        self.render_ms
+    }
+
+    /// total_ms returns the sum of update_ms and render_ms.
+    pub fn total_ms(&self) -> f64 {
+        self.update_ms + self.render_ms
+    }
+}
+
+/// GameClock accumulates the passage of the time scaled by a time scale.
+/// The game time passed to App::update comes from it, so the time scale applies to everything
+/// driven by that time, including a FixedTimestep: at 0.5 it runs half as many steps of the same
+/// length, and at 0.0 or while paused it runs none. AppHandle::step_by advances it by exactly
+/// the step regardless of the time scale.
+#[derive(Debug)]
+pub(crate) struct GameClock {
+    last_time: Option<f64>,
+    elapsed: Option<f64>,
+}
+
+impl GameClock {
+    pub(crate) fn new() -> Self {
+        Self {
+            last_time: None,
+            elapsed: None,
+        }
+    }
+
+    /// reset forgets the last tick, so that the next tick does not count the stalled duration.
+    pub(crate) fn reset(&mut self) {
+        self.last_time = None;
+    }
+
+    /// tick advances the clock to time scaled by time_scale and returns the elapsed game time.
+    /// The first tick starts the clock at time, so the clock equals time when time_scale is 1.0.
+    pub(crate) fn tick(&mut self, time: f64, time_scale: f64) -> f64 {
+        let elapsed = match (self.elapsed, self.last_time) {
+            (Some(elapsed), Some(last_time)) => elapsed + (time - last_time).max(0.0) * time_scale,
+            (Some(elapsed), None) => elapsed,
+            (None, _) => time,
+        };
+        self.elapsed = Some(elapsed);
+        self.last_time = Some(time);
+        elapsed
+    }
+
+    /// advance moves the clock forward by step regardless of the time scale.
+    pub(crate) fn advance(&mut self, step: f64) -> f64 {
+        let elapsed = self.elapsed.unwrap_or(0.0) + step;
+        self.elapsed = Some(elapsed);
+        elapsed
+    }
+}
+
+/// FixedTimestep splits the game time passed to App::update into steps of a fixed length, e.g.
+/// for deterministic physics. It is fed with the game time, which is already scaled by the time
+/// scale, so slow motion runs fewer steps rather than shorter ones.
+#[derive(Debug, Clone)]
+pub struct FixedTimestep {
+    step_ms: f64,
+    max_steps: u32,
+    last_time: Option<f64>,
+    accumulator: f64,
+}
+
+impl FixedTimestep {
+    /// new returns a FixedTimestep with steps of step_ms milliseconds, e.g. 1000.0 / 60.0.
+    pub fn new(step_ms: f64) -> Result<Self> {
+        ensure!(
+            step_ms.is_finite() && step_ms > 0.0,
+            "step_ms: {} of a fixed timestep should be positive and finite",
+            step_ms
+        );
+        Ok(Self {
+            step_ms,
+            max_steps: 8,
+            last_time: None,
+            accumulator: 0.0,
+        })
+    }
+
+    /// max_steps sets the most steps which one call of steps returns, so that a slow frame does
+    /// not make the next frames slower. The time beyond it is dropped. It is 8 by default.
+    pub fn max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = max_steps.max(1);
+        self
+    }
+
+    /// steps adds the game time since the last call, i.e. the elapsed_time passed to
+    /// App::update, and returns the number of steps to run. The first call returns 0.
+    pub fn steps(&mut self, elapsed_time: f64) -> u32 {
+        if let Some(last_time) = self.last_time {
+            self.accumulator += (elapsed_time - last_time).max(0.0);
+        }
+        self.last_time = Some(elapsed_time);
+        let steps = (self.accumulator / self.step_ms).floor();
+        if steps > self.max_steps as f64 {
+            self.accumulator = 0.0;
+            return self.max_steps;
+        }
+        self.accumulator -= steps * self.step_ms;
+        steps as u32
+    }
+
+    /// alpha returns how far the game time is into the next step from 0.0 to 1.0, e.g. to
+    /// interpolate the positions between the last two steps.
+    pub fn alpha(&self) -> f64 {
+        (self.accumulator / self.step_ms).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FixedTimestep, FrameTiming, GameClock, PhaseStats};
+
+    // record_frames records frames of the given durations after a frame at start.
+    fn record_frames(timing: &mut FrameTiming, start: f64, durations: &[f64]) -> f64 {
+        let mut time = start;
+        timing.record(time);
+        for duration in durations {
+            time += duration;
+            timing.record(time);
+        }
+        time
+    }
+
+    #[test]
+    fn frame_timing_of_an_empty_window_is_zero() {
+        let mut timing = FrameTiming::new();
+        assert_eq!(timing.fps(), 0.0);
+        assert_eq!(timing.p95_frame_ms(), 0.0);
+        assert_eq!(timing.last_frame_ms(), 0.0);
+        // The first frame has no duration yet.
+        timing.record(100.0);
+        assert_eq!(timing.fps(), 0.0);
+        assert_eq!(timing.p95_frame_ms(), 0.0);
+    }
+
+    #[test]
+    fn frame_timing_of_a_partially_filled_window_uses_the_recorded_frames() {
+        let mut timing = FrameTiming::new();
+        record_frames(&mut timing, 0.0, &[16.0, 16.0, 32.0]);
+        assert_eq!(timing.fps(), 3.0 * 1000.0 / 64.0);
+        assert_eq!(timing.last_frame_ms(), 32.0);
+        assert_eq!(timing.p95_frame_ms(), 32.0);
+    }
+
+    #[test]
+    fn frame_timing_drops_the_oldest_frames_when_the_window_wraps() {
+        let mut timing = FrameTiming::new();
+        let slow = vec![50.0; 80];
+        let fast = vec![10.0; FrameTiming::WINDOW];
+        let time = record_frames(&mut timing, 0.0, &slow);
+        record_frames(&mut timing, time, &fast);
+        assert_eq!(timing.frame_times.len(), FrameTiming::WINDOW);
+        // None of the slow frames are left in the window.
+        assert_eq!(timing.p95_frame_ms(), 10.0);
+        // fps is smoothed over the last second only.
+        assert_eq!(timing.fps(), 100.0);
+    }
+
+    #[test]
+    fn frame_timing_p95_of_a_known_distribution() {
+        let mut timing = FrameTiming::new();
+        // 1 to 100 ms in a shuffled order.
+        let durations: Vec<f64> = (0..100).map(|i| ((i * 37) % 100 + 1) as f64).collect();
+        record_frames(&mut timing, 0.0, &durations);
+        assert_eq!(timing.p95_frame_ms(), 95.0);
+    }
+
+    #[test]
+    fn frame_timing_reset_does_not_count_the_stall() {
+        let mut timing = FrameTiming::new();
+        record_frames(&mut timing, 0.0, &[16.0, 16.0]);
+        timing.reset();
+        record_frames(&mut timing, 5000.0, &[16.0]);
+        assert_eq!(timing.p95_frame_ms(), 16.0);
+    }
+
+    // fixed_steps counts the steps of 10 ms which a second of 60 Hz frames runs at time_scale.
+    fn fixed_steps(clock: &mut GameClock, fixed: &mut FixedTimestep, time_scale: f64) -> u32 {
+        (0..=60)
+            .map(|i| fixed.steps(clock.tick(1000.0 + i as f64 * 1000.0 / 60.0, time_scale)))
+            .sum()
+    }
+
+    #[test]
+    fn time_scale_feeds_the_fixed_timestep() -> anyhow::Result<()> {
+        let mut clock = GameClock::new();
+        let mut fixed = FixedTimestep::new(10.0)?;
+        assert_eq!(fixed_steps(&mut clock, &mut fixed, 1.0), 100);
+
+        let mut clock = GameClock::new();
+        let mut fixed = FixedTimestep::new(10.0)?;
+        assert_eq!(fixed_steps(&mut clock, &mut fixed, 0.5), 50);
+
+        let mut clock = GameClock::new();
+        let mut fixed = FixedTimestep::new(10.0)?;
+        assert_eq!(fixed_steps(&mut clock, &mut fixed, 0.0), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn pausing_stops_the_fixed_timestep_and_step_by_runs_exact_steps() -> anyhow::Result<()> {
+        let mut clock = GameClock::new();
+        let mut fixed = FixedTimestep::new(10.0)?;
+        fixed.steps(clock.tick(0.0, 1.0));
+        assert_eq!(fixed.steps(clock.tick(30.0, 1.0)), 3);
+        // The loop ticks with a time scale of 0.0 while paused.
+        assert_eq!(fixed.steps(clock.tick(5000.0, 0.0)), 0);
+        // step_by advances by the step, even in slow motion.
+        assert_eq!(fixed.steps(clock.advance(10.0)), 1);
+        assert_eq!(fixed.steps(clock.tick(5010.0, 1.0)), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn fixed_timestep_carries_the_remainder_and_caps_the_steps() -> anyhow::Result<()> {
+        let mut fixed = FixedTimestep::new(10.0)?.max_steps(4);
+        assert_eq!(fixed.steps(100.0), 0);
+        assert_eq!(fixed.steps(115.0), 1);
+        assert_eq!(fixed.alpha(), 0.5);
+        assert_eq!(fixed.steps(120.0), 1);
+        assert_eq!(fixed.alpha(), 0.0);
+        // A stall runs max_steps and drops the rest.
+        assert_eq!(fixed.steps(1120.0), 4);
+        assert_eq!(fixed.steps(1125.0), 0);
+        assert!(FixedTimestep::new(0.0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn phase_stats_of_no_durations_are_zero() {
+        let stats = PhaseStats::new(4);
+        assert_eq!(stats.last_ms(), 0.0);
+        assert_eq!(stats.min_ms(), 0.0);
+        assert_eq!(stats.average_ms(), 0.0);
+        assert_eq!(stats.max_ms(), 0.0);
+    }
+
+    #[test]
+    fn phase_stats_aggregate_the_window_and_drop_the_oldest() {
+        let mut stats = PhaseStats::new(4);
+        for duration in [2.0, 8.0, 4.0, 6.0] {
+            stats.record(duration);
+        }
+        assert_eq!(
+            (stats.min_ms(), stats.average_ms(), stats.max_ms()),
+            (2.0, 5.0, 8.0)
+        );
+        assert_eq!(stats.last_ms(), 6.0);
+        // 2.0 and 8.0 leave the window, and the total follows them.
+        stats.record(3.0);
+        stats.record(3.0);
+        assert_eq!(
+            (stats.min_ms(), stats.average_ms(), stats.max_ms()),
+            (3.0, 4.0, 6.0)
+        );
+        // Negative durations, e.g. from a clock going backwards, count as 0.0.
+        stats.record(-1.0);
+        assert_eq!(stats.min_ms(), 0.0);
+    }
+
+    #[test]
+    fn frame_timing_keeps_the_update_and_render_stats_apart_across_reset() {
+        let mut timing = FrameTiming::new();
+        timing.record_update(1.0);
+        timing.record_render(4.0);
+        timing.record_update(3.0);
+        timing.record_render(6.0);
+        // reset only forgets the last frame time, so the stats of the phases are kept.
+        timing.reset();
+        assert_eq!(timing.update_stats().average_ms(), 2.0);
+        assert_eq!(timing.render_stats().average_ms(), 5.0);
+        assert_eq!(timing.update_stats().max_ms(), 3.0);
+        assert_eq!(timing.render_stats().min_ms(), 4.0);
+    }
+}
@@ -0,0 +1,311 @@
+use std::f64::consts::PI;
+use std::fmt;
+
+use crate::render::Position;
+use crate::schedule::TaskHandle;
+
+/// Easing shapes how a Tween moves from the start to the end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Easing {
+    /// Linear moves at a constant speed.
+    Linear,
+    /// QuadIn starts slowly and accelerates.
+    QuadIn,
+    /// QuadOut starts fast and decelerates.
+    QuadOut,
+    /// QuadInOut accelerates and then decelerates.
+    QuadInOut,
+    /// CubicIn is a steeper QuadIn.
+    CubicIn,
+    /// CubicOut is a steeper QuadOut.
+    CubicOut,
+    /// CubicInOut is a steeper QuadInOut.
+    CubicInOut,
+    /// ElasticIn winds up like a spring before moving.
+    ElasticIn,
+    /// ElasticOut overshoots the end and springs back, e.g. for popping UI.
+    ElasticOut,
+    /// BounceIn is BounceOut backwards.
+    BounceIn,
+    /// BounceOut bounces at the end like a dropped ball.
+    BounceOut,
+}
+
+impl Easing {
+    /// apply returns the eased progress of t from 0.0 to 1.0. t is clamped to 0.0..=1.0.
+    /// The result starts at 0.0 and ends at 1.0, but ElasticIn and ElasticOut go beyond them.
+    pub fn apply(self, t: f64) -> f64 {
+        let t = if t.is_nan() { 1.0 } else { t.clamp(0.0, 1.0) };
+        match self {
+            Self::Linear => t,
+            Self::QuadIn => t * t,
+            Self::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Self::QuadInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Self::CubicIn => t * t * t,
+            Self::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Self::CubicInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Self::ElasticIn => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else {
+                    -(2f64.powf(10.0 * t - 10.0)) * ((t * 10.0 - 10.75) * (2.0 * PI / 3.0)).sin()
+                }
+            }
+            Self::ElasticOut => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else {
+                    2f64.powf(-10.0 * t) * ((t * 10.0 - 0.75) * (2.0 * PI / 3.0)).sin() + 1.0
+                }
+            }
+            Self::BounceIn => 1.0 - bounce_out(1.0 - t),
+            Self::BounceOut => bounce_out(t),
+        }
+    }
+}
+
+fn bounce_out(t: f64) -> f64 {
+    const N: f64 = 7.5625;
+    const D: f64 = 2.75;
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+/// Tweenable is a value which a Tween interpolates, e.g. f64 and Position.
+pub trait Tweenable {
+    /// lerp returns the value at progress t between start (0.0) and end (1.0).
+    fn lerp(start: &Self, end: &Self, t: f64) -> Self;
+}
+
+impl Tweenable for f64 {
+    fn lerp(start: &Self, end: &Self, t: f64) -> Self {
+        start + (end - start) * t
+    }
+}
+
+impl Tweenable for Position {
+    fn lerp(start: &Self, end: &Self, t: f64) -> Self {
+        Position::new(
+            f64::lerp(&start.dx(), &end.dx(), t),
+            f64::lerp(&start.dy(), &end.dy(), t),
+        )
+    }
+}
+
+/// Tween interpolates a value from start to end over a duration, advanced by the frame delta,
+/// e.g. a sliding menu or a counting score. Chain more segments with then.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tween<V> {
+    start: V,
+    segments: Vec<Segment<V>>,
+    elapsed_ms: f64,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Segment<V> {
+    end: V,
+    duration_ms: f64,
+    easing: Easing,
+}
+
+impl<V: Tweenable> Tween<V> {
+    /// new returns a Tween from start to end over duration_ms milliseconds.
+    pub fn new(start: V, end: V, duration_ms: f64, easing: Easing) -> Self {
+        Self {
+            start,
+            segments: vec![],
+            elapsed_ms: 0.0,
+        }
+        .then(end, duration_ms, easing)
+    }
+
+    /// then appends a segment which moves from the end of the previous one to end over
+    /// duration_ms milliseconds.
+    pub fn then(mut self, end: V, duration_ms: f64, easing: Easing) -> Self {
+        self.segments.push(Segment {
+            end,
+            duration_ms: duration_ms.max(0.0),
+            easing,
+        });
+        self
+    }
+
+    /// update advances the Tween by dt milliseconds and returns the value.
+    pub fn update(&mut self, dt: f64) -> V {
+        self.elapsed_ms = (self.elapsed_ms + dt.max(0.0)).min(self.duration_ms());
+        self.value()
+    }
+
+    /// value returns the current value.
+    pub fn value(&self) -> V {
+        let mut from = &self.start;
+        let mut segment_start_ms = 0.0;
+        for (i, segment) in self.segments.iter().enumerate() {
+            let segment_end_ms = segment_start_ms + segment.duration_ms;
+            let last = i + 1 == self.segments.len();
+            if self.elapsed_ms < segment_end_ms || last {
+                let t = if segment.duration_ms > 0.0 {
+                    (self.elapsed_ms - segment_start_ms) / segment.duration_ms
+                } else {
+                    1.0
+                };
+                return V::lerp(from, &segment.end, segment.easing.apply(t));
+            }
+            from = &segment.end;
+            segment_start_ms = segment_end_ms;
+        }
+        // new always adds a segment, so this is not reached.
+        V::lerp(from, from, 0.0)
+    }
+
+    /// is_finished returns true when the Tween reached the end of the last segment.
+    pub fn is_finished(&self) -> bool {
+        self.elapsed_ms >= self.duration_ms()
+    }
+
+    /// duration_ms returns the total duration of the segments in milliseconds.
+    pub fn duration_ms(&self) -> f64 {
+        self.segments
+            .iter()
+            .map(|segment| segment.duration_ms)
+            .sum()
+    }
+
+    /// reset rewinds the Tween to the start.
+    pub fn reset(&mut self) {
+        self.elapsed_ms = 0.0;
+    }
+}
+
+/// Tweens runs many Tweens at once. Every update, each Tween passes its value to its apply
+/// callback with the context passed to update, e.g. your App, and runs its on_complete callback
+/// when it finishes.
+pub struct Tweens<C> {
+    entries: Vec<Entry<C>>,
+}
+
+// Step advances a Tween by the delta, applies its value and returns true when it finished.
+type Step<C> = Box<dyn FnMut(f64, &mut C) -> bool>;
+type OnComplete<C> = Box<dyn FnOnce(&mut C)>;
+
+struct Entry<C> {
+    handle: TaskHandle,
+    step: Step<C>,
+    on_complete: Option<OnComplete<C>>,
+}
+
+impl<C: 'static> Tweens<C> {
+    /// new returns Tweens without Tweens.
+    pub fn new() -> Self {
+        Self { entries: vec![] }
+    }
+
+    /// add runs tween and passes its value to apply every update.
+    /// The handle cancels the Tween without running the rest.
+    pub fn add<V: Tweenable + 'static>(
+        &mut self,
+        tween: Tween<V>,
+        apply: impl FnMut(&mut C, V) + 'static,
+    ) -> TaskHandle {
+        self.push(tween, apply, None)
+    }
+
+    /// add_with_complete is add which also runs on_complete once the tween finishes, e.g. to
+    /// start the next animation.
+    pub fn add_with_complete<V: Tweenable + 'static>(
+        &mut self,
+        tween: Tween<V>,
+        apply: impl FnMut(&mut C, V) + 'static,
+        on_complete: impl FnOnce(&mut C) + 'static,
+    ) -> TaskHandle {
+        self.push(tween, apply, Some(Box::new(on_complete)))
+    }
+
+    /// update advances all the Tweens by dt milliseconds, in the order they were added.
+    pub fn update(&mut self, dt: f64, ctx: &mut C) {
+        let mut i = 0;
+        while i < self.entries.len() {
+            let entry = &mut self.entries[i];
+            if entry.handle.is_cancelled() {
+                self.entries.remove(i);
+                continue;
+            }
+            if !(entry.step)(dt, ctx) {
+                i += 1;
+                continue;
+            }
+            let entry = self.entries.remove(i);
+            if let Some(on_complete) = entry.on_complete {
+                on_complete(ctx);
+            }
+        }
+    }
+
+    /// len returns the number of the running Tweens.
+    pub fn len(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| !entry.handle.is_cancelled())
+            .count()
+    }
+
+    /// is_empty returns true when no Tween is running.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn push<V: Tweenable + 'static>(
+        &mut self,
+        mut tween: Tween<V>,
+        mut apply: impl FnMut(&mut C, V) + 'static,
+        on_complete: Option<OnComplete<C>>,
+    ) -> TaskHandle {
+        let handle = TaskHandle::new();
+        self.entries.push(Entry {
+            handle: handle.clone(),
+            step: Box::new(move |dt, ctx| {
+                apply(ctx, tween.update(dt));
+                tween.is_finished()
+            }),
+            on_complete,
+        });
+        handle
+    }
+}
+
+impl<C: 'static> Default for Tweens<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: 'static> fmt::Debug for Tweens<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Tweens").field("len", &self.len()).finish()
+    }
+}
@@ -1,5 +1,160 @@
+use std::collections::{HashMap, VecDeque};
+
+use anyhow::{ensure, Result};
+
+use crate::render::Position;
+
+/// Key represents a key which KeyEvent stores.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Key {
+    /// The Enter key.
+    Enter,
+    /// The ArrowLeft key.
+    ArrowLeft,
+    /// The ArrowUp key.
+    ArrowUp,
+    /// The ArrowRight key.
+    ArrowRight,
+    /// The ArrowDown key.
+    ArrowDown,
+    /// The Digit0 key.
+    Digit0,
+    /// The Digit1 key.
+    Digit1,
+    /// The Digit2 key.
+    Digit2,
+    /// The Digit3 key.
+    Digit3,
+    /// The Digit4 key.
+    Digit4,
+    /// The Digit5 key.
+    Digit5,
+    /// The Digit6 key.
+    Digit6,
+    /// The Digit7 key.
+    Digit7,
+    /// The Digit8 key.
+    Digit8,
+    /// The Digit9 key.
+    Digit9,
+    /// The KeyA key.
+    KeyA,
+    /// The KeyB key.
+    KeyB,
+    /// The KeyC key.
+    KeyC,
+    /// The KeyD key.
+    KeyD,
+    /// The KeyE key.
+    KeyE,
+    /// The KeyF key.
+    KeyF,
+    /// The KeyG key.
+    KeyG,
+    /// The KeyH key.
+    KeyH,
+    /// The KeyI key.
+    KeyI,
+    /// The KeyJ key.
+    KeyJ,
+    /// The KeyK key.
+    KeyK,
+    /// The KeyL key.
+    KeyL,
+    /// The KeyM key.
+    KeyM,
+    /// The KeyN key.
+    KeyN,
+    /// The KeyO key.
+    KeyO,
+    /// The KeyP key.
+    KeyP,
+    /// The KeyQ key.
+    KeyQ,
+    /// The KeyR key.
+    KeyR,
+    /// The KeyS key.
+    KeyS,
+    /// The KeyT key.
+    KeyT,
+    /// The KeyU key.
+    KeyU,
+    /// The KeyV key.
+    KeyV,
+    /// The KeyW key.
+    KeyW,
+    /// The KeyX key.
+    KeyX,
+    /// The KeyY key.
+    KeyY,
+    /// The KeyZ key.
+    KeyZ,
+    /// Either Shift key.
+    Shift,
+    /// Either Control key.
+    Control,
+    /// Either Alt key, which is Option on macOS.
+    Alt,
+}
+
+impl Key {
+    const ALL: [Key; 44] = [
+        Key::Enter,
+        Key::ArrowLeft,
+        Key::ArrowUp,
+        Key::ArrowRight,
+        Key::ArrowDown,
+        Key::Digit0,
+        Key::Digit1,
+        Key::Digit2,
+        Key::Digit3,
+        Key::Digit4,
+        Key::Digit5,
+        Key::Digit6,
+        Key::Digit7,
+        Key::Digit8,
+        Key::Digit9,
+        Key::KeyA,
+        Key::KeyB,
+        Key::KeyC,
+        Key::KeyD,
+        Key::KeyE,
+        Key::KeyF,
+        Key::KeyG,
+        Key::KeyH,
+        Key::KeyI,
+        Key::KeyJ,
+        Key::KeyK,
+        Key::KeyL,
+        Key::KeyM,
+        Key::KeyN,
+        Key::KeyO,
+        Key::KeyP,
+        Key::KeyQ,
+        Key::KeyR,
+        Key::KeyS,
+        Key::KeyT,
+        Key::KeyU,
+        Key::KeyV,
+        Key::KeyW,
+        Key::KeyX,
+        Key::KeyY,
+        Key::KeyZ,
+        Key::Shift,
+        Key::Control,
+        Key::Alt,
+    ];
+
+    // bit returns the bit of the key in KeyEvent::to_bits, which follows the order of ALL.
+    fn bit(self) -> u64 {
+        let index = Key::ALL.iter().position(|&key| key == self).unwrap_or(0);
+        1 << index
+    }
+}
+
 /// KeyEvent stores which key is down and which key is up.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct KeyEvent {
     enter: bool,
     arrow_left: bool,
@@ -42,6 +197,9 @@ pub struct KeyEvent {
     key_x: bool,
     key_y: bool,
     key_z: bool,
+    shift: bool,
+    control: bool,
+    alt: bool,
 }
 
 impl KeyEvent {
@@ -88,9 +246,103 @@ impl KeyEvent {
             key_x: false,
             key_y: false,
             key_z: false,
+            shift: false,
+            control: false,
+            alt: false,
+        }
+    }
+
+    /// When the key is down(up), is_down returns true(false).
+    pub fn is_down(&self, key: Key) -> bool {
+        match key {
+            Key::Enter => self.enter,
+            Key::ArrowLeft => self.arrow_left,
+            Key::ArrowUp => self.arrow_up,
+            Key::ArrowRight => self.arrow_right,
+            Key::ArrowDown => self.arrow_down,
+            Key::Digit0 => self.digit_0,
+            Key::Digit1 => self.digit_1,
+            Key::Digit2 => self.digit_2,
+            Key::Digit3 => self.digit_3,
+            Key::Digit4 => self.digit_4,
+            Key::Digit5 => self.digit_5,
+            Key::Digit6 => self.digit_6,
+            Key::Digit7 => self.digit_7,
+            Key::Digit8 => self.digit_8,
+            Key::Digit9 => self.digit_9,
+            Key::KeyA => self.key_a,
+            Key::KeyB => self.key_b,
+            Key::KeyC => self.key_c,
+            Key::KeyD => self.key_d,
+            Key::KeyE => self.key_e,
+            Key::KeyF => self.key_f,
+            Key::KeyG => self.key_g,
+            Key::KeyH => self.key_h,
+            Key::KeyI => self.key_i,
+            Key::KeyJ => self.key_j,
+            Key::KeyK => self.key_k,
+            Key::KeyL => self.key_l,
+            Key::KeyM => self.key_m,
+            Key::KeyN => self.key_n,
+            Key::KeyO => self.key_o,
+            Key::KeyP => self.key_p,
+            Key::KeyQ => self.key_q,
+            Key::KeyR => self.key_r,
+            Key::KeyS => self.key_s,
+            Key::KeyT => self.key_t,
+            Key::KeyU => self.key_u,
+            Key::KeyV => self.key_v,
+            Key::KeyW => self.key_w,
+            Key::KeyX => self.key_x,
+            Key::KeyY => self.key_y,
+            Key::KeyZ => self.key_z,
+            Key::Shift => self.shift,
+            Key::Control => self.control,
+            Key::Alt => self.alt,
+        }
+    }
+
+    /// chord_down returns true when all the keys are down, e.g. `&[Key::Control, Key::KeyZ]`
+    /// for undo.
+    /// It returns true for empty keys.
+    pub fn chord_down(&self, keys: &[Key]) -> bool {
+        keys.iter().all(|&key| self.is_down(key))
+    }
+
+    /// keys_down returns the keys which are down, in the order of the variants of Key.
+    pub fn keys_down(&self) -> impl Iterator<Item = Key> + '_ {
+        Key::ALL.into_iter().filter(move |&key| self.is_down(key))
+    }
+
+    /// snapshot returns the keys which are down now. Keep it at the start of a frame, and
+    /// compare it with the KeyEvent later, e.g. with just_pressed.
+    pub fn snapshot(&self) -> KeyEventSnapshot {
+        KeyEventSnapshot {
+            bits: self.to_bits(),
         }
     }
 
+    /// just_pressed returns true when key is down now but was up in previous.
+    pub fn just_pressed(&self, previous: &KeyEventSnapshot, key: Key) -> bool {
+        self.is_down(key) && !previous.is_down(key)
+    }
+
+    /// just_released returns true when key is up now but was down in previous.
+    pub fn just_released(&self, previous: &KeyEventSnapshot, key: Key) -> bool {
+        !self.is_down(key) && previous.is_down(key)
+    }
+
+    /// keys_pressed_since returns the keys which were up in previous and are down now, in the
+    /// order of the variants of Key.
+    pub fn keys_pressed_since<'a>(
+        &'a self,
+        previous: &'a KeyEventSnapshot,
+    ) -> impl Iterator<Item = Key> + 'a {
+        Key::ALL
+            .into_iter()
+            .filter(move |&key| self.just_pressed(previous, key))
+    }
+
     /// When the Enter key is down(up), is_enter_down returns true(false).
     pub fn is_enter_down(&self) -> bool {
         self.enter
@@ -296,261 +548,655 @@ impl KeyEvent {
         self.key_z
     }
 
-    pub(crate) fn update_on_keydown(&mut self, event: web_sys::KeyboardEvent) {
-        match event.key_code() {
-            web_sys::KeyEvent::DOM_VK_RETURN => {
-                self.enter = true;
-            }
-            web_sys::KeyEvent::DOM_VK_LEFT => {
-                self.arrow_left = true;
-            }
-            web_sys::KeyEvent::DOM_VK_UP => {
-                self.arrow_up = true;
-            }
-            web_sys::KeyEvent::DOM_VK_RIGHT => {
-                self.arrow_right = true;
-            }
-            web_sys::KeyEvent::DOM_VK_DOWN => {
-                self.arrow_down = true;
-            }
-            web_sys::KeyEvent::DOM_VK_0 => {
-                self.digit_0 = true;
-            }
-            web_sys::KeyEvent::DOM_VK_1 => {
-                self.digit_1 = true;
-            }
-            web_sys::KeyEvent::DOM_VK_2 => {
-                self.digit_2 = true;
-            }
-            web_sys::KeyEvent::DOM_VK_3 => {
-                self.digit_3 = true;
-            }
-            web_sys::KeyEvent::DOM_VK_4 => {
-                self.digit_4 = true;
-            }
-            web_sys::KeyEvent::DOM_VK_5 => {
-                self.digit_5 = true;
-            }
-            web_sys::KeyEvent::DOM_VK_6 => {
-                self.digit_6 = true;
-            }
-            web_sys::KeyEvent::DOM_VK_7 => {
-                self.digit_7 = true;
-            }
-            web_sys::KeyEvent::DOM_VK_8 => {
-                self.digit_8 = true;
-            }
-            web_sys::KeyEvent::DOM_VK_9 => {
-                self.digit_9 = true;
-            }
-            web_sys::KeyEvent::DOM_VK_A => {
-                self.key_a = true;
-            }
-            web_sys::KeyEvent::DOM_VK_B => {
-                self.key_b = true;
-            }
-            web_sys::KeyEvent::DOM_VK_C => {
-                self.key_c = true;
-            }
-            web_sys::KeyEvent::DOM_VK_D => {
-                self.key_d = true;
-            }
-            web_sys::KeyEvent::DOM_VK_E => {
-                self.key_e = true;
-            }
-            web_sys::KeyEvent::DOM_VK_F => {
-                self.key_f = true;
-            }
-            web_sys::KeyEvent::DOM_VK_G => {
-                self.key_g = true;
-            }
-            web_sys::KeyEvent::DOM_VK_H => {
-                self.key_h = true;
-            }
-            web_sys::KeyEvent::DOM_VK_I => {
-                self.key_i = true;
-            }
-            web_sys::KeyEvent::DOM_VK_J => {
-                self.key_j = true;
-            }
-            web_sys::KeyEvent::DOM_VK_K => {
-                self.key_k = true;
-            }
-            web_sys::KeyEvent::DOM_VK_L => {
-                self.key_l = true;
-            }
-            web_sys::KeyEvent::DOM_VK_M => {
-                self.key_m = true;
-            }
-            web_sys::KeyEvent::DOM_VK_N => {
-                self.key_n = true;
-            }
-            web_sys::KeyEvent::DOM_VK_O => {
-                self.key_o = true;
-            }
-            web_sys::KeyEvent::DOM_VK_P => {
-                self.key_p = true;
-            }
-            web_sys::KeyEvent::DOM_VK_Q => {
-                self.key_q = true;
-            }
-            web_sys::KeyEvent::DOM_VK_R => {
-                self.key_r = true;
-            }
-            web_sys::KeyEvent::DOM_VK_S => {
-                self.key_s = true;
-            }
-            web_sys::KeyEvent::DOM_VK_T => {
-                self.key_t = true;
-            }
-            web_sys::KeyEvent::DOM_VK_U => {
-                self.key_u = true;
-            }
-            web_sys::KeyEvent::DOM_VK_V => {
-                self.key_v = true;
-            }
-            web_sys::KeyEvent::DOM_VK_W => {
-                self.key_w = true;
-            }
-            web_sys::KeyEvent::DOM_VK_X => {
-                self.key_x = true;
-            }
-            web_sys::KeyEvent::DOM_VK_Y => {
-                self.key_y = true;
-            }
-            web_sys::KeyEvent::DOM_VK_Z => {
-                self.key_z = true;
-            }
-            _ => {}
+    /// When either Shift key is down(up), is_shift_down returns true(false).
+    pub fn is_shift_down(&self) -> bool {
+        self.shift
+    }
+
+    /// When either Control key is down(up), is_control_down returns true(false).
+    pub fn is_control_down(&self) -> bool {
+        self.control
+    }
+
+    /// When either Alt key is down(up), is_alt_down returns true(false).
+    pub fn is_alt_down(&self) -> bool {
+        self.alt
+    }
+
+    pub(crate) fn to_bits(&self) -> u64 {
+        let keys = [
+            self.enter,
+            self.arrow_left,
+            self.arrow_up,
+            self.arrow_right,
+            self.arrow_down,
+            self.digit_0,
+            self.digit_1,
+            self.digit_2,
+            self.digit_3,
+            self.digit_4,
+            self.digit_5,
+            self.digit_6,
+            self.digit_7,
+            self.digit_8,
+            self.digit_9,
+            self.key_a,
+            self.key_b,
+            self.key_c,
+            self.key_d,
+            self.key_e,
+            self.key_f,
+            self.key_g,
+            self.key_h,
+            self.key_i,
+            self.key_j,
+            self.key_k,
+            self.key_l,
+            self.key_m,
+            self.key_n,
+            self.key_o,
+            self.key_p,
+            self.key_q,
+            self.key_r,
+            self.key_s,
+            self.key_t,
+            self.key_u,
+            self.key_v,
+            self.key_w,
+            self.key_x,
+            self.key_y,
+            self.key_z,
+            self.shift,
+            self.control,
+            self.alt,
+        ];
+        keys.iter()
+            .enumerate()
+            .fold(0, |bits, (i, &down)| bits | (down as u64) << i)
+    }
+
+    pub(crate) fn from_bits(bits: u64) -> Self {
+        let down = |i: u32| (bits >> i) & 1 == 1;
+        Self {
+            enter: down(0),
+            arrow_left: down(1),
+            arrow_up: down(2),
+            arrow_right: down(3),
+            arrow_down: down(4),
+            digit_0: down(5),
+            digit_1: down(6),
+            digit_2: down(7),
+            digit_3: down(8),
+            digit_4: down(9),
+            digit_5: down(10),
+            digit_6: down(11),
+            digit_7: down(12),
+            digit_8: down(13),
+            digit_9: down(14),
+            key_a: down(15),
+            key_b: down(16),
+            key_c: down(17),
+            key_d: down(18),
+            key_e: down(19),
+            key_f: down(20),
+            key_g: down(21),
+            key_h: down(22),
+            key_i: down(23),
+            key_j: down(24),
+            key_k: down(25),
+            key_l: down(26),
+            key_m: down(27),
+            key_n: down(28),
+            key_o: down(29),
+            key_p: down(30),
+            key_q: down(31),
+            key_r: down(32),
+            key_s: down(33),
+            key_t: down(34),
+            key_u: down(35),
+            key_v: down(36),
+            key_w: down(37),
+            key_x: down(38),
+            key_y: down(39),
+            key_z: down(40),
+            shift: down(41),
+            control: down(42),
+            alt: down(43),
         }
     }
 
+    pub(crate) fn update_on_keydown(&mut self, event: web_sys::KeyboardEvent) {
+        self.set_key(event.key_code(), true);
+    }
+
     pub(crate) fn update_on_keyup(&mut self, event: web_sys::KeyboardEvent) {
-        match event.key_code() {
+        self.set_key(event.key_code(), false);
+    }
+
+    pub(crate) fn set_key(&mut self, key_code: u32, down: bool) {
+        match key_code {
             web_sys::KeyEvent::DOM_VK_RETURN => {
-                self.enter = false;
+                self.enter = down;
             }
             web_sys::KeyEvent::DOM_VK_LEFT => {
-                self.arrow_left = false;
+                self.arrow_left = down;
             }
             web_sys::KeyEvent::DOM_VK_UP => {
-                self.arrow_up = false;
+                self.arrow_up = down;
             }
             web_sys::KeyEvent::DOM_VK_RIGHT => {
-                self.arrow_right = false;
+                self.arrow_right = down;
             }
             web_sys::KeyEvent::DOM_VK_DOWN => {
-                self.arrow_down = false;
+                self.arrow_down = down;
             }
             web_sys::KeyEvent::DOM_VK_0 => {
-                self.digit_0 = false;
+                self.digit_0 = down;
             }
             web_sys::KeyEvent::DOM_VK_1 => {
-                self.digit_1 = false;
+                self.digit_1 = down;
             }
             web_sys::KeyEvent::DOM_VK_2 => {
-                self.digit_2 = false;
+                self.digit_2 = down;
             }
             web_sys::KeyEvent::DOM_VK_3 => {
-                self.digit_3 = false;
+                self.digit_3 = down;
             }
             web_sys::KeyEvent::DOM_VK_4 => {
-                self.digit_4 = false;
+                self.digit_4 = down;
             }
             web_sys::KeyEvent::DOM_VK_5 => {
-                self.digit_5 = false;
+                self.digit_5 = down;
             }
             web_sys::KeyEvent::DOM_VK_6 => {
-                self.digit_6 = false;
+                self.digit_6 = down;
             }
             web_sys::KeyEvent::DOM_VK_7 => {
-                self.digit_7 = false;
+                self.digit_7 = down;
             }
             web_sys::KeyEvent::DOM_VK_8 => {
-                self.digit_8 = false;
+                self.digit_8 = down;
             }
             web_sys::KeyEvent::DOM_VK_9 => {
-                self.digit_9 = false;
+                self.digit_9 = down;
             }
             web_sys::KeyEvent::DOM_VK_A => {
-                self.key_a = false;
+                self.key_a = down;
             }
             web_sys::KeyEvent::DOM_VK_B => {
-                self.key_b = false;
+                self.key_b = down;
             }
             web_sys::KeyEvent::DOM_VK_C => {
-                self.key_c = false;
+                self.key_c = down;
             }
             web_sys::KeyEvent::DOM_VK_D => {
-                self.key_d = false;
+                self.key_d = down;
             }
             web_sys::KeyEvent::DOM_VK_E => {
-                self.key_e = false;
+                self.key_e = down;
             }
             web_sys::KeyEvent::DOM_VK_F => {
-                self.key_f = false;
+                self.key_f = down;
             }
             web_sys::KeyEvent::DOM_VK_G => {
-                self.key_g = false;
+                self.key_g = down;
             }
             web_sys::KeyEvent::DOM_VK_H => {
-                self.key_h = false;
+                self.key_h = down;
             }
             web_sys::KeyEvent::DOM_VK_I => {
-                self.key_i = false;
+                self.key_i = down;
             }
             web_sys::KeyEvent::DOM_VK_J => {
-                self.key_j = false;
+                self.key_j = down;
             }
             web_sys::KeyEvent::DOM_VK_K => {
-                self.key_k = false;
+                self.key_k = down;
             }
             web_sys::KeyEvent::DOM_VK_L => {
-                self.key_l = false;
+                self.key_l = down;
             }
             web_sys::KeyEvent::DOM_VK_M => {
-                self.key_m = false;
+                self.key_m = down;
             }
             web_sys::KeyEvent::DOM_VK_N => {
-                self.key_n = false;
+                self.key_n = down;
             }
             web_sys::KeyEvent::DOM_VK_O => {
-                self.key_o = false;
+                self.key_o = down;
             }
             web_sys::KeyEvent::DOM_VK_P => {
-                self.key_p = false;
+                self.key_p = down;
             }
             web_sys::KeyEvent::DOM_VK_Q => {
-                self.key_q = false;
+                self.key_q = down;
             }
             web_sys::KeyEvent::DOM_VK_R => {
-                self.key_r = false;
+                self.key_r = down;
             }
             web_sys::KeyEvent::DOM_VK_S => {
-                self.key_s = false;
+                self.key_s = down;
             }
             web_sys::KeyEvent::DOM_VK_T => {
-                self.key_t = false;
+                self.key_t = down;
             }
             web_sys::KeyEvent::DOM_VK_U => {
-                self.key_u = false;
+                self.key_u = down;
             }
             web_sys::KeyEvent::DOM_VK_V => {
-                self.key_v = false;
+                self.key_v = down;
             }
             web_sys::KeyEvent::DOM_VK_W => {
-                self.key_w = false;
+                self.key_w = down;
             }
             web_sys::KeyEvent::DOM_VK_X => {
-                self.key_x = false;
+                self.key_x = down;
             }
             web_sys::KeyEvent::DOM_VK_Y => {
-                self.key_y = false;
+                self.key_y = down;
             }
             web_sys::KeyEvent::DOM_VK_Z => {
-                self.key_z = false;
+                self.key_z = down;
+            }
+            web_sys::KeyEvent::DOM_VK_SHIFT => {
+                self.shift = down;
+            }
+            web_sys::KeyEvent::DOM_VK_CONTROL => {
+                self.control = down;
+            }
+            web_sys::KeyEvent::DOM_VK_ALT => {
+                self.alt = down;
             }
             _ => {}
         }
     }
 }
+
+/// is_scrolling_key returns true when the browser scrolls the page or moves the focus by the key.
+pub(crate) fn is_scrolling_key(event: &web_sys::KeyboardEvent) -> bool {
+    matches!(
+        event.key_code(),
+        web_sys::KeyEvent::DOM_VK_LEFT
+            | web_sys::KeyEvent::DOM_VK_UP
+            | web_sys::KeyEvent::DOM_VK_RIGHT
+            | web_sys::KeyEvent::DOM_VK_DOWN
+            | web_sys::KeyEvent::DOM_VK_SPACE
+            | web_sys::KeyEvent::DOM_VK_TAB
+    )
+}
+
+/// KeyEventSnapshot is the keys which were down when KeyEvent::snapshot was called.
+/// It is Copy and as small as a u64, so keep one every frame to find the keys just pressed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct KeyEventSnapshot {
+    bits: u64,
+}
+
+impl KeyEventSnapshot {
+    /// is_down returns true when key was down at the snapshot.
+    pub fn is_down(&self, key: Key) -> bool {
+        self.bits & key.bit() != 0
+    }
+
+    /// keys_down returns the keys which were down at the snapshot, in the order of the variants
+    /// of Key.
+    pub fn keys_down(&self) -> impl Iterator<Item = Key> + '_ {
+        Key::ALL.into_iter().filter(move |&key| self.is_down(key))
+    }
+}
+
+/// MouseEvent stores the position of the mouse on the canvas and how it moved in the frame.
+/// It is passed to App::on_mouse before every update.
+#[derive(Debug)]
+pub struct MouseEvent {
+    position: Position,
+    delta: Position,
+    scroll_x: f64,
+    scroll_y: f64,
+    moved: bool,
+}
+
+impl MouseEvent {
+    pub(crate) fn new() -> Self {
+        Self {
+            position: Position::new(0.0, 0.0),
+            delta: Position::new(0.0, 0.0),
+            scroll_x: 0.0,
+            scroll_y: 0.0,
+            moved: false,
+        }
+    }
+
+    /// position returns the last position of the mouse in pixels of the canvas.
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    /// delta_position returns how far the mouse moved since the last frame in pixels of the
+    /// canvas, e.g. for mouse look or dragging.
+    pub fn delta_position(&self) -> &Position {
+        &self.delta
+    }
+
+    /// scroll_delta returns how far the wheel scrolled horizontally and vertically since the
+    /// last frame. Positive values scroll right and down.
+    pub fn scroll_delta(&self) -> (f64, f64) {
+        (self.scroll_x, self.scroll_y)
+    }
+
+    pub(crate) fn update_on_mousemove(&mut self, position: Position) {
+        // The first move has no previous position to measure from.
+        if self.moved {
+            self.delta = Position::new(
+                self.delta.dx() + position.dx() - self.position.dx(),
+                self.delta.dy() + position.dy() - self.position.dy(),
+            );
+        }
+        self.position = position;
+        self.moved = true;
+    }
+
+    pub(crate) fn update_on_wheel(&mut self, event: &web_sys::WheelEvent) {
+        self.scroll_x += event.delta_x();
+        self.scroll_y += event.delta_y();
+    }
+
+    // end_frame clears the movement after it is passed to the app.
+    pub(crate) fn end_frame(&mut self) {
+        self.delta = Position::new(0.0, 0.0);
+        self.scroll_x = 0.0;
+        self.scroll_y = 0.0;
+    }
+}
+
+/// InputRecorder records KeyEvents over time so that they can be replayed by InputPlayer.
+/// serialize uses a compact binary format of 16 bytes per frame without any dependency, so that
+/// a long session can be kept in memory and attached to a bug report. With the `serde` feature,
+/// to_json saves the frames with the names of the keys instead, e.g. to edit them by hand.
+#[derive(Debug, Default)]
+pub struct InputRecorder {
+    frames: Vec<(u64, u64)>,
+}
+
+impl InputRecorder {
+    const MAGIC: &'static [u8; 4] = b"RSIR";
+    const VERSION: u8 = 1;
+    const HEADER_LEN: usize = 5;
+    const FRAME_LEN: usize = 16;
+
+    /// new returns an empty InputRecorder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// record pushes a snapshot of key_event at frame.
+    pub fn record(&mut self, key_event: &KeyEvent, frame: u64) {
+        self.frames.push((frame, key_event.to_bits()));
+    }
+
+    /// len returns the number of the recorded frames.
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// is_empty returns true when no frame is recorded.
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// serialize encodes the recorded frames into bytes.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(Self::HEADER_LEN + self.frames.len() * Self::FRAME_LEN);
+        data.extend_from_slice(Self::MAGIC);
+        data.push(Self::VERSION);
+        for (frame, bits) in &self.frames {
+            data.extend_from_slice(&frame.to_le_bytes());
+            data.extend_from_slice(&bits.to_le_bytes());
+        }
+        data
+    }
+
+    /// to_json encodes the recorded frames as JSON, e.g.
+    /// `[{"frame":3,"keys":["ArrowLeft","KeyZ"]}]`.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> Result<String> {
+        let frames: Vec<RecordedFrame> = self
+            .frames
+            .iter()
+            .map(|&(frame, bits)| RecordedFrame {
+                frame,
+                keys: KeyEventSnapshot { bits }.keys_down().collect(),
+            })
+            .collect();
+        Ok(serde_json::to_string(&frames)?)
+    }
+}
+
+// RecordedFrame is a frame of InputRecorder in JSON, with the keys which are down.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct RecordedFrame {
+    frame: u64,
+    keys: Vec<Key>,
+}
+
+/// InputPlayer replays KeyEvents recorded by InputRecorder one frame at a time.
+#[derive(Debug)]
+pub struct InputPlayer {
+    frames: Vec<(u64, u64)>,
+    cursor: usize,
+}
+
+impl InputPlayer {
+    /// deserialize reconstructs the frames from bytes encoded by InputRecorder::serialize.
+    pub fn deserialize(data: &[u8]) -> Result<InputPlayer> {
+        ensure!(
+            data.len() >= InputRecorder::HEADER_LEN
+                && &data[..4] == InputRecorder::MAGIC
+                && data[4] == InputRecorder::VERSION,
+            "data is not recorded by InputRecorder version {}",
+            InputRecorder::VERSION
+        );
+        let body = &data[InputRecorder::HEADER_LEN..];
+        ensure!(
+            body.len() % InputRecorder::FRAME_LEN == 0,
+            "data length: {} is broken",
+            data.len()
+        );
+        let frames = body
+            .chunks(InputRecorder::FRAME_LEN)
+            .map(|chunk| {
+                let mut frame = [0; 8];
+                let mut bits = [0; 8];
+                frame.copy_from_slice(&chunk[..8]);
+                bits.copy_from_slice(&chunk[8..]);
+                (u64::from_le_bytes(frame), u64::from_le_bytes(bits))
+            })
+            .collect();
+        Ok(Self { frames, cursor: 0 })
+    }
+
+    /// from_json reconstructs the frames from JSON encoded by InputRecorder::to_json.
+    #[cfg(feature = "serde")]
+    pub fn from_json(json: &str) -> Result<InputPlayer> {
+        let frames: Vec<RecordedFrame> = serde_json::from_str(json)
+            .map_err(|e| anyhow::anyhow!("invalid input recording: {}", e))?;
+        let frames = frames
+            .into_iter()
+            .map(|recorded| {
+                let bits = recorded.keys.iter().fold(0, |bits, key| bits | key.bit());
+                (recorded.frame, bits)
+            })
+            .collect();
+        Ok(Self { frames, cursor: 0 })
+    }
+
+    /// next_frame returns the KeyEvent of the next recorded frame.
+    /// It returns None when all the frames have been replayed.
+    pub fn next_frame(&mut self) -> Option<KeyEvent> {
+        let (_, bits) = self.frames.get(self.cursor)?;
+        self.cursor += 1;
+        Some(KeyEvent::from_bits(*bits))
+    }
+
+    /// frame returns the frame number of the next recorded frame.
+    pub fn frame(&self) -> Option<u64> {
+        self.frames.get(self.cursor).map(|(frame, _)| *frame)
+    }
+
+    /// rewind restarts replaying from the first frame.
+    pub fn rewind(&mut self) {
+        self.cursor = 0;
+    }
+}
+
+/// Axis treats two opposite keys as a signed value in [-1.0, 1.0].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Axis {
+    positive_key: Key,
+    negative_key: Key,
+}
+
+impl Axis {
+    /// new returns an Axis which is 1.0 by positive_key and -1.0 by negative_key.
+    pub fn new(positive_key: Key, negative_key: Key) -> Self {
+        Self {
+            positive_key,
+            negative_key,
+        }
+    }
+
+    /// horizontal returns an Axis by ArrowRight(1.0) and ArrowLeft(-1.0).
+    pub fn horizontal() -> Self {
+        Self::new(Key::ArrowRight, Key::ArrowLeft)
+    }
+
+    /// vertical returns an Axis by ArrowDown(1.0) and ArrowUp(-1.0),
+    /// which matches the y direction on the canvas.
+    pub fn vertical() -> Self {
+        Self::new(Key::ArrowDown, Key::ArrowUp)
+    }
+
+    /// value returns 1.0, -1.0, or 0.0 when both or neither of the keys are down.
+    pub fn value(&self, key_event: &KeyEvent) -> f64 {
+        let positive = if key_event.is_down(self.positive_key) {
+            1.0
+        } else {
+            0.0
+        };
+        let negative = if key_event.is_down(self.negative_key) {
+            1.0
+        } else {
+            0.0
+        };
+        positive - negative
+    }
+}
+
+/// KeySequenceDetector recognizes keys pressed in order within a time window,
+/// e.g. combo inputs and the Konami code.
+#[derive(Debug, Clone)]
+pub struct KeySequenceDetector {
+    sequence: Vec<Key>,
+    window_ms: f64,
+    buffer: VecDeque<(Key, f64)>,
+    previous: KeyEventSnapshot,
+    time: f64,
+    triggered: bool,
+}
+
+impl KeySequenceDetector {
+    /// new returns a KeySequenceDetector which is triggered when all the keys of sequence are
+    /// pressed in order within window_ms milliseconds.
+    pub fn new(sequence: Vec<Key>, window_ms: f64) -> Self {
+        Self {
+            sequence,
+            window_ms,
+            buffer: VecDeque::new(),
+            previous: KeyEventSnapshot::default(),
+            time: 0.0,
+            triggered: false,
+        }
+    }
+
+    /// update tracks the keys newly pressed since the last update. Call it every frame with
+    /// the time passed since the last frame in milliseconds.
+    pub fn update(&mut self, key_event: &KeyEvent, dt: f64) {
+        self.time += dt;
+        self.triggered = false;
+        for key in key_event.keys_pressed_since(&self.previous) {
+            self.buffer.push_back((key, self.time));
+        }
+        self.previous = key_event.snapshot();
+
+        while self.buffer.len() > self.sequence.len() {
+            self.buffer.pop_front();
+        }
+        while matches!(self.buffer.front(), Some((_, time)) if self.time - time > self.window_ms) {
+            self.buffer.pop_front();
+        }
+        if !self.sequence.is_empty() && self.buffer.iter().map(|(key, _)| key).eq(&self.sequence) {
+            self.triggered = true;
+            self.buffer.clear();
+        }
+    }
+
+    /// is_triggered returns true in the frame when the last key of the sequence is pressed.
+    pub fn is_triggered(&self) -> bool {
+        self.triggered
+    }
+
+    /// reset forgets the keys pressed so far.
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.triggered = false;
+    }
+}
+
+/// ButtonMapping binds named game actions, e.g. "jump" and "attack", to keys.
+/// Query actions by name instead of hardcoding keys, so that players can remap them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ButtonMapping {
+    bindings: HashMap<String, Key>,
+}
+
+impl ButtonMapping {
+    /// new returns a ButtonMapping without bindings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// bind binds the action to the key. It replaces the key which the action is bound to.
+    pub fn bind(&mut self, action: &str, key: Key) {
+        self.bindings.insert(String::from(action), key);
+    }
+
+    /// unbind removes the binding of the action.
+    pub fn unbind(&mut self, action: &str) {
+        self.bindings.remove(action);
+    }
+
+    /// key returns the key which the action is bound to.
+    pub fn key(&self, action: &str) -> Option<Key> {
+        self.bindings.get(action).copied()
+    }
+
+    /// is_down returns true when the key bound to the action is down.
+    /// It returns false for unbound actions.
+    pub fn is_down(&self, action: &str, key_event: &KeyEvent) -> bool {
+        matches!(self.key(action), Some(key) if key_event.is_down(key))
+    }
+
+    /// from_json loads bindings saved by to_json, e.g. `{"bindings":{"jump":"KeyZ"}}`.
+    #[cfg(feature = "serde")]
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| anyhow::anyhow!("invalid button mapping: {}", e))
+    }
+
+    /// to_json saves the bindings as JSON, e.g. to store them in localStorage.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
@@ -0,0 +1,422 @@
+#![cfg(target_arch = "wasm32")]
+//! These tests run in a browser. Run them with `wasm-pack test --headless --chrome`, or with
+//! `--firefox`. The loop is driven by LoopDriver::Manual, so that every frame is run by tick.
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use anyhow::Result;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::*;
+use web_sys::KeyEvent as Dom;
+
+use retrospector::app::{is_supported, run, run_async, App, AppConfig, AppHandle, LoopDriver};
+use retrospector::overlay::Overlay;
+use retrospector::render::{Font, Renderer};
+use retrospector::update::KeyEvent;
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+struct TestMock;
+
+impl App for TestMock {
+    fn update(&mut self, _elapsed_time: f64, _key_event: &KeyEvent) -> Result<()> {
+        Ok(())
+    }
+
+    fn render(&self, _renderer: &Renderer) -> Result<()> {
+        Ok(())
+    }
+}
+
+// Recorder records whether ArrowRight is down in every update, and fills a red square at the
+// top-left corner of the canvas in every render.
+#[derive(Default)]
+struct Recorder {
+    arrow_right: Vec<bool>,
+}
+
+impl App for Recorder {
+    fn update(&mut self, _elapsed_time: f64, key_event: &KeyEvent) -> Result<()> {
+        self.arrow_right.push(key_event.is_arrow_right_down());
+        Ok(())
+    }
+
+    fn render(&self, renderer: &Renderer) -> Result<()> {
+        renderer
+            .context()
+            .set_fill_style(&JsValue::from_str("#ff0000"));
+        renderer.context().fill_rect(0.0, 0.0, 10.0, 10.0);
+        Ok(())
+    }
+}
+
+// create_canvas appends a canvas with id to the body of the test page.
+fn create_canvas(id: &str) -> web_sys::HtmlCanvasElement {
+    let document = web_sys::window().unwrap().document().unwrap();
+    let canvas = document
+        .create_element("canvas")
+        .unwrap()
+        .dyn_into::<web_sys::HtmlCanvasElement>()
+        .unwrap();
+    canvas.set_id(id);
+    document.body().unwrap().append_child(&canvas).unwrap();
+    canvas
+}
+
+// start runs app on a new canvas with id, driven by tick, and waits until it is loaded.
+async fn start<T: App + 'static>(app: T, id: &str) -> (AppHandle<T>, web_sys::HtmlCanvasElement) {
+    let canvas = create_canvas(id);
+    let config = AppConfig::builder(id)
+        .size(40.0, 20.0)
+        .loop_driver(LoopDriver::Manual)
+        .build()
+        .unwrap();
+    let handle = run(app, config).unwrap();
+    while handle.is_loading() {
+        next_task().await;
+    }
+    (handle, canvas)
+}
+
+// next_task waits for a timeout, so that the pending promises and futures are settled.
+async fn next_task() {
+    let promise = js_sys::Promise::new(&mut |resolve, _| {
+        web_sys::window()
+            .unwrap()
+            .set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, 0)
+            .unwrap();
+    });
+    JsFuture::from(promise).await.unwrap();
+}
+
+// run_frames runs n frames at 60 fps after the frame at start_time.
+fn run_frames<T: App>(handle: &AppHandle<T>, start_time: f64, n: u32) {
+    for i in 0..n {
+        handle
+            .tick(start_time + (i + 1) as f64 * 1000.0 / 60.0)
+            .unwrap();
+    }
+}
+
+// dispatch_key dispatches a synthetic KeyboardEvent of event_type, e.g. "keydown", to the page.
+fn dispatch_key(event_type: &str, key_code: u32) {
+    let mut init = web_sys::KeyboardEventInit::new();
+    init.key_code(key_code).bubbles(true);
+    let event =
+        web_sys::KeyboardEvent::new_with_keyboard_event_init_dict(event_type, &init).unwrap();
+    let document = web_sys::window().unwrap().document().unwrap();
+    document.dispatch_event(&event).unwrap();
+}
+
+fn pixel_at(canvas: &web_sys::HtmlCanvasElement, x: f64, y: f64) -> Vec<u8> {
+    let context = canvas
+        .get_context("2d")
+        .unwrap()
+        .unwrap()
+        .dyn_into::<web_sys::CanvasRenderingContext2d>()
+        .unwrap();
+    context
+        .get_image_data(x, y, 1.0, 1.0)
+        .unwrap()
+        .data()
+        .to_vec()
+}
+
+#[wasm_bindgen_test]
+fn run_fails_with_a_missing_canvas_id() {
+    let config = AppConfig::new(String::from("canvs"), 32.0, 32.0);
+    let error = run(TestMock, config).err().unwrap();
+    assert_eq!(
+        error.as_string().unwrap(),
+        "canvas element with id 'canvs' not found"
+    );
+}
+
+#[wasm_bindgen_test]
+fn is_supported_in_browsers() {
+    assert!(is_supported());
+}
+
+#[wasm_bindgen_test]
+async fn tick_runs_exactly_one_frame() {
+    let (handle, _) = start(Recorder::default(), "tick").await;
+    assert_eq!(handle.frame(), 0);
+    run_frames(&handle, 0.0, 3);
+    assert_eq!(handle.frame(), 3);
+    assert_eq!(handle.with_app(|app| app.arrow_right.len()).unwrap(), 3);
+    handle.stop();
+    handle.tick(1000.0).unwrap();
+    assert_eq!(handle.frame(), 3);
+}
+
+#[wasm_bindgen_test]
+async fn tick_is_rejected_while_the_app_is_borrowed() {
+    let (handle, _) = start(Recorder::default(), "tick-in-with-app").await;
+    let result = handle.with_app(|_| handle.tick(1000.0 / 60.0)).unwrap();
+    assert!(result.is_err());
+    assert_eq!(handle.frame(), 0);
+    run_frames(&handle, 0.0, 1);
+    assert_eq!(handle.frame(), 1);
+    handle.stop();
+}
+
+#[wasm_bindgen_test]
+fn tick_is_rejected_with_another_loop_driver() {
+    create_canvas("animation-frame");
+    let config = AppConfig::builder("animation-frame").build().unwrap();
+    let handle = run(TestMock, config).unwrap();
+    assert!(handle.tick(0.0).is_err());
+    handle.stop();
+}
+
+#[wasm_bindgen_test]
+async fn keyboard_events_update_the_key_event() {
+    let (handle, _) = start(Recorder::default(), "keys").await;
+    dispatch_key("keydown", Dom::DOM_VK_RIGHT);
+    run_frames(&handle, 0.0, 1);
+    dispatch_key("keyup", Dom::DOM_VK_RIGHT);
+    run_frames(&handle, 16.0, 1);
+    assert_eq!(
+        handle.with_app(|app| app.arrow_right.clone()).unwrap(),
+        vec![true, false]
+    );
+    handle.stop();
+}
+
+#[wasm_bindgen_test]
+async fn render_draws_on_the_canvas() {
+    let (handle, canvas) = start(Recorder::default(), "render").await;
+    assert_eq!(pixel_at(&canvas, 1.0, 1.0), vec![0, 0, 0, 0]);
+    run_frames(&handle, 0.0, 1);
+    assert_eq!(pixel_at(&canvas, 1.0, 1.0), vec![255, 0, 0, 255]);
+    assert_eq!(pixel_at(&canvas, 20.0, 1.0), vec![0, 0, 0, 0]);
+    handle.stop();
+}
+
+#[wasm_bindgen_test]
+fn run_sizes_the_canvas_and_configures_the_context() {
+    let canvas = create_canvas("sizing");
+    let config = AppConfig::builder("sizing")
+        .size(40.0, 20.0)
+        .pixel_ratio(Some(2.0))
+        .pixel_art(true)
+        .loop_driver(LoopDriver::Manual)
+        .build()
+        .unwrap();
+    let handle = run(TestMock, config).unwrap();
+    assert_eq!((canvas.width(), canvas.height()), (80, 40));
+    let style = canvas.style();
+    assert_eq!(style.get_property_value("width").unwrap(), "40px");
+    assert_eq!(
+        style.get_property_value("image-rendering").unwrap(),
+        "pixelated"
+    );
+    let context = canvas
+        .get_context("2d")
+        .unwrap()
+        .unwrap()
+        .dyn_into::<web_sys::CanvasRenderingContext2d>()
+        .unwrap();
+    assert!(!context.image_smoothing_enabled());
+    handle.stop();
+}
+
+#[wasm_bindgen_test]
+async fn run_async_resolves_after_loading() {
+    create_canvas("async");
+    let config = AppConfig::builder("async")
+        .loop_driver(LoopDriver::Manual)
+        .build()
+        .unwrap();
+    let handle = run_async(Recorder::default(), config).await.unwrap();
+    assert!(!handle.is_loading());
+    run_frames(&handle, 0.0, 1);
+    assert_eq!(handle.frame(), 1);
+    handle.stop();
+}
+
+#[wasm_bindgen_test]
+async fn overlay_lies_on_the_canvas_and_dispatches_clicks_before_the_next_update() {
+    let canvas = create_canvas("overlay");
+    let overlay = Overlay::new();
+    overlay.set_html(r#"<button id="overlay-start">Start</button>"#);
+    let clicks = Rc::new(Cell::new(0));
+    {
+        let clicks = Rc::clone(&clicks);
+        overlay.on_click("overlay-start", move || clicks.set(clicks.get() + 1));
+    }
+    let config = AppConfig::builder("overlay")
+        .size(40.0, 20.0)
+        .loop_driver(LoopDriver::Manual)
+        .overlay(&overlay)
+        .build()
+        .unwrap();
+    let handle = run(TestMock, config).unwrap();
+    while handle.is_loading() {
+        next_task().await;
+    }
+    let document = web_sys::window().unwrap().document().unwrap();
+    let button = document
+        .get_element_by_id("overlay-start")
+        .unwrap()
+        .dyn_into::<web_sys::HtmlElement>()
+        .unwrap();
+    button.click();
+    assert_eq!(clicks.get(), 0);
+    run_frames(&handle, 0.0, 1);
+    assert_eq!(clicks.get(), 1);
+
+    let overlay_rect = button.parent_element().unwrap().get_bounding_client_rect();
+    let canvas_rect = canvas.get_bounding_client_rect();
+    assert_eq!(
+        (
+            overlay_rect.left(),
+            overlay_rect.top(),
+            overlay_rect.width()
+        ),
+        (canvas_rect.left(), canvas_rect.top(), canvas_rect.width())
+    );
+    handle.stop();
+    assert!(document.get_element_by_id("overlay-start").is_none());
+}
+
+// next_animation_frame waits for an animation frame, after which the loop has run a frame.
+async fn next_animation_frame() {
+    let promise = js_sys::Promise::new(&mut |resolve, _| {
+        web_sys::window()
+            .unwrap()
+            .request_animation_frame(&resolve)
+            .unwrap();
+    });
+    JsFuture::from(promise).await.unwrap();
+}
+
+// StopInUpdate stops its own loop in the update of stop_at_frame.
+struct StopInUpdate {
+    handle: Rc<RefCell<Option<AppHandle<StopInUpdate>>>>,
+    stop_at_frame: usize,
+    updates: Rc<Cell<usize>>,
+}
+
+impl App for StopInUpdate {
+    fn update(&mut self, _elapsed_time: f64, _key_event: &KeyEvent) -> Result<()> {
+        self.updates.set(self.updates.get() + 1);
+        if self.updates.get() == self.stop_at_frame {
+            if let Some(handle) = &*self.handle.borrow() {
+                handle.stop();
+            }
+        }
+        Ok(())
+    }
+
+    fn render(&self, _renderer: &Renderer) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[wasm_bindgen_test]
+async fn stop_in_update_ends_the_animation_frame_loop() {
+    create_canvas("stop-in-update");
+    let handle_slot = Rc::new(RefCell::new(None));
+    let updates = Rc::new(Cell::new(0));
+    let app = StopInUpdate {
+        handle: Rc::clone(&handle_slot),
+        stop_at_frame: 2,
+        updates: Rc::clone(&updates),
+    };
+    let config = AppConfig::builder("stop-in-update")
+        .size(40.0, 20.0)
+        .build()
+        .unwrap();
+    let handle = run(app, config).unwrap();
+    handle_slot.replace(Some(handle.clone()));
+    while !handle.is_stopped() {
+        next_animation_frame().await;
+    }
+    for _ in 0..3 {
+        next_animation_frame().await;
+    }
+    assert_eq!(updates.get(), 2);
+    // The app is dropped after stop, which needs the cycle through the handle broken.
+    handle_slot.replace(None);
+}
+
+#[wasm_bindgen_test]
+async fn stop_in_an_overlay_callback_ends_the_animation_frame_loop() {
+    create_canvas("stop-in-overlay");
+    let overlay = Overlay::new();
+    overlay.set_html(r#"<button id="stop-in-overlay-quit">Quit</button>"#);
+    let handle_slot: Rc<RefCell<Option<AppHandle<Recorder>>>> = Rc::new(RefCell::new(None));
+    {
+        let handle_slot = Rc::clone(&handle_slot);
+        overlay.on_click("stop-in-overlay-quit", move || {
+            if let Some(handle) = &*handle_slot.borrow() {
+                handle.stop();
+            }
+        });
+    }
+    let config = AppConfig::builder("stop-in-overlay")
+        .size(40.0, 20.0)
+        .overlay(&overlay)
+        .build()
+        .unwrap();
+    let handle = run(Recorder::default(), config).unwrap();
+    handle_slot.replace(Some(handle.clone()));
+    while handle.is_loading() {
+        next_task().await;
+    }
+    overlay.click("stop-in-overlay-quit");
+    while !handle.is_stopped() {
+        next_animation_frame().await;
+    }
+    let frame = handle.frame();
+    for _ in 0..3 {
+        next_animation_frame().await;
+    }
+    assert_eq!(handle.frame(), frame);
+    // A new loop still runs, which it would not after a panic in the stopped one.
+    let (handle, _) = start(Recorder::default(), "stop-in-overlay-next").await;
+    run_frames(&handle, 0.0, 2);
+    assert_eq!(handle.with_app(|app| app.arrow_right.len()).unwrap(), 2);
+    handle.stop();
+}
+
+// FontAfterRestore sets a font inside a save and restore pair, sets it again after the restore,
+// and records the font of the context then.
+#[derive(Default)]
+struct FontAfterRestore {
+    fonts: RefCell<Vec<String>>,
+}
+
+impl App for FontAfterRestore {
+    fn update(&mut self, _elapsed_time: f64, _key_event: &KeyEvent) -> Result<()> {
+        Ok(())
+    }
+
+    fn render(&self, renderer: &Renderer) -> Result<()> {
+        let font = Font::new(23, "monospace");
+        renderer.context().save();
+        renderer.set_font(&font);
+        renderer.context().restore();
+        assert_eq!(renderer.font(), None, "restore dropped the font");
+        renderer.set_font(&font);
+        self.fonts.borrow_mut().push(renderer.context().font());
+        Ok(())
+    }
+}
+
+#[wasm_bindgen_test]
+async fn set_font_applies_the_font_again_after_restore() {
+    let (handle, _) = start(FontAfterRestore::default(), "font-after-restore").await;
+    run_frames(&handle, 0.0, 2);
+    let fonts = handle.with_app(|app| app.fonts.take()).unwrap();
+    assert_eq!(fonts.len(), 2);
+    assert!(
+        fonts.iter().all(|font| font.contains("23px")),
+        "{:?}",
+        fonts
+    );
+    handle.stop();
+}
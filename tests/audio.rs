@@ -0,0 +1,39 @@
+use retrospector::audio::{Tone, Waveform};
+
+fn peak(samples: &[f32]) -> f32 {
+    samples
+        .iter()
+        .fold(0.0, |peak, sample| peak.max(sample.abs()))
+}
+
+#[test]
+fn render_returns_samples_for_the_duration() {
+    let tone = Tone::new(Waveform::Square, 440.0, 250.0);
+    assert_eq!(tone.render(44100.0).len(), 11025);
+    assert_eq!(tone.render(8000.0).len(), 2000);
+    assert!(Tone::new(Waveform::Sine, 440.0, 0.0)
+        .render(44100.0)
+        .is_empty());
+}
+
+#[test]
+fn envelope_fades_in_and_out() {
+    let samples = Tone::new(Waveform::Square, 440.0, 100.0)
+        .envelope(20.0, 20.0)
+        .volume(1.0)
+        .render(1000.0);
+    assert_eq!(samples[0], 0.0);
+    assert!(peak(&samples[..5]) < 0.3);
+    assert!((peak(&samples[40..60]) - 1.0).abs() < f32::EPSILON);
+    assert!(peak(&samples[95..]) < 0.3);
+}
+
+#[test]
+fn presets_are_audible_and_within_range() {
+    for tone in [Tone::jump(), Tone::hit(), Tone::pickup(), Tone::explosion()] {
+        let samples = tone.render(22050.0);
+        assert!(!samples.is_empty());
+        let peak = peak(&samples);
+        assert!(peak > 0.1 && peak <= 1.0, "peak: {}", peak);
+    }
+}
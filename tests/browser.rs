@@ -0,0 +1,126 @@
+#![cfg(target_arch = "wasm32")]
+//! These tests run in a browser. Run them with `wasm-pack test --headless --chrome`.
+
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use wasm_bindgen_test::*;
+
+use retrospector::browser::{set_favicon_from_sprite, set_title};
+use retrospector::config::{get_hash, set_hash};
+use retrospector::render::{Sprite, SpriteStore};
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+fn document() -> web_sys::Document {
+    web_sys::window().unwrap().document().unwrap()
+}
+
+// red_sprite returns a loaded 4x4 sprite of a 8x4 atlas, whose left half is red.
+async fn red_sprite() -> Sprite {
+    let canvas = document()
+        .create_element("canvas")
+        .unwrap()
+        .dyn_into::<web_sys::HtmlCanvasElement>()
+        .unwrap();
+    canvas.set_width(8);
+    canvas.set_height(4);
+    let context = canvas
+        .get_context("2d")
+        .unwrap()
+        .unwrap()
+        .dyn_into::<web_sys::CanvasRenderingContext2d>()
+        .unwrap();
+    context.set_fill_style(&JsValue::from_str("#ff0000"));
+    context.fill_rect(0.0, 0.0, 4.0, 4.0);
+    let image = web_sys::HtmlImageElement::new().unwrap();
+    image.set_src(&canvas.to_data_url().unwrap());
+    JsFuture::from(image.decode()).await.unwrap();
+    Sprite::from_image_element_region(image, 0.0, 0.0, 4.0, 4.0)
+}
+
+// magenta_and_red_png returns a 2x1 PNG whose left pixel is magenta and right pixel is red.
+fn magenta_and_red_png() -> Vec<u8> {
+    let canvas = document()
+        .create_element("canvas")
+        .unwrap()
+        .dyn_into::<web_sys::HtmlCanvasElement>()
+        .unwrap();
+    canvas.set_width(2);
+    canvas.set_height(1);
+    let context = canvas
+        .get_context("2d")
+        .unwrap()
+        .unwrap()
+        .dyn_into::<web_sys::CanvasRenderingContext2d>()
+        .unwrap();
+    context.set_fill_style(&JsValue::from_str("#ff00ff"));
+    context.fill_rect(0.0, 0.0, 1.0, 1.0);
+    context.set_fill_style(&JsValue::from_str("#ff0000"));
+    context.fill_rect(1.0, 0.0, 1.0, 1.0);
+    let url = canvas.to_data_url().unwrap();
+    base64::decode(url.trim_start_matches("data:image/png;base64,")).unwrap()
+}
+
+fn icon_hrefs() -> Vec<String> {
+    let links = document().query_selector_all("link[rel~='icon']").unwrap();
+    (0..links.length())
+        .map(|i| {
+            links
+                .item(i)
+                .unwrap()
+                .dyn_into::<web_sys::Element>()
+                .unwrap()
+                .get_attribute("href")
+                .unwrap()
+        })
+        .collect()
+}
+
+#[wasm_bindgen_test]
+fn set_title_sets_the_title_of_the_page() {
+    set_title("(3 lives) My Game").unwrap();
+    assert_eq!(document().title(), "(3 lives) My Game");
+}
+
+#[wasm_bindgen_test]
+async fn set_favicon_from_sprite_creates_and_then_updates_a_single_icon_link() {
+    let sprite = red_sprite().await;
+    set_favicon_from_sprite(&sprite).unwrap();
+    let hrefs = icon_hrefs();
+    assert_eq!(hrefs.len(), 1);
+    assert!(hrefs[0].starts_with("data:image/png"));
+
+    set_favicon_from_sprite(&sprite).unwrap();
+    assert_eq!(icon_hrefs(), hrefs);
+}
+
+#[wasm_bindgen_test]
+fn get_hash_returns_the_hash_set_by_set_hash() {
+    for hash in ["level=3+4/5", "a+b=c%d", "100%25 done", "jürgen"] {
+        set_hash(hash).unwrap();
+        assert_eq!(get_hash(), hash);
+    }
+    set_hash("").unwrap();
+}
+
+#[wasm_bindgen_test]
+async fn sprite_store_keys_the_atlas_once_for_every_on_load() {
+    let store = SpriteStore::new(&magenta_and_red_png(), "png", 2, 1, 1, 1)
+        .unwrap()
+        .with_color_key(0xff, 0x00, 0xff);
+    let first = store.on_load();
+    let second = store.on_load();
+    assert!(js_sys::Object::is(&first, &second));
+    JsFuture::from(first).await.unwrap();
+    JsFuture::from(store.on_load()).await.unwrap();
+
+    let canvas = document()
+        .create_element("canvas")
+        .unwrap()
+        .dyn_into::<web_sys::HtmlCanvasElement>()
+        .unwrap();
+    let keyed = store.sprite(0).unwrap().pixel_at(0, 0, &canvas).unwrap();
+    let kept = store.sprite(1).unwrap().pixel_at(0, 0, &canvas).unwrap();
+    assert_eq!(keyed[3], 0);
+    assert_eq!(kept, [0xff, 0x00, 0x00, 0xff]);
+}
@@ -0,0 +1,136 @@
+use anyhow::Result;
+
+use retrospector::collision::{
+    circle_circle, point_in_circle, point_in_rect, rect_circle, rect_rect, segment_rect,
+    swept_rect_rect, Circle, Rect,
+};
+use retrospector::render::Position;
+
+fn rect(x: f64, y: f64, width: f64, height: f64) -> Rect {
+    Rect::new(x, y, width, height).unwrap()
+}
+
+fn circle(x: f64, y: f64, radius: f64) -> Circle {
+    Circle::new(x, y, radius).unwrap()
+}
+
+#[test]
+fn new_rejects_negative_and_non_finite_sizes() {
+    assert!(Rect::new(0.0, 0.0, -1.0, 1.0).is_err());
+    assert!(Rect::new(0.0, 0.0, 1.0, f64::NAN).is_err());
+    assert!(Rect::new(f64::INFINITY, 0.0, 1.0, 1.0).is_err());
+    assert!(Circle::new(0.0, 0.0, -0.5).is_err());
+    assert!(Rect::new(0.0, 0.0, 0.0, 0.0).is_ok());
+    assert!(Circle::new(0.0, 0.0, 0.0).is_ok());
+}
+
+#[test]
+fn rect_rect_overlaps_but_not_on_touching_edges() {
+    let a = rect(0.0, 0.0, 10.0, 10.0);
+    assert!(rect_rect(&a, &rect(5.0, 5.0, 10.0, 10.0)));
+    assert!(rect_rect(&a, &rect(2.0, 2.0, 2.0, 2.0)));
+    assert!(rect_rect(&rect(2.0, 2.0, 2.0, 2.0), &a));
+    assert!(!rect_rect(&a, &rect(10.0, 0.0, 10.0, 10.0)));
+    assert!(!rect_rect(&a, &rect(0.0, 10.0, 10.0, 10.0)));
+    assert!(!rect_rect(&a, &rect(20.0, 20.0, 1.0, 1.0)));
+    assert!(!rect_rect(&a, &rect(5.0, 5.0, 0.0, 0.0)));
+}
+
+#[test]
+fn circle_circle_overlaps_but_not_on_touching_edges() {
+    let a = circle(0.0, 0.0, 5.0);
+    assert!(circle_circle(&a, &circle(6.0, 0.0, 2.0)));
+    assert!(circle_circle(&a, &circle(1.0, 1.0, 1.0)));
+    assert!(!circle_circle(&a, &circle(7.0, 0.0, 2.0)));
+    assert!(!circle_circle(&a, &circle(3.0, 4.0, 0.0)));
+}
+
+#[test]
+fn rect_circle_uses_the_closest_point() {
+    let r = rect(0.0, 0.0, 10.0, 10.0);
+    assert!(rect_circle(&r, &circle(5.0, 5.0, 1.0)));
+    assert!(rect_circle(&r, &circle(12.0, 5.0, 3.0)));
+    assert!(!rect_circle(&r, &circle(12.0, 5.0, 2.0)));
+    // The corner is farther than the sides.
+    assert!(!rect_circle(&r, &circle(12.0, 12.0, 2.5)));
+    assert!(rect_circle(&r, &circle(12.0, 12.0, 3.0)));
+    assert!(!rect_circle(
+        &rect(0.0, 0.0, 0.0, 10.0),
+        &circle(0.0, 5.0, 3.0)
+    ));
+}
+
+#[test]
+fn point_in_rect_includes_only_the_top_left_edges() {
+    let r = rect(0.0, 0.0, 10.0, 10.0);
+    assert!(point_in_rect(&Position::new(0.0, 0.0), &r));
+    assert!(point_in_rect(&Position::new(9.9, 5.0), &r));
+    assert!(!point_in_rect(&Position::new(10.0, 5.0), &r));
+    assert!(!point_in_rect(&Position::new(5.0, 10.0), &r));
+    assert!(!point_in_rect(
+        &Position::new(0.0, 0.0),
+        &rect(0.0, 0.0, 0.0, 0.0)
+    ));
+}
+
+#[test]
+fn point_in_circle_excludes_the_edge() {
+    let c = circle(0.0, 0.0, 5.0);
+    assert!(point_in_circle(&Position::new(3.0, 3.0), &c));
+    assert!(!point_in_circle(&Position::new(3.0, 4.0), &c));
+}
+
+#[test]
+fn segment_rect_returns_the_entry_time() {
+    let r = rect(10.0, 0.0, 10.0, 10.0);
+    let start = Position::new(0.0, 5.0);
+    assert_eq!(
+        segment_rect(&start, &Position::new(20.0, 5.0), &r),
+        Some(0.5)
+    );
+    assert_eq!(segment_rect(&start, &Position::new(5.0, 5.0), &r), None);
+    assert_eq!(segment_rect(&start, &Position::new(0.0, 20.0), &r), None);
+    assert_eq!(
+        segment_rect(&Position::new(15.0, 5.0), &Position::new(30.0, 5.0), &r),
+        Some(0.0)
+    );
+    assert_eq!(
+        segment_rect(&Position::new(0.0, 0.0), &Position::new(10.0, 0.0), &r),
+        Some(1.0)
+    );
+}
+
+#[test]
+fn swept_rect_rect_stops_fast_objects_at_thin_walls() -> Result<()> {
+    let moving = Rect::new(0.0, 0.0, 4.0, 4.0)?;
+    let wall = Rect::new(20.0, -10.0, 1.0, 30.0)?;
+    let hit = swept_rect_rect(&moving, (100.0, 0.0), &wall).expect("the wall is hit");
+    assert_eq!(hit.time(), 0.16);
+    assert_eq!(hit.normal(), (-1.0, 0.0));
+
+    let floor = Rect::new(-10.0, 10.0, 30.0, 0.0)?;
+    let hit = swept_rect_rect(&moving, (0.0, 12.0), &floor).expect("the floor is hit");
+    assert_eq!(hit.time(), 0.5);
+    assert_eq!(hit.normal(), (0.0, -1.0));
+
+    assert!(swept_rect_rect(&moving, (10.0, 0.0), &wall).is_none());
+    assert!(swept_rect_rect(&moving, (-100.0, 0.0), &wall).is_none());
+    Ok(())
+}
+
+#[test]
+fn swept_rect_rect_handles_touching_and_overlapping_starts() -> Result<()> {
+    let moving = Rect::new(0.0, 0.0, 10.0, 10.0)?;
+    let touching = Rect::new(10.0, 0.0, 10.0, 10.0)?;
+    let hit = swept_rect_rect(&moving, (5.0, 0.0), &touching).expect("moving into it hits");
+    assert_eq!(hit.time(), 0.0);
+    assert_eq!(hit.normal(), (-1.0, 0.0));
+    assert!(swept_rect_rect(&moving, (-5.0, 0.0), &touching).is_none());
+    assert!(swept_rect_rect(&moving, (0.0, 5.0), &touching).is_none());
+
+    let overlapping = Rect::new(5.0, 5.0, 10.0, 10.0)?;
+    let hit = swept_rect_rect(&moving, (1.0, 0.0), &overlapping).expect("it already overlaps");
+    assert_eq!(hit.time(), 0.0);
+    assert_eq!(hit.normal(), (0.0, 0.0));
+    Ok(())
+}
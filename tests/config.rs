@@ -0,0 +1,33 @@
+use retrospector::config::parse_query;
+
+#[test]
+fn parse_query_percent_decodes_names_and_values() {
+    let params = parse_query("?level=7&name=J%C3%BCrgen+K&a%26b=%3D");
+    assert_eq!(params.len(), 3);
+    assert_eq!(params["level"], "7");
+    assert_eq!(params["name"], "Jürgen K");
+    assert_eq!(params["a&b"], "=");
+}
+
+#[test]
+fn parse_query_decodes_only_literal_pluses_as_spaces() {
+    let params = parse_query("sum=1+2%2B3");
+    assert_eq!(params["sum"], "1 2+3");
+}
+
+#[test]
+fn parse_query_keeps_invalid_escapes() {
+    let params = parse_query("discount=100%&code=%zz%4");
+    assert_eq!(params["discount"], "100%");
+    assert_eq!(params["code"], "%zz%4");
+}
+
+#[test]
+fn parse_query_handles_empty_and_missing_values() {
+    assert!(parse_query("").is_empty());
+    assert!(parse_query("?").is_empty());
+    let params = parse_query("debug&level=&&=orphan");
+    assert_eq!(params.len(), 2);
+    assert_eq!(params["debug"], "");
+    assert_eq!(params["level"], "");
+}
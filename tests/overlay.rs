@@ -0,0 +1,82 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use anyhow::Result;
+
+use retrospector::app::App;
+use retrospector::overlay::Overlay;
+use retrospector::render::Renderer;
+use retrospector::test::TestHarness;
+use retrospector::update::KeyEvent;
+
+// Menu records the menu items chosen before each update.
+struct Menu {
+    chosen: Rc<RefCell<Vec<&'static str>>>,
+    seen: Vec<Vec<&'static str>>,
+}
+
+impl App for Menu {
+    fn update(&mut self, _elapsed_time: f64, _key_event: &KeyEvent) -> Result<()> {
+        self.seen.push(self.chosen.take());
+        Ok(())
+    }
+
+    fn render(&self, _renderer: &Renderer) -> Result<()> {
+        Ok(())
+    }
+}
+
+fn menu(overlay: &Overlay) -> TestHarness<Menu> {
+    let chosen = Rc::new(RefCell::new(vec![]));
+    for id in ["start", "quit"] {
+        let chosen = Rc::clone(&chosen);
+        overlay.on_click(id, move || chosen.borrow_mut().push(id));
+    }
+    let app = Menu {
+        chosen,
+        seen: vec![],
+    };
+    TestHarness::new(app, 100.0, 100.0).overlay(overlay)
+}
+
+#[test]
+fn clicks_are_dispatched_before_the_next_update_in_order() -> Result<()> {
+    let overlay = Overlay::new();
+    overlay.set_html(r#"<button id="start">Start</button><button id="quit">Quit</button>"#);
+    let mut harness = menu(&overlay);
+    overlay.click("quit");
+    overlay.click("start");
+    harness.run_frames(2)?;
+    overlay.click("start");
+    harness.run_frames(1)?;
+    assert_eq!(
+        harness.app().seen,
+        vec![vec!["quit", "start"], vec![], vec!["start"]]
+    );
+    Ok(())
+}
+
+#[test]
+fn clicks_without_a_callback_are_ignored() -> Result<()> {
+    let overlay = Overlay::new();
+    let mut harness = menu(&overlay);
+    overlay.remove_on_click("quit");
+    overlay.click("quit");
+    overlay.click("title");
+    harness.run_frames(1)?;
+    assert_eq!(harness.app().seen, vec![Vec::<&str>::new()]);
+    Ok(())
+}
+
+#[test]
+fn overlay_keeps_its_state_without_a_canvas() {
+    let overlay = Overlay::new();
+    assert!(overlay.is_visible());
+    overlay.set_html("<p>Paused</p>");
+    overlay.hide();
+    let cloned = overlay.clone();
+    assert!(!cloned.is_visible());
+    assert_eq!(cloned.html(), "<p>Paused</p>");
+    cloned.show();
+    assert!(overlay.is_visible());
+}
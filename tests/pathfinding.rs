@@ -0,0 +1,185 @@
+use retrospector::pathfinding::PathFinder;
+use retrospector::render::Grid;
+
+// grid parses a map, where '#' is a wall and any other char is walkable.
+fn grid(map: &[&str]) -> Grid<bool> {
+    let mut grid = Grid::new(map[0].len(), map.len(), true);
+    for (row, line) in map.iter().enumerate() {
+        for (col, c) in line.chars().enumerate() {
+            grid.set(col, row, c != '#').unwrap();
+        }
+    }
+    grid
+}
+
+fn assert_connected(path: &[(usize, usize)], diagonal: bool) {
+    for step in path.windows(2) {
+        let dc = step[0].0.abs_diff(step[1].0);
+        let dr = step[0].1.abs_diff(step[1].1);
+        assert!(dc <= 1 && dr <= 1 && dc + dr > 0, "{:?}", step);
+        assert!(diagonal || dc + dr == 1, "{:?}", step);
+    }
+}
+
+#[test]
+fn find_returns_the_start_when_it_is_the_goal() {
+    let walkable = grid(&["..", ".."]);
+    let mut finder = PathFinder::new(2, 2);
+    assert_eq!(
+        finder.find_in_grid(&walkable, (1, 1), (1, 1)),
+        Some(vec![(1, 1)])
+    );
+}
+
+#[test]
+fn find_returns_a_straight_path_without_walls() {
+    let walkable = grid(&["....."]);
+    let mut finder = PathFinder::new(5, 1);
+    assert_eq!(
+        finder.find_in_grid(&walkable, (0, 0), (4, 0)),
+        Some(vec![(0, 0), (1, 0), (2, 0), (3, 0), (4, 0)])
+    );
+}
+
+#[test]
+fn find_returns_none_when_the_goal_is_walled_off_or_blocked() {
+    let walkable = grid(&[
+        "..#..", //
+        "..#..", //
+        "..#.#",
+    ]);
+    let mut finder = PathFinder::new(5, 3);
+    assert_eq!(finder.find_in_grid(&walkable, (0, 0), (4, 0)), None);
+    assert_eq!(finder.find_in_grid(&walkable, (3, 0), (4, 2)), None);
+    assert_eq!(finder.find_in_grid(&walkable, (0, 0), (5, 0)), None);
+    assert_eq!(
+        finder.find_in_grid(&grid(&["...."]), (0, 0), (1, 0)),
+        None,
+        "a grid of another size should be rejected"
+    );
+}
+
+#[test]
+fn find_goes_around_walls_by_the_shortest_path() {
+    let walkable = grid(&[
+        ".....", //
+        ".###.", //
+        "...#.",
+    ]);
+    let mut finder = PathFinder::new(5, 3);
+    let path = finder.find_in_grid(&walkable, (0, 2), (4, 2)).unwrap();
+    assert_connected(&path, false);
+    assert_eq!(path.first(), Some(&(0, 2)));
+    assert_eq!(path.last(), Some(&(4, 2)));
+    assert_eq!(path.len(), 9);
+    assert!(path
+        .iter()
+        .all(|&(col, row)| walkable.get(col, row) == Some(&true)));
+}
+
+#[test]
+fn find_moves_diagonally_only_when_allowed() {
+    let walkable = grid(&["...", "...", "..."]);
+    let mut straight = PathFinder::new(3, 3);
+    assert_eq!(
+        straight
+            .find_in_grid(&walkable, (0, 0), (2, 2))
+            .unwrap()
+            .len(),
+        5
+    );
+    let mut diagonal = PathFinder::new(3, 3).diagonal(true);
+    assert_eq!(
+        diagonal.find_in_grid(&walkable, (0, 0), (2, 2)),
+        Some(vec![(0, 0), (1, 1), (2, 2)])
+    );
+}
+
+#[test]
+fn find_does_not_cut_corners_by_default() {
+    let walkable = grid(&[
+        ".#", //
+        "..",
+    ]);
+    let mut finder = PathFinder::new(2, 2).diagonal(true);
+    assert_eq!(
+        finder.find_in_grid(&walkable, (0, 0), (1, 1)),
+        Some(vec![(0, 0), (0, 1), (1, 1)])
+    );
+    let mut cutting = PathFinder::new(2, 2).diagonal(true).corner_cutting(true);
+    assert_eq!(
+        cutting.find_in_grid(&walkable, (0, 0), (1, 1)),
+        Some(vec![(0, 0), (1, 1)])
+    );
+    let squeezed = grid(&[
+        ".#", //
+        "#.",
+    ]);
+    assert_eq!(finder.find_in_grid(&squeezed, (0, 0), (1, 1)), None);
+}
+
+#[test]
+fn find_avoids_expensive_cells() {
+    // The middle row is mud, which costs 10 to enter.
+    let mut finder = PathFinder::new(3, 3);
+    let path = finder
+        .find((0, 1), (2, 1), |col, row| {
+            Some(if row == 1 && col == 1 { 10.0 } else { 1.0 })
+        })
+        .unwrap();
+    assert_connected(&path, false);
+    assert!(!path.contains(&(1, 1)));
+    assert_eq!(path.len(), 5);
+}
+
+#[test]
+fn find_can_be_reused_for_many_searches() {
+    let walkable = grid(&[
+        "....", //
+        ".##.", //
+        "....",
+    ]);
+    let mut finder = PathFinder::new(4, 3);
+    for _ in 0..3 {
+        assert_eq!(
+            finder
+                .find_in_grid(&walkable, (0, 0), (3, 2))
+                .unwrap()
+                .len(),
+            6
+        );
+        assert_eq!(
+            finder
+                .find_in_grid(&walkable, (3, 2), (0, 0))
+                .unwrap()
+                .len(),
+            6
+        );
+    }
+}
+
+#[test]
+fn find_solves_a_large_serpentine_maze() {
+    // The walls fill every other column but leave a gap alternately at the bottom and the top,
+    // so the path visits almost every cell.
+    let size = 200;
+    let mut walkable = Grid::new(size, size, true);
+    for col in (1..size).step_by(2) {
+        let gap = if col % 4 == 1 { size - 1 } else { 0 };
+        for row in 0..size {
+            if row != gap {
+                walkable.set(col, row, false).unwrap();
+            }
+        }
+    }
+    // The last open column is climbed upwards, so the maze ends at its top.
+    let goal = (size - 2, 0);
+    let mut finder = PathFinder::new(size, size);
+    let path = finder.find_in_grid(&walkable, (0, 0), goal).unwrap();
+    assert_connected(&path, false);
+    assert_eq!(path.len(), size / 2 * size + size / 2 - 1);
+
+    let mut diagonal = PathFinder::new(size, size).diagonal(true);
+    let path = diagonal.find_in_grid(&walkable, (0, 0), goal).unwrap();
+    assert_connected(&path, true);
+}
@@ -0,0 +1,67 @@
+use retrospector::random::Rng;
+
+#[test]
+fn seeded_generates_the_regression_vector() {
+    let mut rng = Rng::seeded(42);
+    assert_eq!(rng.next_u64(), 0x1578_0b2e_0c2e_c716);
+    assert_eq!(rng.next_u64(), 0x6104_d986_6d11_3a7e);
+    assert_eq!(rng.next_u64(), 0xae17_5332_39e4_99a1);
+}
+
+#[test]
+fn the_same_seed_generates_the_same_numbers() {
+    let mut a = Rng::seeded(7);
+    let mut b = Rng::seeded(7);
+    let mut c = Rng::seeded(8);
+    let a: Vec<u64> = (0..8).map(|_| a.next_u64()).collect();
+    let b: Vec<u64> = (0..8).map(|_| b.next_u64()).collect();
+    let c: Vec<u64> = (0..8).map(|_| c.next_u64()).collect();
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+}
+
+#[test]
+fn next_f64_is_uniform_in_the_unit_interval() {
+    let mut rng = Rng::seeded(1);
+    let samples: Vec<f64> = (0..10_000).map(|_| rng.next_f64()).collect();
+    assert!(samples.iter().all(|x| (0.0..1.0).contains(x)));
+    let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+    assert!((mean - 0.5).abs() < 0.02, "mean: {}", mean);
+}
+
+#[test]
+fn range_i32_covers_the_range_evenly() {
+    let mut rng = Rng::seeded(2);
+    let mut counts = [0; 6];
+    for _ in 0..6_000 {
+        let face = rng.range_i32(1..7);
+        assert!((1..7).contains(&face));
+        counts[(face - 1) as usize] += 1;
+    }
+    assert!(
+        counts.iter().all(|&count| (850..1150).contains(&count)),
+        "{:?}",
+        counts
+    );
+    assert_eq!(rng.range_i32(5..5), 5);
+    assert!((i32::MIN..i32::MAX).contains(&rng.range_i32(i32::MIN..i32::MAX)));
+}
+
+#[test]
+fn chance_pick_and_shuffle() {
+    let mut rng = Rng::seeded(3);
+    let hits = (0..10_000).filter(|_| rng.chance(0.25)).count();
+    assert!((2_300..2_700).contains(&hits), "hits: {}", hits);
+    assert!(!rng.chance(0.0));
+    assert!(rng.chance(1.0));
+
+    let empty: [u8; 0] = [];
+    assert_eq!(rng.pick(&empty), None);
+    assert!([1, 2, 3].contains(rng.pick(&[1, 2, 3]).unwrap()));
+
+    let mut items: Vec<u32> = (0..50).collect();
+    rng.shuffle(&mut items);
+    assert_ne!(items, (0..50).collect::<Vec<u32>>());
+    items.sort_unstable();
+    assert_eq!(items, (0..50).collect::<Vec<u32>>());
+}
@@ -0,0 +1,202 @@
+use retrospector::recording::encode_zip;
+
+fn u16_at(bytes: &[u8], offset: usize) -> usize {
+    u16::from_le_bytes([bytes[offset], bytes[offset + 1]]) as usize
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+#[test]
+fn encode_zip_stores_the_files_with_their_crcs_and_a_central_directory() {
+    let files = vec![
+        (String::from("frame_0000.png"), b"hello".to_vec()),
+        (String::from("frame_0001.png"), vec![]),
+    ];
+    let zip = encode_zip(&files);
+
+    // The local file headers are followed by the names and the stored bytes.
+    assert_eq!(u32_at(&zip, 0), 0x0403_4b50);
+    assert_eq!(u16_at(&zip, 8), 0, "the files should be stored");
+    assert_eq!(u32_at(&zip, 14), 0x3610_a686, "the CRC-32 of hello");
+    assert_eq!(u32_at(&zip, 18), 5);
+    assert_eq!(&zip[30..44], b"frame_0000.png");
+    assert_eq!(&zip[44..49], b"hello");
+    assert_eq!(u32_at(&zip, 49), 0x0403_4b50);
+    assert_eq!(u32_at(&zip, 49 + 14), 0, "the CRC-32 of nothing");
+
+    let end = zip.len() - 22;
+    assert_eq!(u32_at(&zip, end), 0x0605_4b50);
+    assert_eq!(u16_at(&zip, end + 10), 2);
+    let directory = u32_at(&zip, end + 16) as usize;
+    assert_eq!(directory, 49 + 30 + 14);
+    assert_eq!(u32_at(&zip, directory), 0x0201_4b50);
+    assert_eq!(
+        u32_at(&zip, directory + 42),
+        0,
+        "the offset of the first file"
+    );
+    assert_eq!(&zip[directory + 46..directory + 60], b"frame_0000.png");
+}
+
+#[cfg(feature = "gif")]
+mod gif {
+    use retrospector::recording::encode_gif;
+
+    use super::u16_at;
+
+    // Frame is a decoded frame: the delay in centiseconds and the RGB of each pixel.
+    type Frame = (usize, Vec<[u8; 3]>);
+
+    // decode decodes a GIF written by encode_gif, which has a global color table of 256 colors.
+    fn decode(gif: &[u8]) -> (usize, usize, Vec<Frame>) {
+        assert_eq!(&gif[..6], b"GIF89a");
+        let (width, height) = (u16_at(gif, 6), u16_at(gif, 8));
+        assert_eq!(gif[10], 0xf7);
+        let palette = &gif[13..13 + 256 * 3];
+        let mut at = 13 + 256 * 3;
+        let mut frames = vec![];
+        let mut delay = 0;
+        loop {
+            match gif[at] {
+                0x21 => {
+                    if gif[at + 1] == 0xf9 {
+                        delay = u16_at(gif, at + 4);
+                    }
+                    at += 2;
+                    while gif[at] != 0 {
+                        at += gif[at] as usize + 1;
+                    }
+                    at += 1;
+                }
+                0x2c => {
+                    assert_eq!((u16_at(gif, at + 5), u16_at(gif, at + 7)), (width, height));
+                    let min_code_size = gif[at + 10] as u32;
+                    at += 11;
+                    let mut data = vec![];
+                    while gif[at] != 0 {
+                        let len = gif[at] as usize;
+                        data.extend_from_slice(&gif[at + 1..at + 1 + len]);
+                        at += len + 1;
+                    }
+                    at += 1;
+                    let pixels = lzw_decode(&data, min_code_size)
+                        .into_iter()
+                        .map(|index| {
+                            let i = index as usize * 3;
+                            [palette[i], palette[i + 1], palette[i + 2]]
+                        })
+                        .collect();
+                    frames.push((delay, pixels));
+                }
+                0x3b => return (width, height, frames),
+                byte => panic!("unexpected block {:#x} at {}", byte, at),
+            }
+        }
+    }
+
+    fn lzw_decode(data: &[u8], min_code_size: u32) -> Vec<u8> {
+        let clear = 1usize << min_code_size;
+        let end = clear + 1;
+        let mut table: Vec<Vec<u8>> = vec![];
+        let mut code_size = min_code_size + 1;
+        let mut previous: Option<usize> = None;
+        let mut out = vec![];
+        let (mut buffer, mut bits, mut bytes) = (0u32, 0u32, data.iter());
+        loop {
+            while bits < code_size {
+                buffer |= (*bytes.next().expect("no end code") as u32) << bits;
+                bits += 8;
+            }
+            let code = (buffer & ((1 << code_size) - 1)) as usize;
+            buffer >>= code_size;
+            bits -= code_size;
+            if code == clear {
+                table = (0..clear).map(|i| vec![i as u8]).collect();
+                table.push(vec![]);
+                table.push(vec![]);
+                code_size = min_code_size + 1;
+                previous = None;
+                continue;
+            }
+            if code == end {
+                return out;
+            }
+            let entry = match (code < table.len(), previous) {
+                (true, _) => table[code].clone(),
+                (false, Some(previous)) => {
+                    let mut entry = table[previous].clone();
+                    entry.push(table[previous][0]);
+                    entry
+                }
+                (false, None) => panic!("unknown code {}", code),
+            };
+            if let Some(previous) = previous {
+                if table.len() < 4096 {
+                    let mut added = table[previous].clone();
+                    added.push(entry[0]);
+                    table.push(added);
+                    if table.len() == 1 << code_size && code_size < 12 {
+                        code_size += 1;
+                    }
+                }
+            }
+            out.extend_from_slice(&entry);
+            previous = Some(code);
+        }
+    }
+
+    #[test]
+    fn encode_gif_round_trips_the_palette_colors_and_the_delays() {
+        let (width, height) = (3, 2);
+        let red = [255, 0, 0, 255];
+        let white = [255, 255, 255, 255];
+        let green = [0, 255, 0, 255];
+        let first: Vec<u8> = [red, white, green, green, white, red].concat();
+        let second: Vec<u8> = [white; 6].concat();
+        // 16.7 ms is 1.67 centiseconds, so the rounding error is carried over.
+        let frames = vec![(first, 16.7), (second.clone(), 16.7), (second, 16.7)];
+        let gif = encode_gif(width, height, &frames).unwrap();
+        let (decoded_width, decoded_height, decoded) = decode(&gif);
+        assert_eq!((decoded_width, decoded_height), (3, 2));
+        let delays: Vec<usize> = decoded.iter().map(|(delay, _)| *delay).collect();
+        assert_eq!(delays, vec![2, 1, 2]);
+        let (r, w, g) = ([255, 0, 0], [255, 255, 255], [0, 255, 0]);
+        assert_eq!(decoded[0].1, vec![r, w, g, g, w, r]);
+        assert_eq!(decoded[1].1, vec![w; 6]);
+    }
+
+    #[test]
+    fn encode_gif_survives_a_full_code_table() {
+        // Noise makes many codes, so the table of 4096 codes fills up and starts over.
+        let (width, height) = (128u16, 128u16);
+        let mut seed = 1u32;
+        let rgba: Vec<u8> = (0..width as usize * height as usize * 4)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (seed >> 16) as u8 & 0xc0
+            })
+            .collect();
+        let gif = encode_gif(width, height, &[(rgba.clone(), 100.0)]).unwrap();
+        let (_, _, decoded) = decode(&gif);
+        let expected: Vec<[u8; 3]> = rgba
+            .chunks_exact(4)
+            .map(|pixel| {
+                // 0x00, 0x40, 0x80 and 0xc0 map to the levels of the palette.
+                let level = |value: u8, levels: u32| {
+                    let level = (value as u32 * (levels - 1) + 127) / 255;
+                    (level * 255 / (levels - 1)) as u8
+                };
+                [level(pixel[0], 6), level(pixel[1], 7), level(pixel[2], 6)]
+            })
+            .collect();
+        assert_eq!(decoded[0].1, expected);
+    }
+
+    #[test]
+    fn encode_gif_rejects_frames_of_another_size() {
+        assert!(encode_gif(2, 2, &[(vec![0; 12], 10.0)]).is_err());
+        assert!(encode_gif(2, 2, &[]).is_ok());
+    }
+}
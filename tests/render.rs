@@ -0,0 +1,572 @@
+use anyhow::Result;
+
+use retrospector::app::App;
+
+use retrospector::render::batch::SpriteBatch;
+use retrospector::render::color::Color;
+use retrospector::render::pattern::draw_image_tiled;
+use retrospector::render::{
+    draw_arc, draw_dashed_line, draw_image, draw_image_in_viewport, draw_pie_slice, draw_polygon,
+    draw_sprite_outline, Batch, Cursor, CursorImage, Node, ParticleEmitter, ParticleLook, Position,
+    RenderPassId, Renderer, SceneGraph, Viewport,
+};
+use retrospector::test::{mock_sprite, DrawCommand, MockRenderer, TestHarness};
+use retrospector::update::KeyEvent;
+
+#[test]
+fn draw_image_tiled_cuts_the_edge_tiles() -> Result<()> {
+    let mock = MockRenderer::new(100.0, 100.0);
+    let sprite = mock_sprite(16.0, 0.0, 16.0, 16.0);
+    draw_image_tiled(
+        mock.renderer(),
+        &sprite,
+        Position::new(4.0, 8.0),
+        40.0,
+        20.0,
+    )?;
+    let commands = mock.take_commands();
+    assert_eq!(commands.len(), 6);
+    assert_eq!(
+        commands[2],
+        DrawCommand::Image {
+            sx: 16.0,
+            sy: 0.0,
+            sw: 8.0,
+            sh: 16.0,
+            dx: 36.0,
+            dy: 8.0,
+            dw: 8.0,
+            dh: 16.0,
+        }
+    );
+    assert_eq!(
+        commands[5],
+        DrawCommand::Image {
+            sx: 16.0,
+            sy: 0.0,
+            sw: 8.0,
+            sh: 4.0,
+            dx: 36.0,
+            dy: 24.0,
+            dw: 8.0,
+            dh: 4.0,
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn draw_image_tiled_skips_tiles_out_of_the_canvas() -> Result<()> {
+    let mock = MockRenderer::new(32.0, 32.0);
+    let sprite = mock_sprite(0.0, 0.0, 16.0, 16.0);
+    draw_image_tiled(
+        mock.renderer(),
+        &sprite,
+        Position::new(-32.0, 0.0),
+        320.0,
+        16.0,
+    )?;
+    assert_eq!(mock.take_commands().len(), 4);
+    Ok(())
+}
+
+#[test]
+fn sprite_batch_groups_draws_by_atlas_in_insertion_order() -> Result<()> {
+    let mock = MockRenderer::new(100.0, 100.0);
+    // Clones share the atlas, and each mock_sprite has its own atlas.
+    let a = mock_sprite(0.0, 0.0, 8.0, 8.0);
+    let b = mock_sprite(8.0, 0.0, 8.0, 8.0);
+    let (a2, b2) = (a.clone(), b.clone());
+    let mut batch = SpriteBatch::new();
+    batch.push(&a, Position::new(0.0, 0.0));
+    batch.push(&b, Position::new(10.0, 0.0));
+    batch.push(&a2, Position::new(20.0, 0.0));
+    batch.push(&b2, Position::new(30.0, 0.0));
+    batch.push(&a, Position::new(40.0, 0.0));
+    batch.push(&b, Position::new(50.0, 0.0));
+    batch.flush(mock.renderer())?;
+    assert!(batch.is_empty());
+    let draws: Vec<(f64, f64)> = mock
+        .take_commands()
+        .into_iter()
+        .map(|command| match command {
+            DrawCommand::Image { sx, dx, .. } => (sx, dx),
+            command => panic!("unexpected {:?}", command),
+        })
+        .collect();
+    // The atlases may come in either order, but the draws on each keep their order.
+    let expected_a = [(0.0, 0.0), (0.0, 20.0), (0.0, 40.0)];
+    let expected_b = [(8.0, 10.0), (8.0, 30.0), (8.0, 50.0)];
+    let (first, second) = draws.split_at(3);
+    if first[0].0 == 0.0 {
+        assert_eq!((first, second), (&expected_a[..], &expected_b[..]));
+    } else {
+        assert_eq!((first, second), (&expected_b[..], &expected_a[..]));
+    }
+    Ok(())
+}
+
+#[test]
+fn batch_flushes_draws_sorted_by_depth() -> Result<()> {
+    let mock = MockRenderer::new(100.0, 100.0);
+    let front = mock_sprite(0.0, 0.0, 16.0, 16.0);
+    let back = mock_sprite(16.0, 0.0, 16.0, 16.0);
+    let mut batch = Batch::new();
+    batch.push_sprite(40, &front, Position::new(0.0, 24.0));
+    batch.push_rect(
+        10,
+        Position::new(0.0, 0.0),
+        8.0,
+        8.0,
+        Color::from((0, 0, 0)),
+    );
+    batch.push_sprite(10, &back, Position::new(0.0, 0.0));
+    batch.flush(mock.renderer())?;
+    assert!(batch.is_empty());
+    let commands = mock.take_commands();
+    assert_eq!(commands.len(), 3);
+    assert!(matches!(commands[0], DrawCommand::FillRect { .. }));
+    assert!(matches!(commands[1], DrawCommand::Image { sx, .. } if sx == 16.0));
+    assert!(matches!(commands[2], DrawCommand::Image { sx, .. } if sx == 0.0));
+    Ok(())
+}
+
+#[test]
+fn draw_image_draws_sprites_touching_the_canvas_at_fractional_positions() -> Result<()> {
+    let mock = MockRenderer::new(100.0, 100.0);
+    let sprite = mock_sprite(0.0, 0.0, 16.0, 16.0);
+    draw_image(mock.renderer(), &sprite, Position::new(-15.5, 99.25))?;
+    draw_image(mock.renderer(), &sprite, Position::new(100.0, 0.0))?;
+    draw_image(mock.renderer(), &sprite, Position::new(-16.0, 100.0))?;
+    assert!(draw_image(mock.renderer(), &sprite, Position::new(100.5, 0.0)).is_err());
+    assert!(draw_image(mock.renderer(), &sprite, Position::new(0.0, -16.5)).is_err());
+    let commands = mock.take_commands();
+    assert!(matches!(commands[0], DrawCommand::Image { dx, dy, .. } if dx == -15.5 && dy == 99.25));
+    assert_eq!(
+        image_dxs(&commands),
+        [Some(-15.5), Some(100.0), Some(-16.0)]
+    );
+    Ok(())
+}
+
+#[test]
+fn draw_image_in_viewport_skips_the_sprites_which_draw_image_rejects() -> Result<()> {
+    let mock = MockRenderer::new(100.0, 100.0);
+    let sprite = mock_sprite(0.0, 0.0, 16.0, 16.0);
+    let viewport = Viewport::new(Position::new(50.0, 0.0), 100.0, 100.0);
+    for x in [33.5, 34.0, 150.0, 150.5] {
+        draw_image_in_viewport(mock.renderer(), &viewport, &sprite, Position::new(x, 0.0))?;
+    }
+    assert_eq!(image_dxs(&mock.take_commands()), [Some(-16.0), Some(100.0)]);
+    for x in [33.5, 150.5] {
+        assert!(draw_image(mock.renderer(), &sprite, Position::new(x - 50.0, 0.0)).is_err());
+    }
+    Ok(())
+}
+
+#[test]
+fn draw_sprite_outline_draws_the_sprite_unless_even_the_outline_is_out_of_canvas() -> Result<()> {
+    let mock = MockRenderer::new(100.0, 100.0);
+    let sprite = mock_sprite(0.0, 0.0, 16.0, 16.0);
+    draw_sprite_outline(
+        mock.renderer(),
+        &sprite,
+        Position::new(-17.0, 0.0),
+        "gold",
+        2,
+    )?;
+    assert!(draw_sprite_outline(
+        mock.renderer(),
+        &sprite,
+        Position::new(-18.5, 0.0),
+        "gold",
+        2
+    )
+    .is_err());
+    let commands = mock.take_commands();
+    assert_eq!(commands.len(), 1);
+    assert!(matches!(commands[0], DrawCommand::Image { dx, dy, .. } if dx == -17.0 && dy == 0.0));
+    Ok(())
+}
+
+// Layers draws a sprite at x = 0, 10 and 20 in the background, world and UI passes.
+struct Layers;
+
+impl App for Layers {
+    fn update(&mut self, _elapsed_time: f64, _key_event: &KeyEvent) -> Result<()> {
+        Ok(())
+    }
+
+    fn render(&self, _renderer: &Renderer) -> Result<()> {
+        unreachable!("render_pass is implemented")
+    }
+
+    fn render_passes(&self) -> &[RenderPassId] {
+        &[
+            RenderPassId::Background,
+            RenderPassId::World,
+            RenderPassId::Ui,
+        ]
+    }
+
+    fn render_pass(&self, renderer: &Renderer, pass: RenderPassId) -> Result<()> {
+        let dx = match pass {
+            RenderPassId::Background => 0.0,
+            RenderPassId::World => 10.0,
+            _ => 20.0,
+        };
+        draw_image(
+            renderer,
+            &mock_sprite(0.0, 0.0, 8.0, 8.0),
+            Position::new(dx, 0.0),
+        )
+    }
+
+    fn clear_before_pass(&self, pass: RenderPassId) -> bool {
+        pass == RenderPassId::Background
+    }
+}
+
+// Plain draws a sprite at x = 50 in render without passes.
+struct Plain;
+
+impl App for Plain {
+    fn update(&mut self, _elapsed_time: f64, _key_event: &KeyEvent) -> Result<()> {
+        Ok(())
+    }
+
+    fn render(&self, renderer: &Renderer) -> Result<()> {
+        draw_image(
+            renderer,
+            &mock_sprite(0.0, 0.0, 8.0, 8.0),
+            Position::new(50.0, 0.0),
+        )
+    }
+}
+
+fn image_dxs(commands: &[DrawCommand]) -> Vec<Option<f64>> {
+    commands
+        .iter()
+        .map(|command| match command {
+            DrawCommand::Image { dx, .. } => Some(*dx),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn render_passes_are_rendered_in_order_with_the_clears() -> Result<()> {
+    let mut harness = TestHarness::new(Layers, 100.0, 100.0);
+    harness.run_frames(1)?;
+    assert_eq!(harness.commands()[0], DrawCommand::Clear);
+    assert_eq!(
+        image_dxs(harness.commands()),
+        vec![None, Some(0.0), Some(10.0), Some(20.0)]
+    );
+    Ok(())
+}
+
+#[test]
+fn render_is_the_only_pass_by_default() -> Result<()> {
+    let mut harness = TestHarness::new(Plain, 100.0, 100.0);
+    harness.run_frames(1)?;
+    assert_eq!(image_dxs(harness.commands()), vec![Some(50.0)]);
+    Ok(())
+}
+
+fn assert_near(position: Position, dx: f64, dy: f64) {
+    assert!(
+        (position.dx() - dx).abs() < 1e-9 && (position.dy() - dy).abs() < 1e-9,
+        "{:?} should be ({}, {})",
+        position,
+        dx,
+        dy
+    );
+}
+
+#[test]
+fn world_position_accumulates_the_transforms_of_the_ancestors() -> Result<()> {
+    let mut graph = SceneGraph::new();
+    let hull = graph.add(
+        Node::new(Position::new(100.0, 50.0)).with_scale(2.0, 2.0),
+        None,
+    )?;
+    let turret = graph.add(
+        Node::new(Position::new(10.0, 0.0)).with_rotation(std::f64::consts::FRAC_PI_2),
+        Some(hull),
+    )?;
+    let barrel = graph.add(Node::new(Position::new(5.0, 0.0)), Some(turret))?;
+    assert_near(graph.world_position(hull).unwrap(), 100.0, 50.0);
+    assert_near(graph.world_position(turret).unwrap(), 120.0, 50.0);
+    // The turret faces down, so the barrel is below it, scaled by the hull.
+    assert_near(graph.world_position(barrel).unwrap(), 120.0, 60.0);
+    Ok(())
+}
+
+#[test]
+fn nodes_can_be_reparented_and_removed() -> Result<()> {
+    let mut graph = SceneGraph::new();
+    let player = graph.add(Node::new(Position::new(10.0, 10.0)), None)?;
+    let item = graph.add(Node::new(Position::new(50.0, 0.0)), None)?;
+    graph.set_parent(item, Some(player))?;
+    assert_eq!(graph.roots(), &[player]);
+    assert_eq!(graph.children(player), &[item]);
+    assert_near(graph.world_position(item).unwrap(), 60.0, 10.0);
+    assert!(graph.set_parent(player, Some(item)).is_err());
+
+    assert!(graph.remove(player).is_some());
+    assert!(graph.is_empty());
+    assert!(graph.node(item).is_none());
+    let reused = graph.add(Node::new(Position::new(0.0, 0.0)), None)?;
+    assert!(graph.node(player).is_none() && graph.node(reused).is_some());
+    assert!(graph
+        .add(Node::new(Position::new(0.0, 0.0)), Some(item))
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn scene_graph_draws_parents_first_and_siblings_by_depth() -> Result<()> {
+    let mock = MockRenderer::new(100.0, 100.0);
+    let mut graph = SceneGraph::new();
+    let tile =
+        |sx: f64| Node::new(Position::new(0.0, 0.0)).with_sprite(mock_sprite(sx, 0.0, 8.0, 8.0));
+    let parent = graph.add(tile(0.0).with_depth(1), None)?;
+    graph.add(tile(8.0).with_depth(2), Some(parent))?;
+    graph.add(tile(16.0).with_depth(-1), Some(parent))?;
+    graph.add(tile(24.0), None)?;
+    graph.draw(mock.renderer())?;
+    let sxs: Vec<f64> = mock
+        .take_commands()
+        .iter()
+        .filter_map(|command| match command {
+            DrawCommand::Image { sx, .. } => Some(*sx),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(sxs, vec![24.0, 0.0, 16.0, 8.0]);
+    Ok(())
+}
+
+fn sparks(max_particles: usize) -> ParticleEmitter {
+    let look = ParticleLook::Color {
+        color: Color::rgb(255, 200, 0),
+        size: 4.0,
+    };
+    ParticleEmitter::new(look, Position::new(50.0, 50.0), max_particles).seed(7)
+}
+
+#[test]
+fn particle_emitter_spawns_by_rate_up_to_the_cap_and_recycles_expired_particles() {
+    let mut emitter = sparks(10).rate(100.0).lifetime(250.0..250.0);
+    // 100 per second is 1.6 per frame of 16 ms, and the fraction is carried over.
+    emitter.update(16.0);
+    assert_eq!(emitter.live_count(), 1);
+    emitter.update(16.0);
+    assert_eq!(emitter.live_count(), 3);
+    for _ in 0..10 {
+        emitter.update(16.0);
+    }
+    assert_eq!(emitter.live_count(), 10, "the cap should not be exceeded");
+    assert_eq!(emitter.burst(5), 0);
+
+    emitter.set_rate(0.0);
+    emitter.update(200.0);
+    assert!(emitter.live_count() < 10);
+    assert!(emitter
+        .particles()
+        .iter()
+        .all(|particle| particle.age_ms() < particle.lifetime_ms()));
+    emitter.update(250.0);
+    assert_eq!(emitter.live_count(), 0);
+    assert_eq!(emitter.burst(20), 10, "expired particles should be reused");
+    assert_eq!(emitter.max_particles(), 10);
+}
+
+#[test]
+fn particles_move_under_gravity_and_fade_over_their_lifetime() {
+    let mut emitter = sparks(1)
+        .velocity(0.0, 0.0, 0.1..0.1)
+        .gravity(0.0, 0.001)
+        .lifetime(1000.0..1000.0)
+        .alpha(1.0, 0.0)
+        .scale(1.0, 3.0);
+    emitter.burst(1);
+    // The motion does not depend on the frame time.
+    emitter.update(100.0);
+    emitter.update(150.0);
+    emitter.update(250.0);
+    let particle = emitter.particles()[0];
+    assert_near(
+        particle.position(),
+        50.0 + 0.1 * 500.0,
+        50.0 + 0.5 * 0.001 * 500.0 * 500.0,
+    );
+    let (vx, vy) = particle.velocity();
+    assert!((vx - 0.1).abs() < 1e-9 && (vy - 0.5).abs() < 1e-9);
+    assert!((particle.alpha() - 0.5).abs() < 1e-9);
+    assert!((particle.scale() - 2.0).abs() < 1e-9);
+
+    let mock = MockRenderer::new(200.0, 200.0);
+    emitter.draw(mock.renderer()).unwrap();
+    match mock.take_commands().as_slice() {
+        [DrawCommand::FillRect { dw, color, .. }] => {
+            assert_eq!(*dw, 8.0);
+            assert_eq!(*color, Color::rgba(255, 200, 0, 128));
+        }
+        commands => panic!("unexpected commands: {:?}", commands),
+    }
+}
+
+#[test]
+fn particle_emitters_with_the_same_seed_spawn_the_same_particles() {
+    let emitter = || {
+        sparks(50)
+            .velocity(0.0, std::f64::consts::TAU, 0.05..0.2)
+            .lifetime(300.0..600.0)
+    };
+    let (mut a, mut b) = (emitter(), emitter());
+    a.burst(50);
+    b.burst(50);
+    for _ in 0..20 {
+        a.update(16.0);
+        b.update(16.0);
+    }
+    assert_eq!(a.particles(), b.particles());
+    let mut c = emitter().seed(8);
+    c.burst(50);
+    c.update(16.0);
+    assert_ne!(a.particles()[0].velocity(), c.particles()[0].velocity());
+}
+
+#[test]
+fn draw_dashed_line_is_recorded_with_its_pattern_and_rejects_invalid_ones() -> Result<()> {
+    let mock = MockRenderer::new(100.0, 100.0);
+    let (from, to) = (|| Position::new(10.0, 20.0), || Position::new(90.0, 20.0));
+    draw_dashed_line(mock.renderer(), from(), to(), 4.0, 2.0, Color::WHITE, 1.0)?;
+    assert_eq!(
+        mock.take_commands(),
+        vec![DrawCommand::DashedLine {
+            x1: 10.0,
+            y1: 20.0,
+            x2: 90.0,
+            y2: 20.0,
+            dash: 4.0,
+            gap: 2.0,
+            color: Color::WHITE,
+            width: 1.0,
+        }]
+    );
+    assert!(draw_dashed_line(mock.renderer(), from(), to(), 0.0, 2.0, Color::WHITE, 1.0).is_err());
+    assert!(draw_dashed_line(mock.renderer(), from(), to(), 4.0, -1.0, Color::WHITE, 1.0).is_err());
+    assert!(mock.take_commands().is_empty());
+    Ok(())
+}
+
+#[test]
+fn draw_polygon_records_the_points_and_needs_three_of_them() -> Result<()> {
+    let mock = MockRenderer::new(100.0, 100.0);
+    let triangle = [
+        Position::new(10.0, 10.0),
+        Position::new(30.0, 10.0),
+        Position::new(20.0, 25.0),
+    ];
+    draw_polygon(mock.renderer(), &triangle, Some(Color::WHITE), None, 1.0)?;
+    assert_eq!(
+        mock.take_commands(),
+        vec![DrawCommand::Polygon {
+            points: vec![(10.0, 10.0), (30.0, 10.0), (20.0, 25.0)],
+            fill_color: Some(Color::WHITE),
+            stroke_color: None,
+        }]
+    );
+    assert!(draw_polygon(
+        mock.renderer(),
+        &triangle[..2],
+        None,
+        Some(Color::BLACK),
+        1.0
+    )
+    .is_err());
+    assert!(mock.take_commands().is_empty());
+    Ok(())
+}
+
+#[test]
+fn set_cursor_keeps_the_cursor_on_a_headless_renderer() -> Result<()> {
+    let mock = MockRenderer::new(100.0, 100.0);
+    let renderer = mock.renderer();
+    assert!(matches!(renderer.cursor(), Cursor::Default));
+    renderer.set_cursor(Cursor::Hidden)?;
+    assert!(matches!(renderer.cursor(), Cursor::Hidden));
+    renderer.set_cursor(Cursor::Custom {
+        image: CursorImage::Sprite(mock_sprite(0.0, 0.0, 16.0, 16.0)),
+        hotspot: (8, 8),
+    })?;
+    assert!(matches!(
+        renderer.cursor(),
+        Cursor::Custom {
+            hotspot: (8, 8),
+            ..
+        }
+    ));
+    assert!(mock.take_commands().is_empty());
+    Ok(())
+}
+
+#[test]
+fn arcs_and_pie_slices_are_recorded_and_need_a_positive_radius() -> Result<()> {
+    let mock = MockRenderer::new(100.0, 100.0);
+    let center = || Position::new(50.0, 50.0);
+    draw_arc(mock.renderer(), center(), 20.0, 0.0, 1.5, Color::WHITE, 3.0)?;
+    draw_pie_slice(mock.renderer(), center(), 10.0, -1.0, 0.5, Color::BLACK)?;
+    assert_eq!(
+        mock.take_commands(),
+        vec![
+            DrawCommand::Arc {
+                dx: 50.0,
+                dy: 50.0,
+                radius: 20.0,
+                start_rad: 0.0,
+                end_rad: 1.5,
+                color: Color::WHITE,
+                filled: false,
+            },
+            DrawCommand::Arc {
+                dx: 50.0,
+                dy: 50.0,
+                radius: 10.0,
+                start_rad: -1.0,
+                end_rad: 0.5,
+                color: Color::BLACK,
+                filled: true,
+            },
+        ]
+    );
+    assert!(draw_arc(mock.renderer(), center(), 0.0, 0.0, 1.0, Color::WHITE, 1.0).is_err());
+    assert!(draw_pie_slice(mock.renderer(), center(), -1.0, 0.0, 1.0, Color::WHITE).is_err());
+    assert!(mock.take_commands().is_empty());
+    Ok(())
+}
+
+#[test]
+fn try_context_is_none_on_a_headless_renderer() {
+    let mock = MockRenderer::new(100.0, 100.0);
+    assert!(mock.renderer().try_context().is_none());
+}
+
+#[test]
+fn tile_indices_convert_both_ways_within_the_grid() {
+    let position = Position::new(40.0, 20.0);
+    assert_eq!(position.to_tile_index(16.0, 16.0, 5), Some(7));
+    assert_near(
+        Position::from_tile_index(7, 16.0, 16.0, 5).unwrap(),
+        32.0,
+        16.0,
+    );
+    assert_eq!(position.to_tile_index(16.0, 16.0, 2), None);
+    assert_eq!(Position::new(-1.0, 0.0).to_tile_index(16.0, 16.0, 5), None);
+    assert_eq!(position.to_tile_index(0.0, 16.0, 5), None);
+    assert_eq!(position.to_tile_index(16.0, 16.0, 0), None);
+    assert!(Position::from_tile_index(7, 16.0, 16.0, 0).is_none());
+}
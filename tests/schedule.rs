@@ -0,0 +1,115 @@
+use retrospector::schedule::Scheduler;
+
+#[test]
+fn after_runs_once_when_due() {
+    let mut scheduler = Scheduler::new();
+    scheduler.after(100.0, |log: &mut Vec<&str>| log.push("after"));
+    let mut log = vec![];
+    scheduler.update(99.0, &mut log);
+    assert!(log.is_empty());
+    scheduler.update(1.0, &mut log);
+    scheduler.update(1000.0, &mut log);
+    assert_eq!(log, vec!["after"]);
+    assert!(scheduler.is_empty());
+}
+
+#[test]
+fn every_catches_up_when_dt_spans_multiple_intervals() {
+    let mut scheduler = Scheduler::new();
+    scheduler
+        .every(10.0, |count: &mut u32| *count += 1)
+        .unwrap();
+    let mut count = 0;
+    scheduler.update(35.0, &mut count);
+    assert_eq!(count, 3);
+    scheduler.update(5.0, &mut count);
+    assert_eq!(count, 4);
+}
+
+#[test]
+fn every_rejects_a_non_positive_interval() {
+    let mut scheduler = Scheduler::new();
+    assert!(scheduler.every(0.0, |_: &mut ()| {}).is_err());
+    assert!(scheduler.every(f64::NAN, |_: &mut ()| {}).is_err());
+}
+
+#[test]
+fn actions_run_in_time_order_within_a_single_update() {
+    let mut scheduler = Scheduler::new();
+    scheduler.after(30.0, |log: &mut Vec<u32>| log.push(30));
+    scheduler
+        .every(20.0, |log: &mut Vec<u32>| log.push(20))
+        .unwrap();
+    scheduler.after(10.0, |log: &mut Vec<u32>| log.push(10));
+    let mut log = vec![];
+    scheduler.update(40.0, &mut log);
+    assert_eq!(log, vec![10, 20, 30, 20]);
+}
+
+#[test]
+fn cancel_stops_pending_actions() {
+    let mut scheduler = Scheduler::new();
+    let handle = scheduler
+        .every(10.0, |count: &mut u32| *count += 1)
+        .unwrap();
+    let mut count = 0;
+    scheduler.update(20.0, &mut count);
+    handle.cancel();
+    scheduler.update(20.0, &mut count);
+    assert_eq!(count, 2);
+    assert!(handle.is_cancelled());
+    assert!(scheduler.is_empty());
+}
+
+struct Context {
+    count: u32,
+    handle: Option<retrospector::schedule::TaskHandle>,
+}
+
+#[test]
+fn cancel_during_update_skips_the_remaining_catch_up() {
+    let mut scheduler = Scheduler::new();
+    let handle = scheduler
+        .every(10.0, |ctx: &mut Context| {
+            ctx.count += 1;
+            if ctx.count == 2 {
+                ctx.handle.as_ref().unwrap().cancel();
+            }
+        })
+        .unwrap();
+    let mut ctx = Context {
+        count: 0,
+        handle: Some(handle),
+    };
+    scheduler.update(100.0, &mut ctx);
+    assert_eq!(ctx.count, 2);
+}
+
+#[test]
+fn sequence_runs_steps_after_the_previous_ones() {
+    let mut scheduler = Scheduler::new();
+    scheduler.sequence(&[
+        (10.0, |log: &mut Vec<u32>| log.push(1)),
+        (10.0, |log: &mut Vec<u32>| log.push(2)),
+        (0.0, |log: &mut Vec<u32>| log.push(3)),
+    ]);
+    let mut log = vec![];
+    scheduler.update(15.0, &mut log);
+    assert_eq!(log, vec![1]);
+    scheduler.update(5.0, &mut log);
+    assert_eq!(log, vec![1, 2, 3]);
+}
+
+#[test]
+fn cancel_stops_the_remaining_steps_of_a_sequence() {
+    let mut scheduler = Scheduler::new();
+    let handle = scheduler.sequence(&[
+        (10.0, |log: &mut Vec<u32>| log.push(1)),
+        (10.0, |log: &mut Vec<u32>| log.push(2)),
+    ]);
+    let mut log = vec![];
+    scheduler.update(10.0, &mut log);
+    handle.cancel();
+    scheduler.update(100.0, &mut log);
+    assert_eq!(log, vec![1]);
+}
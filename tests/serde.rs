@@ -0,0 +1,110 @@
+#![cfg(feature = "serde")]
+
+use std::fmt::Debug;
+
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use retrospector::app::{AppConfig, ErrorPolicy, LoopDriver};
+use retrospector::collision::{Circle, Rect};
+use retrospector::render::color::Color;
+use retrospector::render::{Grid, Position, Transform, Viewport};
+use retrospector::tween::{Easing, Tween};
+
+// round_trip serializes value to JSON and back. Not all the types are PartialEq, so the Debug
+// outputs are compared.
+fn round_trip<T: Serialize + DeserializeOwned + Debug>(value: &T) -> Result<T> {
+    let json = serde_json::to_string(value)?;
+    let restored: T = serde_json::from_str(&json)?;
+    assert_eq!(
+        format!("{:?}", restored),
+        format!("{:?}", value),
+        "{}",
+        json
+    );
+    Ok(restored)
+}
+
+#[test]
+fn geometry_round_trips() -> Result<()> {
+    round_trip(&Position::new(1.5, -2.0))?;
+    round_trip(&Rect::new(1.0, 2.0, 3.0, 4.0)?)?;
+    round_trip(&Circle::new(1.0, 2.0, 3.0)?)?;
+    round_trip(&Color::rgba(12, 34, 56, 78))?;
+    Ok(())
+}
+
+#[test]
+fn invalid_rects_circles_and_grids_are_rejected() {
+    assert!(
+        serde_json::from_str::<Rect>(r#"{"x":0.0,"y":0.0,"width":-1.0,"height":1.0}"#).is_err()
+    );
+    assert!(serde_json::from_str::<Circle>(r#"{"x":0.0,"y":0.0,"radius":-1.0}"#).is_err());
+    assert!(serde_json::from_str::<Grid<u8>>(r#"{"cells":[1,2,3],"cols":2,"rows":2}"#).is_err());
+}
+
+#[test]
+fn camera_round_trips() -> Result<()> {
+    let viewport = round_trip(&Viewport::new(Position::new(64.0, 32.0), 320.0, 180.0))?;
+    assert_eq!(viewport.world_position().dx(), 64.0);
+    round_trip(&Transform::new(Position::new(8.0, 8.0), 0.5, 2.0, 2.0))?;
+    Ok(())
+}
+
+#[test]
+fn grid_round_trips() -> Result<()> {
+    let mut grid = Grid::new(3, 2, None);
+    grid.set(2, 1, Some(7usize))?;
+    let restored = round_trip(&grid)?;
+    assert_eq!(restored, grid);
+    assert_eq!(restored.get(2, 1), Some(&Some(7)));
+    Ok(())
+}
+
+#[test]
+fn tween_round_trips() -> Result<()> {
+    round_trip(&Easing::ElasticOut)?;
+    let mut tween = Tween::new(0.0, 10.0, 100.0, Easing::QuadIn).then(0.0, 50.0, Easing::Linear);
+    tween.update(120.0);
+    let restored = round_trip(&tween)?;
+    assert_eq!(restored.value(), tween.value());
+    Ok(())
+}
+
+#[test]
+fn app_config_round_trips_without_the_canvas() -> Result<()> {
+    let config = AppConfig::builder("canvas")
+        .size(320.0, 180.0)
+        .pixel_art(true)
+        .background(Color::rgba(0, 0, 0, 255))
+        .title("retro")
+        .error_policy(ErrorPolicy::Pause)
+        .loop_driver(LoopDriver::Timer { interval_ms: 16.0 })
+        .build()?;
+    round_trip(&config)?;
+    let json = serde_json::to_string(&config)?;
+    assert!(!json.contains("canvas\""), "{}", json);
+    Ok(())
+}
+
+#[test]
+fn invalid_app_configs_are_rejected() -> Result<()> {
+    let json = serde_json::to_string(&AppConfig::builder("canvas").build()?)?;
+    for (valid, invalid) in [
+        (r#""canvas_width":300.0"#, r#""canvas_width":0.0"#),
+        (r#""canvas_height":150.0"#, r#""canvas_height":-1.0"#),
+        (r#""max_fps":null"#, r#""max_fps":0.0"#),
+        (r#""render_every":1"#, r#""render_every":0"#),
+    ] {
+        assert!(json.contains(valid), "{}", json);
+        let invalid = json.replace(valid, invalid);
+        assert!(
+            serde_json::from_str::<AppConfig>(&invalid).is_err(),
+            "{}",
+            invalid
+        );
+    }
+    assert!(serde_json::from_str::<AppConfig>(&json).is_ok());
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use retrospector::storage::HighScores;
+
+fn names(high_scores: &HighScores<u32>) -> Vec<&str> {
+    high_scores
+        .entries()
+        .iter()
+        .map(|entry| entry.name())
+        .collect()
+}
+
+#[test]
+fn submit_keeps_scores_sorted_from_the_highest() {
+    let mut high_scores = HighScores::new(10);
+    assert_eq!(high_scores.submit("b", 200), Some(1));
+    assert_eq!(high_scores.submit("c", 100), Some(2));
+    assert_eq!(high_scores.submit("a", 300), Some(1));
+    assert_eq!(names(&high_scores), vec!["a", "b", "c"]);
+}
+
+#[test]
+fn submit_ranks_earlier_submissions_higher_on_ties() {
+    let mut high_scores = HighScores::new(10);
+    high_scores.submit("first", 100);
+    assert_eq!(high_scores.submit("second", 100), Some(2));
+    assert_eq!(names(&high_scores), vec!["first", "second"]);
+}
+
+#[test]
+fn submit_truncates_to_the_capacity() {
+    let mut high_scores = HighScores::new(2);
+    high_scores.submit("a", 300);
+    high_scores.submit("b", 200);
+    assert!(!high_scores.qualifies(&200));
+    assert_eq!(high_scores.submit("c", 200), None);
+    assert_eq!(high_scores.submit("d", 250), Some(2));
+    assert_eq!(names(&high_scores), vec!["a", "d"]);
+}
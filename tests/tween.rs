@@ -0,0 +1,116 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use retrospector::render::Position;
+use retrospector::tween::{Easing, Tween, Tweens};
+
+const EASINGS: [Easing; 11] = [
+    Easing::Linear,
+    Easing::QuadIn,
+    Easing::QuadOut,
+    Easing::QuadInOut,
+    Easing::CubicIn,
+    Easing::CubicOut,
+    Easing::CubicInOut,
+    Easing::ElasticIn,
+    Easing::ElasticOut,
+    Easing::BounceIn,
+    Easing::BounceOut,
+];
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-9,
+        "actual: {}, expected: {}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn easings_start_at_0_and_end_at_1() {
+    for easing in EASINGS {
+        assert_close(easing.apply(0.0), 0.0);
+        assert_close(easing.apply(1.0), 1.0);
+        assert_close(easing.apply(-1.0), 0.0);
+        assert_close(easing.apply(2.0), 1.0);
+    }
+}
+
+#[test]
+fn easings_have_their_shapes() {
+    assert_close(Easing::Linear.apply(0.25), 0.25);
+    assert_close(Easing::QuadIn.apply(0.5), 0.25);
+    assert_close(Easing::QuadOut.apply(0.5), 0.75);
+    assert_close(Easing::QuadInOut.apply(0.5), 0.5);
+    assert_close(Easing::CubicInOut.apply(0.25), 0.0625);
+    assert!(Easing::ElasticOut.apply(0.2) > 1.0);
+    assert!(Easing::ElasticIn.apply(0.8) < 0.0);
+    assert_close(Easing::BounceOut.apply(1.0 / 2.75), 1.0);
+}
+
+#[test]
+fn tween_updates_and_finishes() {
+    let mut tween = Tween::new(10.0, 20.0, 100.0, Easing::Linear);
+    assert_close(tween.value(), 10.0);
+    assert_close(tween.update(25.0), 12.5);
+    assert!(!tween.is_finished());
+    assert_close(tween.update(1000.0), 20.0);
+    assert!(tween.is_finished());
+    tween.reset();
+    assert_close(tween.value(), 10.0);
+}
+
+#[test]
+fn then_chains_segments_from_the_previous_end() {
+    let mut tween = Tween::new(
+        Position::new(0.0, 0.0),
+        Position::new(10.0, 0.0),
+        100.0,
+        Easing::Linear,
+    )
+    .then(Position::new(10.0, 10.0), 0.0, Easing::Linear)
+    .then(Position::new(10.0, 30.0), 200.0, Easing::Linear);
+    assert_close(tween.duration_ms(), 300.0);
+    let position = tween.update(50.0);
+    assert_close(position.dx(), 5.0);
+    let position = tween.update(150.0);
+    assert_close(position.dx(), 10.0);
+    assert_close(position.dy(), 20.0);
+    let position = tween.update(100.0);
+    assert_close(position.dy(), 30.0);
+    assert!(tween.is_finished());
+}
+
+#[test]
+fn tweens_apply_values_and_fire_on_complete() {
+    let completed = Rc::new(RefCell::new(vec![]));
+    let mut tweens = Tweens::new();
+    let short = Rc::clone(&completed);
+    tweens.add_with_complete(
+        Tween::new(0.0, 1.0, 100.0, Easing::Linear),
+        |values: &mut Vec<f64>, value| values.push(value),
+        move |_| short.borrow_mut().push("short"),
+    );
+    let long = Rc::clone(&completed);
+    tweens.add_with_complete(
+        Tween::new(0.0, 10.0, 200.0, Easing::Linear),
+        |values: &mut Vec<f64>, value| values.push(value),
+        move |_| long.borrow_mut().push("long"),
+    );
+    let cancelled = tweens.add(
+        Tween::new(0.0, -1.0, 100.0, Easing::Linear),
+        |values, value| values.push(value),
+    );
+    cancelled.cancel();
+    assert_eq!(tweens.len(), 2);
+
+    let mut values = vec![];
+    tweens.update(100.0, &mut values);
+    assert_eq!(values, vec![1.0, 5.0]);
+    assert_eq!(*completed.borrow(), vec!["short"]);
+    tweens.update(100.0, &mut values);
+    assert_eq!(values, vec![1.0, 5.0, 10.0]);
+    assert_eq!(*completed.borrow(), vec!["short", "long"]);
+    assert!(tweens.is_empty());
+}
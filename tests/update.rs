@@ -0,0 +1,304 @@
+use anyhow::Result;
+use web_sys::KeyEvent as Dom;
+
+use retrospector::app::App;
+use retrospector::render::Renderer;
+use retrospector::test::TestHarness;
+use retrospector::update::{
+    InputPlayer, InputRecorder, Key, KeyEvent, KeyEventSnapshot, KeySequenceDetector,
+};
+
+struct Combo {
+    detector: KeySequenceDetector,
+    last_time: f64,
+    triggered: u32,
+}
+
+impl Combo {
+    fn new(window_ms: f64) -> Self {
+        Self {
+            detector: KeySequenceDetector::new(
+                vec![Key::ArrowDown, Key::ArrowRight, Key::KeyZ],
+                window_ms,
+            ),
+            last_time: 0.0,
+            triggered: 0,
+        }
+    }
+}
+
+impl App for Combo {
+    fn update(&mut self, elapsed_time: f64, key_event: &KeyEvent) -> Result<()> {
+        self.detector
+            .update(key_event, elapsed_time - self.last_time);
+        self.last_time = elapsed_time;
+        if self.detector.is_triggered() {
+            self.triggered += 1;
+        }
+        Ok(())
+    }
+
+    fn render(&self, _renderer: &Renderer) -> Result<()> {
+        Ok(())
+    }
+}
+
+fn press(harness: &mut TestHarness<Combo>, key_code: u32) -> Result<()> {
+    harness.key_down(key_code);
+    harness.run_frames(1)?;
+    harness.key_up(key_code);
+    harness.run_frames(1)
+}
+
+#[test]
+fn sequence_triggers_once_within_the_window() -> Result<()> {
+    let mut harness = TestHarness::new(Combo::new(500.0), 100.0, 100.0);
+    press(&mut harness, Dom::DOM_VK_DOWN)?;
+    press(&mut harness, Dom::DOM_VK_RIGHT)?;
+    press(&mut harness, Dom::DOM_VK_Z)?;
+    assert_eq!(harness.app().triggered, 1);
+    harness.run_frames(10)?;
+    assert_eq!(harness.app().triggered, 1);
+    Ok(())
+}
+
+#[test]
+fn held_keys_are_counted_once() -> Result<()> {
+    let mut harness = TestHarness::new(Combo::new(500.0), 100.0, 100.0);
+    harness.key_down(Dom::DOM_VK_DOWN);
+    harness.run_frames(3)?;
+    harness.key_down(Dom::DOM_VK_RIGHT);
+    harness.run_frames(3)?;
+    press(&mut harness, Dom::DOM_VK_Z)?;
+    assert_eq!(harness.app().triggered, 1);
+    Ok(())
+}
+
+#[test]
+fn sequence_is_not_triggered_out_of_the_window_or_order() -> Result<()> {
+    let mut harness = TestHarness::new(Combo::new(100.0), 100.0, 100.0);
+    press(&mut harness, Dom::DOM_VK_DOWN)?;
+    press(&mut harness, Dom::DOM_VK_RIGHT)?;
+    harness.run_frames(10)?;
+    press(&mut harness, Dom::DOM_VK_Z)?;
+    assert_eq!(harness.app().triggered, 0);
+
+    press(&mut harness, Dom::DOM_VK_RIGHT)?;
+    press(&mut harness, Dom::DOM_VK_DOWN)?;
+    press(&mut harness, Dom::DOM_VK_Z)?;
+    assert_eq!(harness.app().triggered, 0);
+    Ok(())
+}
+
+struct KeysDown {
+    keys: Vec<Key>,
+}
+
+impl App for KeysDown {
+    fn update(&mut self, _elapsed_time: f64, key_event: &KeyEvent) -> Result<()> {
+        self.keys = key_event.keys_down().collect();
+        Ok(())
+    }
+
+    fn render(&self, _renderer: &Renderer) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn keys_down_yields_only_the_pressed_keys() -> Result<()> {
+    let mut harness = TestHarness::new(KeysDown { keys: vec![] }, 100.0, 100.0);
+    harness.run_frames(1)?;
+    assert!(harness.app().keys.is_empty());
+    harness.key_down(Dom::DOM_VK_Z);
+    harness.key_down(Dom::DOM_VK_LEFT);
+    harness.run_frames(1)?;
+    assert_eq!(harness.app().keys, vec![Key::ArrowLeft, Key::KeyZ]);
+    harness.key_up(Dom::DOM_VK_LEFT);
+    harness.run_frames(1)?;
+    assert_eq!(harness.app().keys, vec![Key::KeyZ]);
+    Ok(())
+}
+
+// Edges records the keys pressed and released since the snapshot of the previous frame.
+#[derive(Default)]
+struct Edges {
+    previous: KeyEventSnapshot,
+    pressed: Vec<Key>,
+    released: bool,
+}
+
+impl App for Edges {
+    fn update(&mut self, _elapsed_time: f64, key_event: &KeyEvent) -> Result<()> {
+        self.pressed = key_event.keys_pressed_since(&self.previous).collect();
+        self.released = key_event.just_released(&self.previous, Key::Enter);
+        self.previous = key_event.snapshot();
+        Ok(())
+    }
+
+    fn render(&self, _renderer: &Renderer) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn snapshots_detect_the_keys_just_pressed_and_released() -> Result<()> {
+    let mut harness = TestHarness::new(Edges::default(), 100.0, 100.0);
+    harness.key_down(Dom::DOM_VK_RETURN);
+    harness.key_down(Dom::DOM_VK_A);
+    harness.run_frames(1)?;
+    assert_eq!(harness.app().pressed, vec![Key::Enter, Key::KeyA]);
+    let snapshot = harness.app().previous;
+    assert!(snapshot.is_down(Key::Enter) && !snapshot.is_down(Key::KeyZ));
+    assert_eq!(
+        snapshot.keys_down().collect::<Vec<_>>(),
+        vec![Key::Enter, Key::KeyA]
+    );
+
+    harness.run_frames(1)?;
+    assert!(
+        harness.app().pressed.is_empty(),
+        "held keys are not pressed again"
+    );
+    harness.key_up(Dom::DOM_VK_RETURN);
+    harness.key_down(Dom::DOM_VK_Z);
+    harness.run_frames(1)?;
+    assert_eq!(harness.app().pressed, vec![Key::KeyZ]);
+    assert!(harness.app().released);
+    harness.run_frames(1)?;
+    assert!(!harness.app().released);
+    Ok(())
+}
+
+// Recording records every frame into an InputRecorder and keeps the KeyEvents to compare.
+#[derive(Default)]
+struct Recording {
+    recorder: InputRecorder,
+    key_events: Vec<KeyEvent>,
+}
+
+impl App for Recording {
+    fn update(&mut self, _elapsed_time: f64, key_event: &KeyEvent) -> Result<()> {
+        let frame = self.key_events.len() as u64 * 2;
+        self.recorder.record(key_event, frame);
+        self.key_events.push(key_event.clone());
+        Ok(())
+    }
+
+    fn render(&self, _renderer: &Renderer) -> Result<()> {
+        Ok(())
+    }
+}
+
+fn record_session() -> Result<Recording> {
+    let mut harness = TestHarness::new(Recording::default(), 100.0, 100.0);
+    harness.run_frames(1)?;
+    harness.key_down(Dom::DOM_VK_LEFT);
+    harness.key_down(Dom::DOM_VK_Z);
+    harness.run_frames(2)?;
+    harness.key_up(Dom::DOM_VK_LEFT);
+    harness.key_down(Dom::DOM_VK_RETURN);
+    harness.run_frames(1)?;
+    Ok(std::mem::take(harness.app_mut()))
+}
+
+fn assert_replays(mut player: InputPlayer, recording: &Recording) {
+    for (i, key_event) in recording.key_events.iter().enumerate() {
+        assert_eq!(player.frame(), Some(i as u64 * 2));
+        assert_eq!(player.next_frame().as_ref(), Some(key_event));
+    }
+    assert_eq!(player.frame(), None);
+    assert!(player.next_frame().is_none());
+}
+
+#[test]
+fn input_recording_round_trips_through_bytes() -> Result<()> {
+    let recording = record_session()?;
+    assert_eq!(recording.recorder.len(), 4);
+    let data = recording.recorder.serialize();
+    assert_eq!(data.len(), 5 + 4 * 16);
+    let mut player = InputPlayer::deserialize(&data)?;
+    assert_replays(InputPlayer::deserialize(&data)?, &recording);
+    player.next_frame();
+    player.rewind();
+    assert_replays(player, &recording);
+    Ok(())
+}
+
+#[test]
+fn input_player_rejects_truncated_or_foreign_data() -> Result<()> {
+    let data = record_session()?.recorder.serialize();
+    assert!(InputPlayer::deserialize(&data[..data.len() - 1]).is_err());
+    assert!(InputPlayer::deserialize(&data[..data.len() - 16 + 8]).is_err());
+    assert!(InputPlayer::deserialize(&data[..4]).is_err());
+    assert!(InputPlayer::deserialize(&[]).is_err());
+    let mut other_version = data.clone();
+    other_version[4] += 1;
+    assert!(InputPlayer::deserialize(&other_version).is_err());
+    // A recording without frames is valid.
+    let mut empty = InputPlayer::deserialize(&data[..5])?;
+    assert!(empty.next_frame().is_none());
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn input_recording_round_trips_through_json() -> Result<()> {
+    let recording = record_session()?;
+    let json = recording.recorder.to_json()?;
+    assert!(
+        json.contains(r#"{"frame":2,"keys":["ArrowLeft","KeyZ"]}"#),
+        "{}",
+        json
+    );
+    assert_replays(InputPlayer::from_json(&json)?, &recording);
+    assert!(InputPlayer::from_json(&json[..json.len() - 1]).is_err());
+    Ok(())
+}
+
+// Chord records whether Control+Z and Control+Shift+Z are down in every update.
+#[derive(Default)]
+struct Chord {
+    undo: bool,
+    redo: bool,
+}
+
+impl App for Chord {
+    fn update(&mut self, _elapsed_time: f64, key_event: &KeyEvent) -> Result<()> {
+        self.undo = key_event.chord_down(&[Key::Control, Key::KeyZ]);
+        self.redo = key_event.chord_down(&[Key::Control, Key::Shift, Key::KeyZ]);
+        Ok(())
+    }
+
+    fn render(&self, _renderer: &Renderer) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn chord_down_needs_all_the_keys_including_modifiers() -> Result<()> {
+    let mut harness = TestHarness::new(Chord::default(), 100.0, 100.0);
+    harness.key_down(Dom::DOM_VK_Z);
+    harness.run_frames(1)?;
+    assert!(!harness.app().undo && !harness.app().redo);
+    harness.key_down(Dom::DOM_VK_CONTROL);
+    harness.run_frames(1)?;
+    assert!(harness.app().undo && !harness.app().redo);
+    harness.key_down(Dom::DOM_VK_SHIFT);
+    harness.run_frames(1)?;
+    assert!(harness.app().undo && harness.app().redo);
+    harness.key_up(Dom::DOM_VK_CONTROL);
+    harness.run_frames(1)?;
+    assert!(!harness.app().undo && !harness.app().redo);
+    Ok(())
+}
+
+#[test]
+fn modifier_keys_are_kept_in_snapshots() -> Result<()> {
+    let mut harness = TestHarness::new(Edges::default(), 100.0, 100.0);
+    harness.key_down(Dom::DOM_VK_ALT);
+    harness.key_down(Dom::DOM_VK_SHIFT);
+    harness.run_frames(1)?;
+    assert_eq!(harness.app().pressed, vec![Key::Shift, Key::Alt]);
+    Ok(())
+}